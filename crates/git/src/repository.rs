@@ -36,6 +36,10 @@ pub trait GitRepository: Send + Sync {
     /// Returns the SHA of the current HEAD.
     fn head_sha(&self) -> Option<String>;
 
+    /// Returns the full SHA of the commit that `sha` names or abbreviates, if the
+    /// repository has one.
+    fn resolve_commit(&self, sha: &str) -> Option<String>;
+
     fn status(&self, path_prefixes: &[PathBuf]) -> Result<GitStatus>;
 
     fn branches(&self) -> Result<Vec<Branch>>;
@@ -122,6 +126,12 @@ impl GitRepository for RealGitRepository {
         Some(self.repository.lock().head().ok()?.target()?.to_string())
     }
 
+    fn resolve_commit(&self, sha: &str) -> Option<String> {
+        let repo = self.repository.lock();
+        let commit = repo.find_commit_by_prefix(sha).ok()?;
+        Some(commit.id().to_string())
+    }
+
     fn status(&self, path_prefixes: &[PathBuf]) -> Result<GitStatus> {
         let working_directory = self
             .repository
@@ -241,6 +251,10 @@ impl GitRepository for FakeGitRepository {
         None
     }
 
+    fn resolve_commit(&self, _sha: &str) -> Option<String> {
+        None
+    }
+
     fn status(&self, path_prefixes: &[PathBuf]) -> Result<GitStatus> {
         let state = self.state.lock();
         let mut entries = state
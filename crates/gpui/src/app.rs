@@ -563,6 +563,18 @@ impl AppContext {
         self.platform.window_appearance()
     }
 
+    /// Plays the platform's alert sound.
+    pub fn beep(&self) {
+        self.platform.beep()
+    }
+
+    /// Shows a notification from the application in the system's notification area.
+    /// Systems may keep it to themselves, such as while the application is active
+    /// or when the user turned notifications off.
+    pub fn show_notification(&self, title: &str, body: &str) {
+        self.platform.show_notification(title, body)
+    }
+
     /// Writes data to the primary selection buffer.
     /// Only available on Linux.
     #[cfg(target_os = "linux")]
@@ -87,6 +87,8 @@ impl LinuxClient for HeadlessClient {
 
     fn reveal_path(&self, _path: std::path::PathBuf) {}
 
+    fn beep(&self) {}
+
     fn write_to_primary(&self, _item: crate::ClipboardItem) {}
 
     fn write_to_clipboard(&self, _item: crate::ClipboardItem) {}
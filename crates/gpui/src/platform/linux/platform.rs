@@ -14,12 +14,16 @@ use std::{
     path::{Path, PathBuf},
     process::Command,
     rc::Rc,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
 use anyhow::anyhow;
 use ashpd::desktop::file_chooser::{OpenFileRequest, SaveFileRequest};
+use ashpd::desktop::notification::{Notification, NotificationProxy};
 use ashpd::desktop::open_uri::{OpenDirectoryRequest, OpenFileRequest as OpenUriRequest};
 use ashpd::{url, ActivationToken};
 use async_task::Runnable;
@@ -71,6 +75,7 @@ pub trait LinuxClient {
     fn set_cursor_style(&self, style: CursorStyle);
     fn open_uri(&self, uri: &str);
     fn reveal_path(&self, path: PathBuf);
+    fn beep(&self);
     fn write_to_primary(&self, item: ClipboardItem);
     fn write_to_clipboard(&self, item: ClipboardItem);
     fn read_from_primary(&self) -> Option<ClipboardItem>;
@@ -351,6 +356,27 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.reveal_path(path.to_owned());
     }
 
+    fn beep(&self) {
+        LinuxClient::beep(self);
+    }
+
+    fn show_notification(&self, title: &str, body: &str) {
+        static NEXT_NOTIFICATION_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_NOTIFICATION_ID.fetch_add(1, Ordering::Relaxed);
+        let notification = Notification::new(title).body(body);
+        self.foreground_executor()
+            .spawn(async move {
+                let result = async {
+                    let proxy = NotificationProxy::new().await?;
+                    proxy
+                        .add_notification(&format!("notification-{id}"), notification)
+                        .await
+                };
+                result.await.log_err();
+            })
+            .detach();
+    }
+
     fn on_quit(&self, callback: Box<dyn FnMut()>) {
         self.with_common(|common| {
             common.callbacks.quit = Some(callback);
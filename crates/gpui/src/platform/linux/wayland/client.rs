@@ -676,6 +676,10 @@ impl LinuxClient for WaylandClient {
         }
     }
 
+    fn beep(&self) {
+        log::info!("beep is not implemented on Wayland, ignoring the call")
+    }
+
     fn reveal_path(&self, path: PathBuf) {
         let mut state = self.0.borrow_mut();
         if let (Some(activation), Some(window)) = (
@@ -769,6 +769,11 @@ impl PlatformWindow for WaylandWindow {
         self.borrow().maximized
     }
 
+    fn is_minimized(&self) -> bool {
+        // xdg-shell has no way for the compositor to tell a window it was minimized.
+        false
+    }
+
     fn window_bounds(&self) -> WindowBounds {
         let state = self.borrow();
         if state.fullscreen {
@@ -106,6 +106,9 @@ pub struct X11ClientState {
     pub(crate) event_loop: Option<calloop::EventLoop<'static, X11Client>>,
 
     pub(crate) last_click: Instant,
+    /// The server time of the last key or button press, which window managers
+    /// use to tell requests the user made from ones that would steal focus.
+    pub(crate) last_user_time: xproto::Timestamp,
     pub(crate) last_location: Point<Pixels>,
     pub(crate) current_count: usize,
 
@@ -389,6 +392,7 @@ impl X11Client {
             loop_handle: handle,
             common,
             last_click: Instant::now(),
+            last_user_time: x11rb::CURRENT_TIME,
             last_location: Point::new(px(0.0), px(0.0)),
             current_count: 0,
             scale_factor,
@@ -716,6 +720,7 @@ impl X11Client {
             Event::KeyPress(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_user_time = event.time;
 
                 let modifiers = modifiers_from_state(event.state);
                 state.modifiers = modifiers;
@@ -812,6 +817,7 @@ impl X11Client {
             Event::XinputButtonPress(event) => {
                 let window = self.get_window(event.event)?;
                 let mut state = self.0.borrow_mut();
+                state.last_user_time = event.time;
 
                 let modifiers = modifiers_from_xinput_info(event.mods);
                 state.modifiers = modifiers;
@@ -1292,6 +1298,12 @@ impl LinuxClient for X11Client {
         reveal_path_internal(self.background_executor(), path, None);
     }
 
+    fn beep(&self) {
+        let state = self.0.borrow();
+        state.xcb_connection.bell(0).log_err();
+        state.xcb_connection.flush().log_err();
+    }
+
     fn write_to_primary(&self, item: crate::ClipboardItem) {
         let state = self.0.borrow_mut();
         state
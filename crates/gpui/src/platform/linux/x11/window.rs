@@ -585,9 +585,8 @@ impl Drop for X11Window {
 }
 
 enum WmHintPropertyState {
-    // Remove = 0,
-    // Add = 1,
-    Toggle = 2,
+    Remove = 0,
+    Add = 1,
 }
 
 impl X11Window {
@@ -722,9 +721,18 @@ impl X11WindowStatePtr {
     }
 
     pub fn property_notify(&self, event: xproto::PropertyNotifyEvent) {
-        let mut state = self.state.borrow_mut();
+        let state = self.state.borrow_mut();
         if event.atom == state.atoms._NET_WM_STATE {
+            let was_fullscreen = state.fullscreen;
             self.set_wm_properties(state);
+            // Entering or leaving fullscreen doesn't always change the window's bounds,
+            // so it is reported as a move, which notifies the window's fullscreen
+            // observers when the state changed.
+            if self.state.borrow().fullscreen != was_fullscreen {
+                if let Some(ref mut fun) = self.callbacks.borrow_mut().moved {
+                    fun()
+                }
+            }
         } else if event.atom == state.atoms._GTK_EDGE_CONSTRAINTS {
             self.set_edge_constraints(state);
         }
@@ -772,16 +780,13 @@ impl X11WindowStatePtr {
             .chunks_exact(4)
             .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
 
-        state.active = false;
         state.fullscreen = false;
         state.maximized_vertical = false;
         state.maximized_horizontal = false;
-        state.hidden = true;
+        state.hidden = false;
 
         for atom in atoms {
-            if atom == state.atoms._NET_WM_STATE_FOCUSED {
-                state.active = true;
-            } else if atom == state.atoms._NET_WM_STATE_FULLSCREEN {
+            if atom == state.atoms._NET_WM_STATE_FULLSCREEN {
                 state.fullscreen = true;
             } else if atom == state.atoms._NET_WM_STATE_MAXIMIZED_VERT {
                 state.maximized_vertical = true;
@@ -927,6 +932,7 @@ impl X11WindowStatePtr {
     }
 
     pub fn set_active(&self, focus: bool) {
+        self.state.borrow_mut().active = focus;
         if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
             fun(focus);
         }
@@ -964,9 +970,15 @@ impl PlatformWindow for X11Window {
         !state.hidden && state.maximized_vertical && state.maximized_horizontal
     }
 
+    fn is_minimized(&self) -> bool {
+        self.0.state.borrow().hidden
+    }
+
     fn window_bounds(&self) -> WindowBounds {
         let state = self.0.state.borrow();
-        if self.is_maximized() {
+        if state.fullscreen {
+            WindowBounds::Fullscreen(state.bounds)
+        } else if self.is_maximized() {
             WindowBounds::Maximized(state.bounds)
         } else {
             WindowBounds::Windowed(state.bounds)
@@ -1035,7 +1047,18 @@ impl PlatformWindow for X11Window {
     }
 
     fn activate(&self) {
-        let data = [1, xproto::Time::CURRENT_TIME.into(), 0, 0, 0];
+        let timestamp = self
+            .0
+            .state
+            .borrow()
+            .client
+            .0
+            .upgrade()
+            .map_or(x11rb::CURRENT_TIME, |client| client.borrow().last_user_time);
+        // Window managers ignore activation requests with no timestamp, or one older
+        // than the user's last interaction with another window, to keep windows from
+        // stealing focus.
+        let data = [1, timestamp, 0, 0, 0];
         let message = xproto::ClientMessageEvent::new(
             32,
             self.0.x_window,
@@ -1053,11 +1076,7 @@ impl PlatformWindow for X11Window {
             .log_err();
         self.0
             .xcb_connection
-            .set_input_focus(
-                xproto::InputFocus::POINTER_ROOT,
-                self.0.x_window,
-                xproto::Time::CURRENT_TIME,
-            )
+            .set_input_focus(xproto::InputFocus::POINTER_ROOT, self.0.x_window, timestamp)
             .log_err();
         self.0.xcb_connection.flush().unwrap();
     }
@@ -1153,18 +1172,30 @@ impl PlatformWindow for X11Window {
     }
 
     fn zoom(&self) {
+        // Asking for the state the window should end up in, rather than toggling,
+        // keeps a window that is half maximized from being flipped to the other half.
+        let wm_hint_property_state = if self.is_maximized() {
+            WmHintPropertyState::Remove
+        } else {
+            WmHintPropertyState::Add
+        };
         let state = self.0.state.borrow();
         self.set_wm_hints(
-            WmHintPropertyState::Toggle,
+            wm_hint_property_state,
             state.atoms._NET_WM_STATE_MAXIMIZED_VERT,
             state.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
         );
     }
 
     fn toggle_fullscreen(&self) {
+        let wm_hint_property_state = if self.is_fullscreen() {
+            WmHintPropertyState::Remove
+        } else {
+            WmHintPropertyState::Add
+        };
         let state = self.0.state.borrow();
         self.set_wm_hints(
-            WmHintPropertyState::Toggle,
+            wm_hint_property_state,
             state.atoms._NET_WM_STATE_FULLSCREEN,
             xproto::AtomEnum::NONE.into(),
         );
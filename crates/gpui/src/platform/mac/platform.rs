@@ -54,7 +54,10 @@ use std::{
     ptr,
     rc::Rc,
     slice, str,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 use strum::IntoEnumIterator;
 
@@ -700,6 +703,53 @@ impl Platform for MacPlatform {
         done_rx
     }
 
+    fn beep(&self) {
+        unsafe { NSBeep() }
+    }
+
+    fn show_notification(&self, title: &str, body: &str) {
+        static NEXT_NOTIFICATION_ID: AtomicUsize = AtomicUsize::new(0);
+        unsafe {
+            // UNUserNotificationCenter throws for binaries that aren't in an app
+            // bundle, which have no bundle identifier.
+            let bundle: id = NSBundle::mainBundle();
+            let bundle_identifier: id = msg_send![bundle, bundleIdentifier];
+            if bundle_identifier == nil {
+                return;
+            }
+            let center: id = msg_send![class!(UNUserNotificationCenter), currentNotificationCenter];
+            let notification_id = NEXT_NOTIFICATION_ID.fetch_add(1, Ordering::Relaxed);
+            let title = title.to_string();
+            let body = body.to_string();
+            // Only the first request prompts the user. Later ones report what they
+            // answered, which is also how a later change in System Settings is seen.
+            let block = ConcreteBlock::new(move |granted: BOOL, _error: id| {
+                if granted != YES {
+                    return;
+                }
+                let content: id = msg_send![class!(UNMutableNotificationContent), new];
+                let _: () = msg_send![content, setTitle: ns_string(&title)];
+                let _: () = msg_send![content, setBody: ns_string(&body)];
+                let request: id = msg_send![
+                    class!(UNNotificationRequest),
+                    requestWithIdentifier: ns_string(&format!("notification-{notification_id}"))
+                    content: content
+                    trigger: nil
+                ];
+                let _: () =
+                    msg_send![center, addNotificationRequest: request withCompletionHandler: nil];
+                let _: () = msg_send![content, release];
+            });
+            let block = block.copy();
+            let options = UN_AUTHORIZATION_OPTION_SOUND | UN_AUTHORIZATION_OPTION_ALERT;
+            let _: () = msg_send![
+                center,
+                requestAuthorizationWithOptions: options
+                completionHandler: &*block
+            ];
+        }
+    }
+
     fn reveal_path(&self, path: &Path) {
         unsafe {
             let path = path.to_path_buf();
@@ -1320,6 +1370,17 @@ extern "C" fn handle_dock_menu(this: &mut Object, _: Sel, _: id) -> id {
     }
 }
 
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+    fn NSBeep();
+}
+
+#[link(name = "UserNotifications", kind = "framework")]
+extern "C" {}
+
+const UN_AUTHORIZATION_OPTION_SOUND: NSUInteger = 1 << 1;
+const UN_AUTHORIZATION_OPTION_ALERT: NSUInteger = 1 << 2;
+
 unsafe fn ns_string(string: &str) -> id {
     NSString::alloc(nil).init_str(string).autorelease()
 }
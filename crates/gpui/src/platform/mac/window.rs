@@ -788,6 +788,12 @@ impl PlatformWindow for MacWindow {
         self.0.as_ref().lock().is_maximized()
     }
 
+    fn is_minimized(&self) -> bool {
+        let window = self.0.lock().native_window;
+        let minimized: BOOL = unsafe { msg_send![window, isMiniaturized] };
+        minimized == YES
+    }
+
     fn window_bounds(&self) -> WindowBounds {
         self.0.as_ref().lock().window_bounds()
     }
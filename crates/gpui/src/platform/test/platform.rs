@@ -252,6 +252,10 @@ impl Platform for TestPlatform {
         unimplemented!()
     }
 
+    fn beep(&self) {}
+
+    fn show_notification(&self, _title: &str, _body: &str) {}
+
     fn on_quit(&self, _callback: Box<dyn FnMut()>) {}
 
     fn on_reopen(&self, _callback: Box<dyn FnMut()>) {
@@ -122,6 +122,10 @@ impl PlatformWindow for TestWindow {
         false
     }
 
+    fn is_minimized(&self) -> bool {
+        false
+    }
+
     fn content_size(&self) -> Size<Pixels> {
         self.bounds().size
     }
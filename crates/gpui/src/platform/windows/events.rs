@@ -9,6 +9,7 @@ use windows::Win32::{
     UI::{
         HiDpi::*,
         Input::{Ime::*, KeyboardAndMouse::*},
+        Shell::*,
         WindowsAndMessaging::*,
     },
 };
@@ -17,6 +18,9 @@ use crate::*;
 
 pub(crate) const CURSOR_STYLE_CHANGED: u32 = WM_USER + 1;
 pub(crate) const CLOSE_ONE_WINDOW: u32 = WM_USER + 2;
+pub(crate) const NOTIFICATION_ICON_EVENT: u32 = WM_USER + 3;
+/// The one notification area icon a window adds, to show notifications from.
+pub(crate) const NOTIFICATION_ICON_ID: u32 = 1;
 
 const SIZE_MOVE_LOOP_TIMER_ID: usize = 1;
 const AUTO_HIDE_TASKBAR_THICKNESS_PX: i32 = 1;
@@ -87,6 +91,7 @@ pub(crate) fn handle_msg(
         WM_SETTINGCHANGE => handle_system_settings_changed(handle, state_ptr),
         WM_DWMCOLORIZATIONCOLORCHANGED => handle_system_theme_changed(state_ptr),
         CURSOR_STYLE_CHANGED => handle_cursor_changed(lparam, state_ptr),
+        NOTIFICATION_ICON_EVENT => handle_notification_icon_msg(handle, lparam),
         _ => None,
     };
     if let Some(n) = handled {
@@ -218,6 +223,7 @@ fn handle_destroy_msg(handle: HWND, state_ptr: Rc<WindowsWindowStatePtr>) -> Opt
     if let Some(callback) = callback {
         callback();
     }
+    remove_notification_icon(handle);
     unsafe {
         PostMessageW(
             None,
@@ -230,6 +236,29 @@ fn handle_destroy_msg(handle: HWND, state_ptr: Rc<WindowsWindowStatePtr>) -> Opt
     Some(0)
 }
 
+fn handle_notification_icon_msg(handle: HWND, lparam: LPARAM) -> Option<isize> {
+    // The icon is only there to show a notification from, so it goes once the
+    // notification does.
+    match lparam.loword() as u32 {
+        NIN_BALLOONTIMEOUT | NIN_BALLOONUSERCLICK | NIN_BALLOONHIDE => {
+            remove_notification_icon(handle);
+        }
+        _ => {}
+    }
+    Some(0)
+}
+
+fn remove_notification_icon(handle: HWND) {
+    let data = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: handle,
+        uID: NOTIFICATION_ICON_ID,
+        ..Default::default()
+    };
+    // Fails when the window has no icon, which is most of the time.
+    let _ = unsafe { Shell_NotifyIconW(NIM_DELETE, &data) };
+}
+
 fn handle_mouse_move_msg(
     lparam: LPARAM,
     wparam: WPARAM,
@@ -28,6 +28,7 @@ use windows::{
                 CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard,
                 RegisterClipboardFormatW, SetClipboardData,
             },
+            Diagnostics::Debug::MessageBeep,
             LibraryLoader::*,
             Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
             Ole::*,
@@ -382,6 +383,38 @@ impl Platform for WindowsPlatform {
         rx
     }
 
+    fn beep(&self) {
+        unsafe { MessageBeep(MB_OK) }.log_err();
+    }
+
+    // Toast notifications need an AppUserModelID registered with a Start menu
+    // shortcut, which Zed doesn't set up yet. A balloon from a notification area
+    // icon is shown as a toast without one, so an icon is added to show it from,
+    // and the window removes it once the balloon is gone.
+    fn show_notification(&self, title: &str, body: &str) {
+        let Some(hwnd) = self.raw_window_handles.read().first().copied() else {
+            return;
+        };
+        let mut data = NOTIFYICONDATAW {
+            cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: hwnd,
+            uID: NOTIFICATION_ICON_ID,
+            uFlags: NIF_ICON | NIF_MESSAGE | NIF_INFO,
+            uCallbackMessage: NOTIFICATION_ICON_EVENT,
+            hIcon: self.icon,
+            dwInfoFlags: NIIF_INFO,
+            ..Default::default()
+        };
+        copy_to_wide_buffer(title, &mut data.szInfoTitle);
+        copy_to_wide_buffer(body, &mut data.szInfo);
+        unsafe {
+            // The icon is still there while an earlier balloon shows.
+            if !Shell_NotifyIconW(NIM_ADD, &data).as_bool() {
+                Shell_NotifyIconW(NIM_MODIFY, &data).ok().log_err();
+            }
+        }
+    }
+
     fn reveal_path(&self, path: &Path) {
         let Ok(file_full_path) = path.canonicalize() else {
             log::error!("unable to parse file path");
@@ -101,6 +101,24 @@ pub(crate) unsafe fn set_window_long(
     }
 }
 
+/// Copies as much of `string` as fits into `buffer`, leaving room for the
+/// terminating nul.
+pub(crate) fn copy_to_wide_buffer(string: &str, buffer: &mut [u16]) {
+    let Some(capacity) = buffer.len().checked_sub(1) else {
+        return;
+    };
+    let mut len = 0;
+    for unit in string.encode_utf16().take(capacity) {
+        buffer[len] = unit;
+        len += 1;
+    }
+    // Don't leave half of a surrogate pair at the end.
+    if len > 0 && (0xD800..0xDC00).contains(&buffer[len - 1]) {
+        len -= 1;
+    }
+    buffer[len] = 0;
+}
+
 pub(crate) fn windows_credentials_target_name(url: &str) -> String {
     format!("zed:url={}", url)
 }
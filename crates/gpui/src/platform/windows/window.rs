@@ -398,6 +398,10 @@ impl PlatformWindow for WindowsWindow {
         self.0.state.borrow().is_maximized()
     }
 
+    fn is_minimized(&self) -> bool {
+        unsafe { IsIconic(self.0.hwnd) }.as_bool()
+    }
+
     fn window_bounds(&self) -> WindowBounds {
         self.0.state.borrow().window_bounds()
     }
@@ -538,6 +538,8 @@ pub struct Window {
     modifiers: Modifiers,
     scale_factor: f32,
     bounds_observers: SubscriberSet<(), AnyObserver>,
+    fullscreen: bool,
+    fullscreen_observers: SubscriberSet<(), AnyObserver>,
     appearance: WindowAppearance,
     appearance_observers: SubscriberSet<(), AnyObserver>,
     active: Rc<Cell<bool>>,
@@ -667,6 +669,7 @@ impl Window {
                 WindowBounds::Windowed(_) => {}
             }
         }
+        let fullscreen = platform_window.is_fullscreen();
 
         platform_window.on_close(Box::new({
             let mut cx = cx.to_async();
@@ -816,6 +819,8 @@ impl Window {
             modifiers,
             scale_factor,
             bounds_observers: SubscriberSet::new(),
+            fullscreen,
+            fullscreen_observers: SubscriberSet::new(),
             appearance,
             appearance_observers: SubscriberSet::new(),
             active,
@@ -987,6 +992,12 @@ impl<'a> WindowContext<'a> {
         self.window.platform_window.is_maximized()
     }
 
+    /// Check if the platform window is minimized. Wayland compositors don't tell
+    /// windows that they were minimized, so there this is always false.
+    pub fn is_minimized(&self) -> bool {
+        self.window.platform_window.is_minimized()
+    }
+
     /// request a certain window decoration (Wayland)
     pub fn request_decorations(&self, decorations: WindowDecorations) {
         self.window.platform_window.request_decorations(decorations);
@@ -1204,6 +1215,17 @@ impl<'a> WindowContext<'a> {
             .bounds_observers
             .clone()
             .retain(&(), |callback| callback(self));
+
+        // Platforms report entering or leaving fullscreen as a move or a resize, even
+        // when the bounds stay the same.
+        let fullscreen = self.window.platform_window.is_fullscreen();
+        if fullscreen != self.window.fullscreen {
+            self.window.fullscreen = fullscreen;
+            self.window
+                .fullscreen_observers
+                .clone()
+                .retain(&(), |callback| callback(self));
+        }
     }
 
     /// Returns the bounds of the current window in the global coordinate space, which could span across multiple displays.
@@ -4198,6 +4220,22 @@ impl<'a, V: 'static> ViewContext<'a, V> {
         subscription
     }
 
+    /// Register a callback to be invoked when the window enters or leaves fullscreen.
+    /// Observe this rather than the window bounds to follow the fullscreen state, since
+    /// the bounds don't always change with it.
+    pub fn observe_window_fullscreen(
+        &mut self,
+        mut callback: impl FnMut(&mut V, &mut ViewContext<V>) + 'static,
+    ) -> Subscription {
+        let view = self.view.downgrade();
+        let (subscription, activate) = self.window.fullscreen_observers.insert(
+            (),
+            Box::new(move |cx| view.update(cx, |view, cx| callback(view, cx)).is_ok()),
+        );
+        activate();
+        subscription
+    }
+
     /// Register a callback to be invoked when the window is activated or deactivated.
     pub fn observe_window_activation(
         &mut self,
@@ -101,7 +101,9 @@ impl ProjectEnvironment {
         }
     }
 
-    fn get_worktree_env(
+    /// Returns the environment of a shell started in the worktree, which is only
+    /// loaded once per worktree.
+    pub(crate) fn get_worktree_env(
         &mut self,
         worktree_id: WorktreeId,
         worktree_abs_path: Arc<Path>,
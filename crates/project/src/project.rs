@@ -4650,6 +4650,13 @@ impl Project {
                                     proto::shell::WithArguments { program, args },
                                 )
                             }
+                            Shell::Remote { host, user, args } => {
+                                proto::shell::ShellType::Remote(proto::shell::Remote {
+                                    host,
+                                    user,
+                                    args,
+                                })
+                            }
                         }),
                     }),
                     tags: template.tags,
@@ -5210,6 +5217,11 @@ impl Project {
                                 args: with_arguments.args,
                             }
                         }
+                        proto::shell::ShellType::Remote(remote) => Shell::Remote {
+                            host: remote.host,
+                            user: remote.user,
+                            args: remote.args,
+                        },
                     };
                     let task_template = TaskTemplate {
                         label: proto_template.label,
@@ -1,7 +1,7 @@
 use crate::Project;
 use anyhow::Context as _;
 use collections::HashMap;
-use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, WeakModel};
+use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, Task, WeakModel};
 use itertools::Itertools;
 use settings::{Settings, SettingsLocation};
 use smol::channel::bounded;
@@ -12,7 +12,7 @@ use std::{
 };
 use task::{Shell, SpawnInTerminal};
 use terminal::{
-    terminal_settings::{self, TerminalSettings},
+    terminal_settings::{self, TerminalProfile, TerminalSettings},
     TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
 use util::ResultExt;
@@ -30,6 +30,8 @@ pub struct Terminals {
 pub enum TerminalKind {
     /// Run a shell at the given path (or $HOME if None)
     Shell(Option<PathBuf>),
+    /// Run a profile from the `terminal.profiles` setting at the given path (or $HOME if None)
+    Profile(TerminalProfile, Option<PathBuf>),
     /// Run a task.
     Task(SpawnInTerminal),
 }
@@ -81,14 +83,46 @@ impl Project {
         Some(SshCommand::DevServer(ssh_command))
     }
 
+    /// Creates a terminal once the environment it starts with is ready, which can take
+    /// a while when the `project_environment` setting has the project's shell load it.
     pub fn create_terminal(
         &mut self,
         kind: TerminalKind,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
-    ) -> anyhow::Result<Model<Terminal>> {
-        let path = match &kind {
-            TerminalKind::Shell(path) => path.as_ref().map(|path| path.to_path_buf()),
+    ) -> Task<anyhow::Result<Model<Terminal>>> {
+        self.create_terminal_with_scrollback(kind, None, window, cx)
+    }
+
+    /// Like [`Project::create_terminal`], showing output saved from an earlier session
+    /// above what the shell prints.
+    pub fn create_terminal_with_scrollback(
+        &mut self,
+        kind: TerminalKind,
+        restored_scrollback: Option<String>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<Model<Terminal>>> {
+        let project_env = self.terminal_project_environment(&kind, cx);
+        cx.spawn(|project, mut cx| async move {
+            let project_env = project_env.await;
+            project.update(&mut cx, |project, cx| {
+                project.create_terminal_with_environment(
+                    kind,
+                    project_env,
+                    restored_scrollback,
+                    window,
+                    cx,
+                )
+            })?
+        })
+    }
+
+    fn terminal_path(&self, kind: &TerminalKind, cx: &AppContext) -> Option<PathBuf> {
+        match kind {
+            TerminalKind::Shell(path) | TerminalKind::Profile(_, path) => {
+                path.as_ref().map(|path| path.to_path_buf())
+            }
             TerminalKind::Task(spawn_task) => {
                 if let Some(cwd) = &spawn_task.cwd {
                     Some(cwd.clone())
@@ -96,7 +130,45 @@ impl Project {
                     self.active_project_directory(cx)
                 }
             }
+        }
+    }
+
+    /// The shell environment of the worktree a terminal opens in, if the
+    /// `project_environment` setting asks for it.
+    fn terminal_project_environment(
+        &mut self,
+        kind: &TerminalKind,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Option<HashMap<String, String>>> {
+        let Some(path) = self.terminal_path(kind, cx) else {
+            return Task::ready(None);
+        };
+        let Some((worktree, _)) = self.find_worktree(&path, cx) else {
+            return Task::ready(None);
+        };
+        let worktree = worktree.read(cx);
+        let (worktree_id, worktree_abs_path) = (worktree.id(), worktree.abs_path());
+        let settings_location = SettingsLocation {
+            worktree_id: worktree_id.to_usize(),
+            path: &path,
         };
+        if !TerminalSettings::get(Some(settings_location), cx).project_environment {
+            return Task::ready(None);
+        }
+        self.environment.update(cx, |environment, cx| {
+            environment.get_worktree_env(worktree_id, worktree_abs_path, cx)
+        })
+    }
+
+    fn create_terminal_with_environment(
+        &mut self,
+        kind: TerminalKind,
+        project_env: Option<HashMap<String, String>>,
+        restored_scrollback: Option<String>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<Model<Terminal>> {
+        let path = self.terminal_path(&kind, cx);
         let ssh_command = self.ssh_command(cx);
 
         let mut settings_location = None;
@@ -118,9 +190,39 @@ impl Project {
             .read(cx)
             .get_cli_environment()
             .unwrap_or_default();
+        // Then the project's, with what direnv or the shell's profile set up for it.
+        env.extend(project_env.unwrap_or_default());
         // Then extend it with the explicit env variables from the settings, so they take
-        // precedence.
-        env.extend(settings.env.clone());
+        // precedence. A local shell also inherits Zed's own environment, so that is what
+        // the variables they refer to are looked up in, beneath the above.
+        let mut local_env = env::vars().collect::<HashMap<_, _>>();
+        local_env.extend(env.clone());
+        let settings_env = expand_env_settings(&settings.env, &local_env);
+        local_env.extend(settings_env.clone());
+        env.extend(settings_env);
+        // Only the variables that were asked for explicitly are set on a remote host, as
+        // the local ones, like `PATH`, would likely break its environment. The variables
+        // they refer to are left for the remote shell to expand, in its own environment.
+        let mut remote_env = settings.env.clone();
+
+        let remote_destination = settings.shell.remote_destination();
+        let remote_ssh_args = settings.shell.ssh_args();
+        let mut shell = shell_with_settings_args(
+            settings.shell.clone(),
+            settings.login_shell,
+            &settings.shell_args,
+        );
+        let mut profile_command = None;
+        if let TerminalKind::Profile(profile, _) = &kind {
+            let profile_env = expand_env_settings(&profile.env, &local_env);
+            remote_env.extend(profile.env.clone());
+            env.extend(profile_env);
+            shell = profile.shell(&shell, path.as_deref());
+            profile_command = profile
+                .program
+                .clone()
+                .map(|program| (program, profile.args.clone()));
+        }
 
         let local_path = if ssh_command.is_none() {
             path.clone()
@@ -133,7 +235,7 @@ impl Project {
         let mut python_venv_activate_command = None;
 
         let (spawn_task, shell) = match kind {
-            TerminalKind::Shell(_) => {
+            TerminalKind::Shell(_) | TerminalKind::Profile(..) => {
                 if let Some(python_venv_directory) = python_venv_directory {
                     python_venv_activate_command =
                         self.python_activate_command(&python_venv_directory, settings);
@@ -150,12 +252,38 @@ impl Project {
                         env.entry("TERM".to_string())
                             .or_insert_with(|| "xterm-256color".to_string());
 
-                        let (program, args) =
-                            wrap_for_ssh(ssh_command, None, path.as_deref(), env, None);
+                        let (program, args) = wrap_for_ssh(
+                            ssh_command,
+                            profile_command
+                                .as_ref()
+                                .map(|(program, args)| (program, args)),
+                            path.as_deref(),
+                            env,
+                            None,
+                        );
                         env = HashMap::default();
                         (None, Shell::WithArguments { program, args })
                     }
-                    None => (None, settings.shell.clone()),
+                    None => match remote_ssh_args {
+                        Some(ssh_args) => {
+                            log::debug!("Connecting to a remote shell: {ssh_args:?}");
+                            remote_env
+                                .entry("TERM".to_string())
+                                .or_insert_with(|| "xterm-256color".to_string());
+                            let (program, args) = wrap_commands_for_ssh(
+                                &SshCommand::Direct(ssh_args),
+                                profile_command
+                                    .as_ref()
+                                    .map(|(program, args)| (program, args)),
+                                path.as_deref(),
+                                remote_env_assignments(&remote_env),
+                            );
+                            let (program, args) = reconnect_on_drop(program, args)?;
+                            env = HashMap::default();
+                            (None, Shell::WithArguments { program, args })
+                        }
+                        None => (None, shell),
+                    },
                 }
             }
             TerminalKind::Task(spawn_task) => {
@@ -216,8 +344,13 @@ impl Project {
             shell,
             env,
             Some(settings.blinking),
+            settings.cursor_shape,
+            &settings.word_characters,
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            settings.scroll_to_bottom_on_input,
+            settings.scroll_to_bottom_on_output,
+            restored_scrollback,
             window,
             completion_tx,
             cx,
@@ -243,6 +376,11 @@ impl Project {
             })
             .detach();
 
+            if let Some(destination) = remote_destination.filter(|_| ssh_command.is_none()) {
+                terminal_handle.update(cx, |terminal, _| {
+                    terminal.set_remote_destination(destination)
+                });
+            }
             if let Some(activate_command) = python_venv_activate_command {
                 self.activate_python_virtual_environment(activate_command, &terminal_handle, cx);
             }
@@ -311,6 +449,111 @@ impl Project {
     }
 }
 
+/// Adds the arguments from the `login_shell` and `shell_args` settings to a shell,
+/// ahead of its own. The system shell has to be named to take arguments, which
+/// leaves it as is when `$SHELL` is not set.
+fn shell_with_settings_args(shell: Shell, login_shell: bool, shell_args: &[String]) -> Shell {
+    let mut settings_args = Vec::new();
+    if login_shell && cfg!(not(target_os = "windows")) {
+        settings_args.push("-l".to_string());
+    }
+    settings_args.extend(shell_args.iter().cloned());
+    if settings_args.is_empty() {
+        return shell;
+    }
+
+    let (program, args) = match shell {
+        Shell::System => match env::var("SHELL") {
+            Ok(program) => (program, Vec::new()),
+            Err(_) => return Shell::System,
+        },
+        Shell::Program(program) => (program, Vec::new()),
+        Shell::WithArguments { program, args } => (program, args),
+        // The settings' arguments are for a local shell.
+        Shell::Remote { .. } => return shell,
+    };
+    settings_args.extend(args);
+    Shell::WithArguments {
+        program,
+        args: settings_args,
+    }
+}
+
+/// Expands `$VAR` and `${VAR}` references in the values of the `env` setting against the
+/// environment the terminal inherits, leaving unknown variables as they are.
+fn expand_env_settings(
+    settings_env: &HashMap<String, String>,
+    inherited_env: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    settings_env
+        .iter()
+        .map(|(key, value)| {
+            let value = shellexpand::env_with_context_no_errors(value, |name| {
+                inherited_env.get(name).cloned()
+            });
+            (key.clone(), value.into_owned())
+        })
+        .collect()
+}
+
+/// Assignments that set the `env` setting's variables on a remote host, where the
+/// remote shell expands the `$VAR` and `${VAR}` references in their values.
+fn remote_env_assignments(env: &HashMap<String, String>) -> String {
+    let mut assignments = String::new();
+    for (key, value) in env {
+        if let Ok(key) = shlex::try_quote(key) {
+            assignments.push_str(&format!("{key}={} ", quote_except_references(value)));
+        }
+    }
+    assignments
+}
+
+/// Quotes `value` as a single `sh` word, leaving the `$VAR` and `${VAR}` references in
+/// it for the shell to expand.
+fn quote_except_references(value: &str) -> String {
+    fn is_name_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+    fn quote_literal(quoted: &mut String, literal: &str) {
+        if !literal.is_empty() {
+            quoted.push('\'');
+            quoted.push_str(&literal.replace('\'', "'\\''"));
+            quoted.push('\'');
+        }
+    }
+
+    let mut quoted = String::new();
+    let mut literal_start = 0;
+    let mut rest = value;
+    while let Some(dollar) = rest.find('$') {
+        let after = &rest[dollar + 1..];
+        let (name, reference_len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) if end > 0 && braced[..end].chars().all(is_name_char) => {
+                    (&braced[..end], end + 3)
+                }
+                _ => ("", 1),
+            },
+            None => {
+                let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+                (&after[..end], end + 1)
+            }
+        };
+        let reference_start = value.len() - rest.len() + dollar;
+        if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            quote_literal(&mut quoted, &value[literal_start..reference_start]);
+            quoted.push_str(&format!("\"${{{name}}}\""));
+            literal_start = reference_start + reference_len;
+        }
+        rest = &value[reference_start + reference_len..];
+    }
+    quote_literal(&mut quoted, &value[literal_start..]);
+    if quoted.is_empty() {
+        quoted.push_str("''");
+    }
+    quoted
+}
+
 pub fn wrap_for_ssh(
     ssh_command: &SshCommand,
     command: Option<(&String, &Vec<String>)>,
@@ -318,15 +561,6 @@ pub fn wrap_for_ssh(
     env: HashMap<String, String>,
     venv_directory: Option<PathBuf>,
 ) -> (String, Vec<String>) {
-    let to_run = if let Some((command, args)) = command {
-        iter::once(command)
-            .chain(args)
-            .filter_map(|arg| shlex::try_quote(arg).ok())
-            .join(" ")
-    } else {
-        "exec ${SHELL:-sh} -l".to_string()
-    };
-
     let mut env_changes = String::new();
     for (k, v) in env.iter() {
         if let Some((k, v)) = shlex::try_quote(k).ok().zip(shlex::try_quote(v).ok()) {
@@ -338,6 +572,25 @@ pub fn wrap_for_ssh(
             env_changes.push_str(&format!("PATH={}:$PATH ", str));
         }
     }
+    wrap_commands_for_ssh(ssh_command, command, path, env_changes)
+}
+
+/// Like [`wrap_for_ssh`], with the variable assignments to run the command with
+/// already quoted for the remote shell.
+fn wrap_commands_for_ssh(
+    ssh_command: &SshCommand,
+    command: Option<(&String, &Vec<String>)>,
+    path: Option<&Path>,
+    env_changes: String,
+) -> (String, Vec<String>) {
+    let to_run = if let Some((command, args)) = command {
+        iter::once(command)
+            .chain(args)
+            .filter_map(|arg| shlex::try_quote(arg).ok())
+            .join(" ")
+    } else {
+        "exec ${SHELL:-sh} -l".to_string()
+    };
 
     let commands = if let Some(path) = path {
         format!("cd {:?}; {} {}", path, env_changes, to_run)
@@ -362,6 +615,51 @@ pub fn wrap_for_ssh(
     (program, args)
 }
 
+/// How many times in a row reconnecting may fail before the terminal gives up.
+#[cfg(not(target_os = "windows"))]
+const MAX_RECONNECT_ATTEMPTS: usize = 30;
+
+/// Runs `ssh` again when the connection drops, so that the terminal reconnects rather
+/// than closes.
+///
+/// `ssh` exits with 255 for any failure of its own, including a mistyped host or a
+/// rejected key, which retrying won't fix. So it only reconnects once a session was
+/// established, which `ssh` reports by running its `LocalCommand`, and gives up after
+/// [`MAX_RECONNECT_ATTEMPTS`] attempts in a row that didn't get one.
+#[cfg(not(target_os = "windows"))]
+fn reconnect_on_drop(program: String, args: Vec<String>) -> anyhow::Result<(String, Vec<String>)> {
+    let quote = |arg: &String| {
+        shlex::try_quote(arg)
+            .map(|arg| arg.into_owned())
+            .with_context(|| format!("can't pass {arg:?} to the shell"))
+    };
+    let args = args
+        .iter()
+        .map(quote)
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .join(" ");
+    let program = quote(&program)?;
+    let script = format!(
+        "marker=\"${{TMPDIR:-/tmp}}/zed-ssh-connected-$$\"; trap 'rm -f \"$marker\"' EXIT; \
+         connected=; attempts=0; \
+         while true; do \
+         {program} -o PermitLocalCommand=yes -o \"LocalCommand=touch '$marker'\" {args}; \
+         status=$?; \
+         if [ -e \"$marker\" ]; then rm -f \"$marker\"; connected=1; attempts=0; fi; \
+         [ $status -eq 255 ] && [ -n \"$connected\" ] \
+         && [ $attempts -lt {MAX_RECONNECT_ATTEMPTS} ] || exit $status; \
+         attempts=$((attempts + 1)); \
+         printf '\\r\\n[Connection lost, reconnecting]\\r\\n'; sleep 2; \
+         done"
+    );
+    Ok(("sh".to_string(), vec!["-c".to_string(), script]))
+}
+
+#[cfg(target_os = "windows")]
+fn reconnect_on_drop(program: String, args: Vec<String>) -> anyhow::Result<(String, Vec<String>)> {
+    Ok((program, args))
+}
+
 fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> anyhow::Result<()> {
     let mut env_paths = vec![new_path.to_path_buf()];
     if let Some(path) = env.get("PATH").or(env::var("PATH").ok().as_ref()) {
@@ -378,6 +676,122 @@ fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> a
 #[cfg(test)]
 mod tests {
     use collections::HashMap;
+    use task::Shell;
+
+    use super::{expand_env_settings, quote_except_references, shell_with_settings_args};
+
+    #[test]
+    fn test_expand_env_settings() {
+        let inherited_env = HashMap::from_iter([
+            ("HOME".to_string(), "/home/me".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ]);
+        let settings_env = HashMap::from_iter([
+            ("PATH".to_string(), "${HOME}/.local/bin:$PATH".to_string()),
+            ("PLAIN".to_string(), "value".to_string()),
+            (
+                "UNSET".to_string(),
+                "$ZED_TEST_UNSET_VARIABLE/${ZED_TEST_UNSET_VARIABLE}".to_string(),
+            ),
+            ("UNCLOSED".to_string(), "${HOME".to_string()),
+            ("TRAILING".to_string(), "cost: 5$".to_string()),
+            // Zed's own environment isn't looked at, only the one passed in.
+            ("OWN".to_string(), "$CARGO_MANIFEST_DIR".to_string()),
+        ]);
+
+        let expanded = expand_env_settings(&settings_env, &inherited_env);
+        assert_eq!(expanded["PATH"], "/home/me/.local/bin:/usr/bin");
+        assert_eq!(expanded["PLAIN"], "value");
+        assert_eq!(
+            expanded["UNSET"],
+            "$ZED_TEST_UNSET_VARIABLE/${ZED_TEST_UNSET_VARIABLE}"
+        );
+        assert_eq!(expanded["UNCLOSED"], "${HOME");
+        assert_eq!(expanded["TRAILING"], "cost: 5$");
+        assert_eq!(expanded["OWN"], "$CARGO_MANIFEST_DIR");
+        assert_eq!(expanded.len(), settings_env.len());
+    }
+
+    #[test]
+    fn test_quote_except_references() {
+        assert_eq!(
+            quote_except_references("${HOME}/.local/bin:$PATH"),
+            r#""${HOME}"'/.local/bin:'"${PATH}""#
+        );
+        assert_eq!(quote_except_references("it's"), r#"'it'\''s'"#);
+        assert_eq!(quote_except_references("cost: 5$"), "'cost: 5$'");
+        assert_eq!(quote_except_references("${HOME"), "'${HOME'");
+        assert_eq!(quote_except_references("$(rm -rf ~)"), "'$(rm -rf ~)'");
+        assert_eq!(quote_except_references(""), "''");
+    }
+
+    #[test]
+    fn test_shell_with_settings_args() {
+        let shell_args = vec!["--norc".to_string()];
+        let login_args = if cfg!(target_os = "windows") {
+            vec![]
+        } else {
+            vec!["-l".to_string()]
+        };
+
+        let program = Shell::Program("bash".to_string());
+        assert_eq!(
+            shell_with_settings_args(program.clone(), false, &[]),
+            program
+        );
+        assert_eq!(
+            shell_with_settings_args(program, false, &shell_args),
+            Shell::WithArguments {
+                program: "bash".to_string(),
+                args: vec!["--norc".to_string()],
+            }
+        );
+
+        // The settings' arguments come ahead of the shell's own.
+        let with_arguments = Shell::WithArguments {
+            program: "bash".to_string(),
+            args: vec!["-i".to_string()],
+        };
+        let mut expected_args = login_args;
+        expected_args.extend(["--norc".to_string(), "-i".to_string()]);
+        assert_eq!(
+            shell_with_settings_args(with_arguments, true, &shell_args),
+            Shell::WithArguments {
+                program: "bash".to_string(),
+                args: expected_args,
+            }
+        );
+
+        let remote = Shell::Remote {
+            host: "example.com".to_string(),
+            user: None,
+            args: Vec::new(),
+        };
+        assert_eq!(
+            shell_with_settings_args(remote.clone(), true, &shell_args),
+            remote
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_reconnect_on_drop() {
+        let (program, args) = super::reconnect_on_drop(
+            "ssh".to_string(),
+            vec!["-t".to_string(), "my host".to_string()],
+        )
+        .unwrap();
+        assert_eq!(program, "sh");
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0], "-c");
+        assert!(args[1].contains(
+            r#"ssh -o PermitLocalCommand=yes -o "LocalCommand=touch '$marker'" -t 'my host';"#
+        ));
+        assert!(args[1].contains(r#"[ $status -eq 255 ] && [ -n "$connected" ]"#));
+
+        // Arguments that can't be quoted aren't left out, running a different command.
+        assert!(super::reconnect_on_drop("ssh".to_string(), vec!["nul\0".to_string()]).is_err());
+    }
 
     #[test]
     fn test_add_environment_path_with_existing_path() {
@@ -127,7 +127,7 @@ impl TerminalOutput {
         let term = alacritty_terminal::Term::new(
             Config::default(),
             &terminal_size(cx),
-            terminal::ZedListener(events_tx.clone()),
+            terminal::ZedListener::new(events_tx.clone()),
         );
 
         mem::forget(events_rx);
@@ -258,7 +258,9 @@ impl Render for TerminalOutput {
                 point: ic.point,
                 cell: ic.cell.clone(),
             });
-        let (cells, rects) = TerminalElement::layout_grid(grid, &text_style, text_system, None, cx);
+        let theme = terminal::terminal_theme(cx);
+        let (cells, rects) =
+            TerminalElement::layout_grid(grid, &text_style, text_system, None, &theme, cx);
 
         // lines are 0-indexed, so we must add 1 to get the number of lines
         let text_line_height = text_style.line_height_in_pixels(cx.rem_size());
@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sum_tree::TreeMap;
+
+fn generate_map(len: usize) -> TreeMap<usize, usize> {
+    TreeMap::from_ordered_entries((0..len).map(|key| (key, key * 2)))
+}
+
+fn sum_tree_benchmarks(c: &mut Criterion) {
+    let sizes = [1_000, 100_000];
+
+    let mut group = c.benchmark_group("iter");
+    for size in sizes.iter() {
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let map = generate_map(*size);
+
+            b.iter(|| {
+                let sum = map.iter().map(|(_, value)| *value).sum::<usize>();
+                black_box(sum);
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("retain");
+    for size in sizes.iter() {
+        group.throughput(Throughput::Elements(*size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let map = generate_map(*size);
+
+            b.iter_with_large_drop(|| {
+                let mut map = map.clone();
+                map.retain(|key, _| key % 2 == 0);
+                map
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sum_tree_benchmarks);
+criterion_main!(benches);
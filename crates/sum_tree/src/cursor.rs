@@ -18,9 +18,11 @@ pub struct Cursor<'a, T: Item, D> {
     at_end: bool,
 }
 
+/// Iterates every item in a tree by walking its leaves directly, without the
+/// position bookkeeping performed by [`Cursor`].
 pub struct Iter<'a, T: Item> {
-    tree: &'a SumTree<T>,
-    stack: ArrayVec<StackEntry<'a, T, ()>, 16>,
+    stack: ArrayVec<(&'a [SumTree<T>], usize), 16>,
+    leaf: std::slice::Iter<'a, T>,
 }
 
 impl<'a, T, D> Cursor<'a, T, D>
@@ -537,9 +539,26 @@ where
 
 impl<'a, T: Item> Iter<'a, T> {
     pub(crate) fn new(tree: &'a SumTree<T>) -> Self {
-        Self {
-            tree,
-            stack: Default::default(),
+        let mut this = Self {
+            stack: ArrayVec::new(),
+            leaf: [].iter(),
+        };
+        this.descend(tree);
+        this
+    }
+
+    fn descend(&mut self, mut tree: &'a SumTree<T>) {
+        loop {
+            match tree.0.as_ref() {
+                Node::Internal { child_trees, .. } => {
+                    self.stack.push((child_trees.as_slice(), 0));
+                    tree = &child_trees[0];
+                }
+                Node::Leaf { items, .. } => {
+                    self.leaf = items.iter();
+                    return;
+                }
+            }
         }
     }
 }
@@ -548,55 +567,20 @@ impl<'a, T: Item> Iterator for Iter<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut descend = false;
-
-        if self.stack.is_empty() {
-            self.stack.push(StackEntry {
-                tree: self.tree,
-                index: 0,
-                position: (),
-            });
-            descend = true;
-        }
-
-        while !self.stack.is_empty() {
-            let new_subtree = {
-                let entry = self.stack.last_mut().unwrap();
-                match entry.tree.0.as_ref() {
-                    Node::Internal { child_trees, .. } => {
-                        if !descend {
-                            entry.index += 1;
-                        }
-                        child_trees.get(entry.index)
-                    }
-                    Node::Leaf { items, .. } => {
-                        if !descend {
-                            entry.index += 1;
-                        }
-
-                        if let Some(next_item) = items.get(entry.index) {
-                            return Some(next_item);
-                        } else {
-                            None
-                        }
-                    }
-                }
-            };
+        loop {
+            if let Some(item) = self.leaf.next() {
+                return Some(item);
+            }
 
-            if let Some(subtree) = new_subtree {
-                descend = true;
-                self.stack.push(StackEntry {
-                    tree: subtree,
-                    index: 0,
-                    position: (),
-                });
+            let entry = self.stack.last_mut()?;
+            entry.1 += 1;
+            let (child_trees, index) = *entry;
+            if let Some(child) = child_trees.get(index) {
+                self.descend(child);
             } else {
-                descend = false;
                 self.stack.pop();
             }
         }
-
-        None
     }
 }
 
@@ -0,0 +1,266 @@
+use std::{fmt::Debug, ops::Range};
+
+use crate::{Dimension, Item, KeyedItem, SumTree, Summary};
+
+/// A map from half-open ranges to values.
+///
+/// Entries are ordered by range start (then end), and every subtree tracks the
+/// greatest range end it contains, which lets queries skip subtrees that end
+/// before the point of interest. Stabbing and overlap queries run in
+/// `O(log n + k)` for `k` results.
+#[derive(Clone, PartialEq, Eq)]
+pub struct IntervalMap<K, V>(SumTree<IntervalEntry<K, V>>)
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntervalEntry<K, V> {
+    range: Range<K>,
+    value: V,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IntervalKey<K>(Option<(K, K)>);
+
+impl<K> Default for IntervalKey<K> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IntervalSummary<K> {
+    max_key: IntervalKey<K>,
+    max_end: Option<K>,
+}
+
+impl<K> Default for IntervalSummary<K> {
+    fn default() -> Self {
+        Self {
+            max_key: IntervalKey::default(),
+            max_end: None,
+        }
+    }
+}
+
+impl<K: Clone + Debug + Ord, V: Clone + Debug> IntervalMap<K, V> {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Inserts a value for the given range, returning the value previously
+    /// stored for exactly the same range, if any.
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Option<V> {
+        self.0
+            .insert_or_replace(IntervalEntry { range, value }, &())
+            .map(|entry| entry.value)
+    }
+
+    pub fn remove(&mut self, range: &Range<K>) -> Option<V> {
+        self.0
+            .remove(&interval_key(range), &())
+            .map(|entry| entry.value)
+    }
+
+    pub fn get(&self, range: &Range<K>) -> Option<&V> {
+        self.0
+            .get(&interval_key(range), &())
+            .map(|entry| &entry.value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Range<K>, &V)> + '_ {
+        self.0.iter().map(|entry| (&entry.range, &entry.value))
+    }
+
+    /// Returns every entry whose range contains the given point, ordered by range start.
+    pub fn containing<'a>(
+        &'a self,
+        point: &'a K,
+    ) -> impl Iterator<Item = (&'a Range<K>, &'a V)> + 'a {
+        self.0
+            .filter::<_, ()>(move |summary: &IntervalSummary<K>| {
+                summary.max_end.as_ref().map_or(false, |end| end > point)
+            })
+            .take_while(move |entry| entry.range.start <= *point)
+            .map(|entry| (&entry.range, &entry.value))
+    }
+
+    /// Returns every entry whose range overlaps the given range, ordered by range start.
+    pub fn overlapping<'a>(
+        &'a self,
+        range: &'a Range<K>,
+    ) -> impl Iterator<Item = (&'a Range<K>, &'a V)> + 'a {
+        self.0
+            .filter::<_, ()>(move |summary: &IntervalSummary<K>| {
+                summary
+                    .max_end
+                    .as_ref()
+                    .map_or(false, |end| *end > range.start)
+            })
+            .take_while(move |entry| entry.range.start < range.end)
+            .map(|entry| (&entry.range, &entry.value))
+    }
+}
+
+fn interval_key<K: Clone>(range: &Range<K>) -> IntervalKey<K> {
+    IntervalKey(Some((range.start.clone(), range.end.clone())))
+}
+
+impl<K, V> Debug for IntervalMap<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> Default for IntervalMap<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<K, V> Item for IntervalEntry<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone,
+{
+    type Summary = IntervalSummary<K>;
+
+    fn summary(&self) -> Self::Summary {
+        IntervalSummary {
+            max_key: self.key(),
+            max_end: Some(self.range.end.clone()),
+        }
+    }
+}
+
+impl<K, V> KeyedItem for IntervalEntry<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone,
+{
+    type Key = IntervalKey<K>;
+
+    fn key(&self) -> Self::Key {
+        interval_key(&self.range)
+    }
+}
+
+impl<K> Summary for IntervalSummary<K>
+where
+    K: Clone + Debug + Ord,
+{
+    type Context = ();
+
+    fn add_summary(&mut self, summary: &Self, _: &()) {
+        self.max_key = summary.max_key.clone();
+        if summary.max_end > self.max_end {
+            self.max_end = summary.max_end.clone();
+        }
+    }
+}
+
+impl<'a, K> Dimension<'a, IntervalSummary<K>> for IntervalKey<K>
+where
+    K: Clone + Debug + Ord,
+{
+    fn add_summary(&mut self, summary: &'a IntervalSummary<K>, _: &()) {
+        *self = summary.max_key.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_basic() {
+        let mut map = IntervalMap::default();
+        assert!(map.is_empty());
+
+        map.insert(0..5, "a");
+        map.insert(3..8, "b");
+        map.insert(10..12, "c");
+        assert_eq!(map.insert(3..8, "B"), Some("b"));
+        assert_eq!(map.get(&(3..8)), Some(&"B"));
+
+        assert_eq!(
+            map.containing(&4).collect::<Vec<_>>(),
+            vec![(&(0..5), &"a"), (&(3..8), &"B")]
+        );
+        assert_eq!(
+            map.containing(&5).collect::<Vec<_>>(),
+            vec![(&(3..8), &"B")]
+        );
+        assert_eq!(map.containing(&9).count(), 0);
+
+        assert_eq!(
+            map.overlapping(&(7..11)).collect::<Vec<_>>(),
+            vec![(&(3..8), &"B"), (&(10..12), &"c")]
+        );
+        assert_eq!(map.overlapping(&(8..10)).count(), 0);
+
+        assert_eq!(map.remove(&(0..5)), Some("a"));
+        assert_eq!(map.remove(&(0..5)), None);
+        assert_eq!(map.containing(&1).count(), 0);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let mut map = IntervalMap::default();
+            let mut reference = Vec::new();
+            for value in 0..rng.gen_range(0..100) {
+                let start = rng.gen_range(0..100);
+                let end = rng.gen_range(start..start + 20);
+                if map.insert(start..end, value).is_none() {
+                    reference.push((start..end, value));
+                } else {
+                    let entry = reference
+                        .iter_mut()
+                        .find(|(range, _)| *range == (start..end))
+                        .unwrap();
+                    entry.1 = value;
+                }
+            }
+            reference.sort_by_key(|(range, _)| (range.start, range.end));
+
+            for _ in 0..20 {
+                let point = rng.gen_range(0..130);
+                let expected = reference
+                    .iter()
+                    .filter(|(range, _)| range.contains(&point))
+                    .map(|(range, value)| (range.clone(), *value))
+                    .collect::<Vec<_>>();
+                let actual = map
+                    .containing(&point)
+                    .map(|(range, value)| (range.clone(), *value))
+                    .collect::<Vec<_>>();
+                assert_eq!(actual, expected, "containing {point}");
+
+                let start = rng.gen_range(0..130);
+                let query = start..rng.gen_range(start..start + 30);
+                let expected = reference
+                    .iter()
+                    .filter(|(range, _)| range.start < query.end && query.start < range.end)
+                    .map(|(range, value)| (range.clone(), *value))
+                    .collect::<Vec<_>>();
+                let actual = map
+                    .overlapping(&query)
+                    .map(|(range, value)| (range.clone(), *value))
+                    .collect::<Vec<_>>();
+                assert_eq!(actual, expected, "overlapping {query:?}");
+            }
+        }
+    }
+}
@@ -1,13 +1,19 @@
 mod cursor;
+mod interval_map;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test;
 mod tree_map;
 
 use arrayvec::ArrayVec;
 pub use cursor::{Cursor, FilterCursor, Iter};
+pub use interval_map::IntervalMap;
 use rayon::prelude::*;
 use std::marker::PhantomData;
 use std::mem;
 use std::{cmp::Ordering, fmt, iter::FromIterator, sync::Arc};
-pub use tree_map::{MapSeekTarget, TreeMap, TreeSet};
+pub use tree_map::{
+    MapSeekTarget, TreeMap, TreeMapCursor, TreeMapEditBuilder, TreeMapSnapshot, TreeSet,
+};
 
 #[cfg(test)]
 pub const TREE_BASE: usize = 2;
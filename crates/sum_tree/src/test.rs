@@ -0,0 +1,117 @@
+use rand::prelude::*;
+use std::{collections::BTreeMap, fmt::Debug, ops::Range};
+
+use crate::TreeMap;
+
+/// An ordered map whose behavior can be checked against a [`BTreeMap`] by
+/// [`check_random_map_operations`].
+pub trait RandomizedMap<K, V>: Default {
+    fn insert(&mut self, key: K, value: V);
+    fn remove(&mut self, key: &K) -> Option<V>;
+    /// Removes every entry whose key lies within the given range.
+    fn remove_range(&mut self, range: Range<K>);
+    fn get(&self, key: &K) -> Option<V>;
+    /// Returns the entries whose keys are greater than or equal to the given key, in order.
+    fn entries_from(&self, key: &K) -> Vec<(K, V)>;
+    fn entries(&self) -> Vec<(K, V)>;
+}
+
+/// Applies `operations` random inserts, removals and range removals to a new
+/// map, mirroring each one on a [`BTreeMap`] and asserting that both agree
+/// after every step. Returns the resulting map for further inspection.
+pub fn check_random_map_operations<M, K, V>(
+    rng: &mut StdRng,
+    operations: usize,
+    mut random_key: impl FnMut(&mut StdRng) -> K,
+    mut random_value: impl FnMut(&mut StdRng) -> V,
+) -> M
+where
+    M: RandomizedMap<K, V>,
+    K: Clone + Debug + Ord,
+    V: Clone + Debug + PartialEq,
+{
+    let mut map = M::default();
+    let mut reference = BTreeMap::new();
+
+    for _ in 0..operations {
+        match rng.gen_range(0..100) {
+            0..=59 => {
+                let key = random_key(rng);
+                let value = random_value(rng);
+                log::info!("inserting {key:?}: {value:?}");
+                map.insert(key.clone(), value.clone());
+                reference.insert(key, value);
+            }
+            60..=89 => {
+                let key = random_key(rng);
+                log::info!("removing {key:?}");
+                assert_eq!(map.remove(&key), reference.remove(&key), "remove {key:?}");
+            }
+            _ => {
+                let mut start = random_key(rng);
+                let mut end = random_key(rng);
+                if start > end {
+                    std::mem::swap(&mut start, &mut end);
+                }
+                log::info!("removing range {start:?}..{end:?}");
+                reference.retain(|key, _| *key < start || *key >= end);
+                map.remove_range(start..end);
+            }
+        }
+
+        assert_eq!(
+            map.entries(),
+            reference
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        let key = random_key(rng);
+        assert_eq!(map.get(&key), reference.get(&key).cloned(), "get {key:?}");
+        assert_eq!(
+            map.entries_from(&key),
+            reference
+                .range(key.clone()..)
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Vec<_>>(),
+            "entries from {key:?}"
+        );
+    }
+
+    map
+}
+
+impl<K, V> RandomizedMap<K, V> for TreeMap<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    fn insert(&mut self, key: K, value: V) {
+        TreeMap::insert(self, key, value);
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        TreeMap::remove(self, key)
+    }
+
+    fn remove_range(&mut self, range: Range<K>) {
+        TreeMap::remove_range(self, &range.start, &range.end);
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        TreeMap::get(self, key).cloned()
+    }
+
+    fn entries_from(&self, key: &K) -> Vec<(K, V)> {
+        self.iter_from(key)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    fn entries(&self) -> Vec<(K, V)> {
+        self.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
@@ -1,6 +1,11 @@
-use std::{cmp::Ordering, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap},
+    fmt::Debug,
+    ops::Deref,
+};
 
-use crate::{Bias, Dimension, Edit, Item, KeyedItem, SeekTarget, SumTree, Summary};
+use crate::{Bias, Cursor, Dimension, Edit, Item, KeyedItem, SeekTarget, SumTree, Summary};
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct TreeMap<K, V>(SumTree<MapEntry<K, V>>)
@@ -26,12 +31,64 @@ impl<K> Default for MapKey<K> {
 #[derive(Clone, Debug)]
 pub struct MapKeyRef<'a, K>(Option<&'a K>);
 
+#[derive(Clone, Debug)]
+pub struct MapSummary<K> {
+    max_key: MapKey<K>,
+    count: usize,
+}
+
+impl<K> Default for MapSummary<K> {
+    fn default() -> Self {
+        Self {
+            max_key: MapKey::default(),
+            count: 0,
+        }
+    }
+}
+
 impl<'a, K> Default for MapKeyRef<'a, K> {
     fn default() -> Self {
         Self(None)
     }
 }
 
+/// A cursor over the entries of a [`TreeMap`] that can be consumed incrementally
+/// and repositioned forward without restarting the search from the root.
+pub struct TreeMapCursor<'a, K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    cursor: Cursor<'a, MapEntry<K, V>, MapKeyRef<'a, K>>,
+}
+
+/// An immutable view of a [`TreeMap`] at a point in time.
+///
+/// Taking a snapshot is cheap, as it shares structure with the map it came from.
+/// Snapshots expose only the map's read methods and are `Send + Sync` whenever
+/// the keys and values are, so they can be handed to background tasks while the
+/// original map continues to be mutated.
+#[derive(Clone)]
+pub struct TreeMapSnapshot<K, V>(TreeMap<K, V>)
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug;
+
+/// Accumulates inserts, removals and updates to a [`TreeMap`] so they can be
+/// applied together in a single pass over the tree.
+///
+/// When several operations target the same key, they take effect in the order
+/// they were recorded.
+pub struct TreeMapEditBuilder<'a, K, V> {
+    edits: BTreeMap<K, PendingEdit<'a, V>>,
+}
+
+enum PendingEdit<'a, V> {
+    Insert(V),
+    Remove,
+    Update(Vec<Box<dyn FnOnce(&mut V) + 'a>>),
+}
+
 #[derive(Clone)]
 pub struct TreeSet<K>(TreeMap<K, ()>)
 where
@@ -105,6 +162,29 @@ impl<K: Clone + Debug + Ord, V: Clone + Debug> TreeMap<K, V> {
         cursor.item().map(|item| (&item.key, &item.value))
     }
 
+    /// Returns the entry at the given index in key order, in `O(log n)`.
+    pub fn nth(&self, index: usize) -> Option<(&K, &V)> {
+        let mut cursor = self.0.cursor::<usize>();
+        cursor.seek(&index, Bias::Right, &());
+        cursor.item().map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Returns the index of the given key in key order, in `O(log n)`.
+    pub fn position_of(&self, key: &K) -> Option<usize> {
+        let mut cursor = self.0.cursor::<(MapKeyRef<'_, K>, usize)>();
+        cursor.seek(&MapKeyRef(Some(key)), Bias::Left, &());
+        let entry = cursor.item()?;
+        (entry.key == *key).then(|| cursor.start().1)
+    }
+
+    /// Returns a cursor positioned on the entry for the given key, or on the
+    /// first entry after it if the key is not present.
+    pub fn cursor_for_key(&self, key: &K) -> TreeMapCursor<'_, K, V> {
+        let mut cursor = self.0.cursor::<MapKeyRef<'_, K>>();
+        cursor.seek(&MapKeyRef(Some(key)), Bias::Left, &());
+        TreeMapCursor { cursor }
+    }
+
     pub fn iter_from<'a>(&'a self, from: &'a K) -> impl Iterator<Item = (&K, &V)> + '_ {
         let mut cursor = self.0.cursor::<MapKeyRef<'_, K>>();
         let from_key = MapKeyRef(Some(from));
@@ -113,6 +193,9 @@ impl<K: Clone + Debug + Ord, V: Clone + Debug> TreeMap<K, V> {
         cursor.map(|map_entry| (&map_entry.key, &map_entry.value))
     }
 
+    /// Calls `f` with the value stored for the given key, returning its result, or
+    /// returns `None` without calling `f` if the key is absent. Keys are unique, so
+    /// at most one entry is visited.
     pub fn update<F, T>(&mut self, key: &K, f: F) -> Option<T>
     where
         F: FnOnce(&mut V) -> T,
@@ -157,6 +240,10 @@ impl<K: Clone + Debug + Ord, V: Clone + Debug> TreeMap<K, V> {
         self.0.iter().map(|entry| &entry.value)
     }
 
+    pub fn snapshot(&self) -> TreeMapSnapshot<K, V> {
+        TreeMapSnapshot(self.clone())
+    }
+
     pub fn insert_tree(&mut self, other: TreeMap<K, V>) {
         let edits = other
             .iter()
@@ -182,11 +269,147 @@ where
     }
 }
 
+impl<'a, K, V> TreeMapEditBuilder<'a, K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    pub fn new() -> Self {
+        Self {
+            edits: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.edits.insert(key, PendingEdit::Insert(value));
+    }
+
+    pub fn remove(&mut self, key: K) {
+        self.edits.insert(key, PendingEdit::Remove);
+    }
+
+    /// Records an update to the value for the given key. Like [`TreeMap::update`],
+    /// this has no effect if the key is absent when the edits are applied.
+    pub fn update(&mut self, key: K, f: impl FnOnce(&mut V) + 'a) {
+        match self.edits.entry(key) {
+            btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                PendingEdit::Insert(value) => f(value),
+                PendingEdit::Remove => {}
+                PendingEdit::Update(updates) => updates.push(Box::new(f)),
+            },
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(PendingEdit::Update(vec![Box::new(f)]));
+            }
+        }
+    }
+
+    pub fn apply(self, map: &mut TreeMap<K, V>) {
+        let mut edits = Vec::with_capacity(self.edits.len());
+        let mut cursor = map.0.cursor::<MapKeyRef<'_, K>>();
+        for (key, edit) in self.edits {
+            match edit {
+                PendingEdit::Insert(value) => edits.push(Edit::Insert(MapEntry { key, value })),
+                PendingEdit::Remove => edits.push(Edit::Remove(MapKey(Some(key)))),
+                PendingEdit::Update(updates) => {
+                    if cursor.seek_forward(&MapKeyRef(Some(&key)), Bias::Left, &()) {
+                        let mut value = cursor.item().unwrap().value.clone();
+                        for update in updates {
+                            update(&mut value);
+                        }
+                        edits.push(Edit::Insert(MapEntry { key, value }));
+                    }
+                }
+            }
+        }
+        drop(cursor);
+        map.0.edit(edits, &());
+    }
+}
+
+impl<'a, K, V> Default for TreeMapEditBuilder<'a, K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Deref for TreeMapSnapshot<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    type Target = TreeMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K, V> Debug for TreeMapSnapshot<K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a, K, V> TreeMapCursor<'a, K, V>
+where
+    K: Clone + Debug + Ord,
+    V: Clone + Debug,
+{
+    pub fn key(&self) -> Option<&'a K> {
+        self.cursor.item().map(|entry| &entry.key)
+    }
+
+    pub fn value(&self) -> Option<&'a V> {
+        self.cursor.item().map(|entry| &entry.value)
+    }
+
+    /// Returns the entry under the cursor and advances past it.
+    pub fn next_value(&mut self) -> Option<(&'a K, &'a V)> {
+        let entry = self.cursor.item()?;
+        self.cursor.next(&());
+        Some((&entry.key, &entry.value))
+    }
+
+    /// Moves the cursor past the entries of the current key and returns the key
+    /// it lands on, if any.
+    pub fn seek_next_key(&mut self) -> Option<&'a K> {
+        let current = self.key()?;
+        self.cursor
+            .seek_forward(&MapKeyRef(Some(current)), Bias::Right, &());
+        self.key()
+    }
+
+    /// Moves the cursor forward to the entry for the given key, or to the first
+    /// entry after it. Keys preceding the cursor's position leave it in place.
+    /// Returns whether the cursor ends up on the given key.
+    pub fn seek_forward(&mut self, key: &K) -> bool {
+        match self.key() {
+            Some(current) if current >= key => current == key,
+            Some(_) => self
+                .cursor
+                .seek_forward(&MapKeyRef(Some(key)), Bias::Left, &()),
+            None => false,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct MapSeekTargetAdaptor<'a, T>(&'a T);
 
-impl<'a, K: Debug + Clone + Ord, T: MapSeekTarget<K>> SeekTarget<'a, MapKey<K>, MapKeyRef<'a, K>>
-    for MapSeekTargetAdaptor<'_, T>
+impl<'a, K: Debug + Clone + Ord, T: MapSeekTarget<K>>
+    SeekTarget<'a, MapSummary<K>, MapKeyRef<'a, K>> for MapSeekTargetAdaptor<'_, T>
 {
     fn cmp(&self, cursor_location: &MapKeyRef<K>, _: &()) -> Ordering {
         if let Some(key) = &cursor_location.0 {
@@ -222,10 +445,13 @@ where
     K: Clone + Debug + Ord,
     V: Clone,
 {
-    type Summary = MapKey<K>;
+    type Summary = MapSummary<K>;
 
     fn summary(&self) -> Self::Summary {
-        self.key()
+        MapSummary {
+            max_key: self.key(),
+            count: 1,
+        }
     }
 }
 
@@ -241,27 +467,46 @@ where
     }
 }
 
-impl<K> Summary for MapKey<K>
+impl<K> Summary for MapSummary<K>
 where
     K: Clone + Debug,
 {
     type Context = ();
 
     fn add_summary(&mut self, summary: &Self, _: &()) {
-        *self = summary.clone()
+        self.max_key = summary.max_key.clone();
+        self.count += summary.count;
+    }
+}
+
+impl<'a, K> Dimension<'a, MapSummary<K>> for MapKey<K>
+where
+    K: Clone + Debug + Ord,
+{
+    fn add_summary(&mut self, summary: &'a MapSummary<K>, _: &()) {
+        *self = summary.max_key.clone();
     }
 }
 
-impl<'a, K> Dimension<'a, MapKey<K>> for MapKeyRef<'a, K>
+impl<'a, K> Dimension<'a, MapSummary<K>> for MapKeyRef<'a, K>
 where
     K: Clone + Debug + Ord,
 {
-    fn add_summary(&mut self, summary: &'a MapKey<K>, _: &()) {
-        self.0 = summary.0.as_ref();
+    fn add_summary(&mut self, summary: &'a MapSummary<K>, _: &()) {
+        self.0 = summary.max_key.0.as_ref();
     }
 }
 
-impl<'a, K> SeekTarget<'a, MapKey<K>, MapKeyRef<'a, K>> for MapKeyRef<'_, K>
+impl<'a, K> Dimension<'a, MapSummary<K>> for usize
+where
+    K: Clone + Debug + Ord,
+{
+    fn add_summary(&mut self, summary: &'a MapSummary<K>, _: &()) {
+        *self += summary.count;
+    }
+}
+
+impl<'a, K> SeekTarget<'a, MapSummary<K>, MapKeyRef<'a, K>> for MapKeyRef<'_, K>
 where
     K: Clone + Debug + Ord,
 {
@@ -300,11 +545,72 @@ where
     pub fn iter(&self) -> impl Iterator<Item = &K> + '_ {
         self.0.iter().map(|(k, _)| k)
     }
+
+    /// Returns a cursor positioned on the given key, or on the first key after it
+    /// if the key is not present.
+    pub fn cursor_for_key(&self, key: &K) -> TreeMapCursor<'_, K, ()> {
+        self.0.cursor_for_key(key)
+    }
+
+    /// Returns a set containing the keys of both sets.
+    ///
+    /// The keys of `other` are spliced into a clone of `self` in a single
+    /// sorted pass, so untouched subtrees of `self` are shared with the result.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        let edits = other
+            .iter()
+            .map(|key| {
+                Edit::Insert(MapEntry {
+                    key: key.clone(),
+                    value: (),
+                })
+            })
+            .collect();
+        result.0 .0.edit(edits, &());
+        result
+    }
+
+    /// Returns a set containing the keys present in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut cursor = self.0 .0.cursor::<MapKeyRef<'_, K>>();
+        Self::from_ordered_entries(
+            other
+                .iter()
+                .filter(|key| cursor.seek_forward(&MapKeyRef(Some(*key)), Bias::Left, &()))
+                .cloned(),
+        )
+    }
+
+    /// Returns a set containing the keys of `self` that are not in `other`.
+    ///
+    /// Like [`TreeSet::union`], subtrees of `self` that contain none of the
+    /// removed keys are shared with the result.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        let edits = other
+            .iter()
+            .map(|key| Edit::Remove(MapKey(Some(key.clone()))))
+            .collect();
+        result.0 .0.edit(edits, &());
+        result
+    }
+}
+
+impl<K> Debug for TreeSet<K>
+where
+    K: Clone + Debug + Ord,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test::check_random_map_operations;
+    use rand::prelude::*;
 
     #[test]
     fn test_basic() {
@@ -353,6 +659,182 @@ mod tests {
         assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&4, &"d"), (&6, &"f")]);
     }
 
+    #[test]
+    fn test_debug() {
+        let mut map = TreeMap::default();
+        map.insert(2, "b");
+        map.insert(1, "a");
+        assert_eq!(format!("{:?}", map), r#"{1: "a", 2: "b"}"#);
+
+        let mut set = TreeSet::default();
+        set.insert("b");
+        set.insert("a");
+        assert_eq!(format!("{:?}", set), r#"{"a", "b"}"#);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = TreeSet::from_ordered_entries([1, 2, 3, 5, 8]);
+        let b = TreeSet::from_ordered_entries([2, 4, 5, 9]);
+
+        assert_eq!(
+            a.union(&b).iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 8, 9]
+        );
+        assert_eq!(
+            a.intersection(&b).iter().copied().collect::<Vec<_>>(),
+            vec![2, 5]
+        );
+        assert_eq!(
+            a.difference(&b).iter().copied().collect::<Vec<_>>(),
+            vec![1, 3, 8]
+        );
+        assert_eq!(
+            b.difference(&a).iter().copied().collect::<Vec<_>>(),
+            vec![4, 9]
+        );
+
+        let empty = TreeSet::default();
+        assert_eq!(a.union(&empty).iter().count(), 5);
+        assert_eq!(a.intersection(&empty).iter().count(), 0);
+        assert_eq!(empty.difference(&a).iter().count(), 0);
+    }
+
+    #[test]
+    fn test_random_operations() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            check_random_map_operations::<TreeMap<u32, u32>, _, _>(
+                &mut rng,
+                50,
+                |rng| rng.gen_range(0..30),
+                |rng| rng.gen(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_update() {
+        let mut map = TreeMap::from_ordered_entries([(1, 10), (2, 20), (3, 30)]);
+        assert_eq!(
+            map.update(&2, |value| {
+                *value += 1;
+                *value
+            }),
+            Some(21)
+        );
+        assert_eq!(map.update(&4, |_| unreachable!()), None::<()>);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&1, &10), (&2, &21), (&3, &30)]
+        );
+    }
+
+    #[test]
+    fn test_edit_builder() {
+        let mut map = TreeMap::from_ordered_entries([(1, 10), (2, 20), (3, 30), (4, 40)]);
+
+        let mut edits: TreeMapEditBuilder<i32, i32> = TreeMapEditBuilder::new();
+        assert!(edits.is_empty());
+        edits.update(1, |value| *value += 1);
+        edits.update(1, |value| *value *= 2);
+        edits.remove(2);
+        edits.update(2, |value| *value += 1);
+        edits.insert(5, 50);
+        edits.update(5, |value| *value += 5);
+        edits.update(4, |value| *value += 4);
+        edits.remove(4);
+        edits.update(6, |value| *value += 6);
+        edits.apply(&mut map);
+
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&1, &22), (&3, &30), (&5, &55)]
+        );
+    }
+
+    #[test]
+    fn test_snapshot() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let mut map = TreeMap::from_ordered_entries([(1, "a"), (2, "b")]);
+        let snapshot = map.snapshot();
+        assert_send_sync(&snapshot);
+
+        map.insert(3, "c");
+        map.remove(&1);
+        assert_eq!(
+            snapshot.iter().collect::<Vec<_>>(),
+            vec![(&1, &"a"), (&2, &"b")]
+        );
+
+        let handle = std::thread::spawn(move || snapshot.get(&1).copied());
+        assert_eq!(handle.join().unwrap(), Some("a"));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&2, &"b"), (&3, &"c")]);
+    }
+
+    #[test]
+    fn test_nth_and_position_of() {
+        let mut map = TreeMap::default();
+        assert_eq!(map.nth(0), None);
+        assert_eq!(map.position_of(&1), None);
+
+        for key in [8, 2, 6, 4, 0] {
+            map.insert(key, key * 10);
+        }
+        for (index, key) in [0, 2, 4, 6, 8].iter().enumerate() {
+            assert_eq!(map.nth(index), Some((key, &(key * 10))));
+            assert_eq!(map.position_of(key), Some(index));
+        }
+        assert_eq!(map.nth(5), None);
+        assert_eq!(map.position_of(&3), None);
+        assert_eq!(map.position_of(&9), None);
+
+        map.remove(&4);
+        assert_eq!(map.nth(2), Some((&6, &60)));
+        assert_eq!(map.position_of(&8), Some(3));
+    }
+
+    #[test]
+    fn test_cursor_for_key() {
+        let map = TreeMap::from_ordered_entries([(1, "a"), (3, "c"), (5, "e"), (7, "g")]);
+
+        let mut cursor = map.cursor_for_key(&2);
+        assert_eq!(cursor.key(), Some(&3));
+        assert_eq!(cursor.next_value(), Some((&3, &"c")));
+        assert_eq!(cursor.value(), Some(&"e"));
+
+        // Seeking backward leaves the cursor in place.
+        assert!(!cursor.seek_forward(&1));
+        assert_eq!(cursor.key(), Some(&5));
+
+        assert!(cursor.seek_forward(&7));
+        assert_eq!(cursor.next_value(), Some((&7, &"g")));
+        assert_eq!(cursor.next_value(), None);
+        assert!(!cursor.seek_forward(&9));
+
+        let mut cursor = map.cursor_for_key(&8);
+        assert_eq!(cursor.key(), None);
+        assert_eq!(cursor.next_value(), None);
+    }
+
+    #[test]
+    fn test_seek_next_key() {
+        let map = TreeMap::from_ordered_entries([(1, "a"), (3, "c"), (5, "e")]);
+        let mut cursor = map.cursor_for_key(&1);
+        assert_eq!(cursor.seek_next_key(), Some(&3));
+        assert_eq!(cursor.value(), Some(&"c"));
+        assert_eq!(cursor.seek_next_key(), Some(&5));
+        assert_eq!(cursor.seek_next_key(), None);
+        assert_eq!(cursor.seek_next_key(), None);
+
+        let set = TreeSet::from_ordered_entries([2, 4, 6]);
+        let mut cursor = set.cursor_for_key(&3);
+        assert_eq!(cursor.key(), Some(&4));
+        assert_eq!(cursor.seek_next_key(), Some(&6));
+        assert_eq!(cursor.seek_next_key(), None);
+    }
+
     #[test]
     fn test_iter_from() {
         let mut map = TreeMap::default();
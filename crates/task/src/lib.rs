@@ -265,4 +265,43 @@ pub enum Shell {
         /// The arguments to pass to the program.
         args: Vec<String>,
     },
+    /// Use the login shell of a remote host, connecting to it with `ssh`.
+    Remote {
+        /// The host to connect to, as `ssh` takes it.
+        host: String,
+        /// The user to log in as, instead of the one `ssh` picks.
+        #[serde(default)]
+        user: Option<String>,
+        /// Additional arguments to pass to `ssh`, such as `["-p", "2222"]`.
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl Shell {
+    /// The `ssh` arguments that connect to a [`Shell::Remote`] host.
+    pub fn ssh_args(&self) -> Option<Vec<String>> {
+        let Shell::Remote { host, user, args } = self else {
+            return None;
+        };
+        let mut ssh_args = args.clone();
+        if let Some(user) = user {
+            ssh_args.extend(["-l".to_string(), user.clone()]);
+        }
+        ssh_args.push(host.clone());
+        Some(ssh_args)
+    }
+
+    /// How a [`Shell::Remote`] host is shown, as `user@host` or `host`.
+    pub fn remote_destination(&self) -> Option<String> {
+        match self {
+            Shell::Remote {
+                host,
+                user: Some(user),
+                ..
+            } => Some(format!("{user}@{host}")),
+            Shell::Remote { host, .. } => Some(host.clone()),
+            _ => None,
+        }
+    }
 }
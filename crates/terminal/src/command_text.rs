@@ -0,0 +1,74 @@
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line},
+    term::cell::Flags,
+    Term,
+};
+
+/// The command on the cursor's line, which may wrap onto the lines around it,
+/// starting at `start_column` of its first line, where typing began after the
+/// prompt.
+pub(crate) fn command_text<T>(term: &Term<T>, start_column: usize) -> String {
+    let grid = term.grid();
+    let last_column = term.last_column();
+    let wraps = |line: i32| {
+        grid[Line(line)][last_column]
+            .flags
+            .contains(Flags::WRAPLINE)
+    };
+    let mut first_line = grid.cursor.point.line.0;
+    while first_line > term.topmost_line().0 && wraps(first_line - 1) {
+        first_line -= 1;
+    }
+    let mut last_line = grid.cursor.point.line.0;
+    while last_line < term.bottommost_line().0 && wraps(last_line) {
+        last_line += 1;
+    }
+
+    let mut text = String::new();
+    for line in first_line..=last_line {
+        let row = &grid[Line(line)];
+        let start_column = if line == first_line { start_column } else { 0 };
+        for column in start_column..term.columns() {
+            let cell = &row[Column(column)];
+            if !cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                text.push(cell.c);
+            }
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config, vte::ansi::Processor};
+    use gpui::{px, size};
+
+    use crate::TerminalSize;
+
+    use super::*;
+
+    fn write(term: &mut Term<VoidListener>, parser: &mut Processor, text: &str) {
+        for byte in text.bytes() {
+            parser.advance(term, byte);
+        }
+    }
+
+    #[test]
+    fn test_command_text() {
+        // Ten columns and three lines.
+        let dimensions = TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.)));
+        let mut term = Term::new(Config::default(), &dimensions, VoidListener);
+        let mut parser = Processor::new();
+
+        write(&mut term, &mut parser, "$ ls -la");
+        assert_eq!(command_text(&term, 2), "ls -la");
+        write(&mut term, &mut parser, "\r\n$ echo wrapped");
+        assert_eq!(command_text(&term, 2), "echo wrapped");
+        write(&mut term, &mut parser, "\x1b[A");
+        assert_eq!(command_text(&term, 2), "echo wrapped");
+    }
+}
@@ -0,0 +1,405 @@
+use std::fmt::Write as _;
+
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line},
+    selection::SelectionRange,
+    term::cell::{Cell, Flags},
+    vte::ansi::{Color as AnsiColor, NamedColor},
+    Term,
+};
+use gpui::{Hsla, Rgba};
+use serde::Deserialize;
+use theme::Theme;
+
+use crate::convert_color;
+
+/// The markup produced when copying a selection with its formatting.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyFormat {
+    /// The source of a `<pre>` block with inline styles, for pasting into HTML
+    /// documents. It is copied as plain text, not as rich text.
+    #[default]
+    Html,
+    /// Text with SGR escape sequences, for pasting into another terminal.
+    Ansi,
+}
+
+const STYLE_FLAGS: Flags = Flags::BOLD
+    .union(Flags::DIM)
+    .union(Flags::ITALIC)
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::STRIKEOUT)
+    .union(Flags::INVERSE);
+
+#[derive(Clone, Copy, PartialEq)]
+struct CellStyle {
+    fg: AnsiColor,
+    bg: AnsiColor,
+    flags: Flags,
+}
+
+impl CellStyle {
+    fn of(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: cell.flags & STYLE_FLAGS,
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        self.fg == AnsiColor::Named(NamedColor::Foreground)
+            && self.bg == AnsiColor::Named(NamedColor::Background)
+            && self.flags.is_empty()
+    }
+}
+
+/// Renders the selected cells in the given format, keeping their colors and attributes.
+pub(crate) fn format_selection<T>(
+    term: &Term<T>,
+    range: &SelectionRange,
+    format: CopyFormat,
+    theme: &Theme,
+) -> String {
+    let lines = selected_runs(term, range, true);
+    match format {
+        CopyFormat::Html => to_html(&lines, theme),
+        CopyFormat::Ansi => to_ansi(&lines),
+    }
+}
+
+/// Renders the selected cells as text with SGR escape sequences.
+pub(crate) fn ansi_text<T>(term: &Term<T>, range: &SelectionRange) -> String {
+    to_ansi(&selected_runs(term, range, true))
+}
+
+/// The selected text without formatting. Unless `join_wrapped_lines` is set, lines
+/// that wrapped at the edge of the terminal are broken where they wrapped.
+pub(crate) fn plain_text<T>(
+    term: &Term<T>,
+    range: &SelectionRange,
+    join_wrapped_lines: bool,
+) -> String {
+    selected_runs(term, range, join_wrapped_lines)
+        .iter()
+        .map(|runs| {
+            runs.iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits the selection into lines of consecutively styled text.
+fn selected_runs<T>(
+    term: &Term<T>,
+    range: &SelectionRange,
+    join_wrapped_lines: bool,
+) -> Vec<Vec<(CellStyle, String)>> {
+    let last_column = term.last_column();
+    let mut lines = Vec::new();
+    let mut runs: Vec<(CellStyle, String)> = Vec::new();
+
+    for line in range.start.line.0..=range.end.line.0 {
+        let line = Line(line);
+        let start_column = if range.is_block || line == range.start.line {
+            range.start.column
+        } else {
+            Column(0)
+        };
+        let end_column = if range.is_block || line == range.end.line {
+            range.end.column.min(last_column)
+        } else {
+            last_column
+        };
+
+        let row = &term.grid()[line];
+        // Trailing blank cells are padding rather than output, unless a background
+        // color was painted over them.
+        let mut content_end = end_column.0;
+        while content_end > start_column.0 {
+            let cell = &row[Column(content_end)];
+            if cell.c != ' ' || cell.bg != AnsiColor::Named(NamedColor::Background) {
+                break;
+            }
+            content_end -= 1;
+        }
+
+        for column in start_column.0..=content_end {
+            let cell = &row[Column(column)];
+            if cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+
+            let style = CellStyle::of(cell);
+            if runs
+                .last()
+                .map_or(true, |(run_style, _)| *run_style != style)
+            {
+                runs.push((style, String::new()));
+            }
+            let text = &mut runs.last_mut().unwrap().1;
+            text.push(cell.c);
+            if let Some(zerowidth) = cell.zerowidth() {
+                text.extend(zerowidth);
+            }
+        }
+
+        let wraps = row[last_column].flags.contains(Flags::WRAPLINE);
+        if !join_wrapped_lines
+            || range.is_block
+            || line == range.end.line
+            || !wraps
+            || end_column != last_column
+        {
+            lines.push(std::mem::take(&mut runs));
+        }
+    }
+
+    lines
+}
+
+fn to_html(lines: &[Vec<(CellStyle, String)>], theme: &Theme) -> String {
+    let foreground = convert_color(&AnsiColor::Named(NamedColor::Foreground), theme);
+    let background = convert_color(&AnsiColor::Named(NamedColor::Background), theme);
+
+    let mut html = String::new();
+    write!(
+        html,
+        "<pre style=\"font-family: monospace; color: {}; background-color: {};\">",
+        hex(foreground),
+        hex(background)
+    )
+    .ok();
+
+    for (ix, line) in lines.iter().enumerate() {
+        if ix > 0 {
+            html.push('\n');
+        }
+        for (style, text) in line {
+            if style.is_default() {
+                push_escaped(&mut html, text);
+                continue;
+            }
+
+            let (mut fg, mut bg) = (style.fg, style.bg);
+            if style.flags.contains(Flags::INVERSE) {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+
+            let mut css = String::new();
+            if fg != AnsiColor::Named(NamedColor::Foreground) {
+                write!(css, "color: {};", hex(convert_color(&fg, theme))).ok();
+            }
+            if bg != AnsiColor::Named(NamedColor::Background) {
+                write!(css, "background-color: {};", hex(convert_color(&bg, theme))).ok();
+            }
+            if style.flags.contains(Flags::BOLD) {
+                css.push_str("font-weight: bold;");
+            }
+            if style.flags.contains(Flags::DIM) {
+                css.push_str("opacity: 0.7;");
+            }
+            if style.flags.contains(Flags::ITALIC) {
+                css.push_str("font-style: italic;");
+            }
+            match (
+                style.flags.intersects(Flags::ALL_UNDERLINES),
+                style.flags.contains(Flags::STRIKEOUT),
+            ) {
+                (true, true) => css.push_str("text-decoration: underline line-through;"),
+                (true, false) => css.push_str("text-decoration: underline;"),
+                (false, true) => css.push_str("text-decoration: line-through;"),
+                (false, false) => {}
+            }
+
+            write!(html, "<span style=\"{css}\">").ok();
+            push_escaped(&mut html, text);
+            html.push_str("</span>");
+        }
+    }
+
+    html.push_str("</pre>");
+    html
+}
+
+fn to_ansi(lines: &[Vec<(CellStyle, String)>]) -> String {
+    let mut ansi = String::new();
+    for (ix, line) in lines.iter().enumerate() {
+        if ix > 0 {
+            ansi.push('\n');
+        }
+        let mut styled = false;
+        for (style, text) in line {
+            if style.is_default() {
+                if styled {
+                    ansi.push_str("\x1b[0m");
+                    styled = false;
+                }
+            } else {
+                write!(ansi, "\x1b[{}m", sgr_params(style)).ok();
+                styled = true;
+            }
+            ansi.push_str(text);
+        }
+        if styled {
+            ansi.push_str("\x1b[0m");
+        }
+    }
+    ansi
+}
+
+/// The parameters of an SGR sequence that resets the pen and then applies `style`.
+fn sgr_params(style: &CellStyle) -> String {
+    let mut params = vec!["0".to_string()];
+    for (flag, param) in [
+        (Flags::BOLD, "1"),
+        (Flags::DIM, "2"),
+        (Flags::ITALIC, "3"),
+        (Flags::ALL_UNDERLINES, "4"),
+        (Flags::INVERSE, "7"),
+        (Flags::STRIKEOUT, "9"),
+    ] {
+        if style.flags.intersects(flag) {
+            params.push(param.to_string());
+        }
+    }
+    params.extend(color_param(&style.fg, 30));
+    params.extend(color_param(&style.bg, 40));
+    params.join(";")
+}
+
+/// Encodes a color as an SGR parameter, where `base` is 30 for foreground and 40 for
+/// background colors. The theme's default colors need no parameter after a reset.
+fn color_param(color: &AnsiColor, base: u8) -> Option<String> {
+    match color {
+        AnsiColor::Named(named) => {
+            let index = match named {
+                NamedColor::Black | NamedColor::DimBlack => 0,
+                NamedColor::Red | NamedColor::DimRed => 1,
+                NamedColor::Green | NamedColor::DimGreen => 2,
+                NamedColor::Yellow | NamedColor::DimYellow => 3,
+                NamedColor::Blue | NamedColor::DimBlue => 4,
+                NamedColor::Magenta | NamedColor::DimMagenta => 5,
+                NamedColor::Cyan | NamedColor::DimCyan => 6,
+                NamedColor::White | NamedColor::DimWhite => 7,
+                NamedColor::BrightBlack => 8,
+                NamedColor::BrightRed => 9,
+                NamedColor::BrightGreen => 10,
+                NamedColor::BrightYellow => 11,
+                NamedColor::BrightBlue => 12,
+                NamedColor::BrightMagenta => 13,
+                NamedColor::BrightCyan => 14,
+                NamedColor::BrightWhite => 15,
+                _ => return None,
+            };
+            Some(if index < 8 {
+                (base + index).to_string()
+            } else {
+                (base + 60 + index - 8).to_string()
+            })
+        }
+        AnsiColor::Indexed(index) => Some(format!("{};5;{}", base + 8, index)),
+        AnsiColor::Spec(rgb) => Some(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+fn hex(color: Hsla) -> String {
+    let color = Rgba::from(color);
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        (color.r * 255.).round() as u8,
+        (color.g * 255.).round() as u8,
+        (color.b * 255.).round() as u8
+    )
+}
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::Point,
+        term::Config,
+        vte::ansi::{Processor, Rgb},
+    };
+    use gpui::{px, size};
+
+    use crate::TerminalSize;
+
+    use super::*;
+
+    fn run(style: CellStyle, text: &str) -> (CellStyle, String) {
+        (style, text.to_string())
+    }
+
+    #[test]
+    fn test_ansi_output() {
+        let plain = CellStyle {
+            fg: AnsiColor::Named(NamedColor::Foreground),
+            bg: AnsiColor::Named(NamedColor::Background),
+            flags: Flags::empty(),
+        };
+        let red_bold = CellStyle {
+            fg: AnsiColor::Named(NamedColor::Red),
+            flags: Flags::BOLD,
+            ..plain
+        };
+        let true_color = CellStyle {
+            fg: AnsiColor::Spec(Rgb { r: 1, g: 2, b: 3 }),
+            bg: AnsiColor::Named(NamedColor::BrightBlue),
+            flags: Flags::ITALIC | Flags::UNDERLINE,
+        };
+
+        let lines = vec![
+            vec![run(plain, "$ "), run(red_bold, "error"), run(plain, ": no")],
+            vec![run(true_color, "x")],
+        ];
+        assert_eq!(
+            to_ansi(&lines),
+            "$ \x1b[0;1;31merror\x1b[0m: no\n\x1b[0;3;4;38;2;1;2;3;104mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_plain_text() {
+        // Ten columns and three lines.
+        let dimensions = TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.)));
+        let mut term = Term::new(Config::default(), &dimensions, VoidListener);
+        let mut parser = Processor::new();
+        for byte in "$ echo 0123456789\r\nok".bytes() {
+            parser.advance(&mut term, byte);
+        }
+        let range = SelectionRange::new(
+            Point::new(Line(0), Column(0)),
+            Point::new(Line(2), Column(9)),
+            false,
+        );
+        assert_eq!(plain_text(&term, &range, true), "$ echo 0123456789\nok");
+        assert_eq!(plain_text(&term, &range, false), "$ echo 012\n3456789\nok");
+    }
+
+    #[test]
+    fn test_html_escaping() {
+        let mut html = String::new();
+        push_escaped(&mut html, "<a href=\"x\">&</a>");
+        assert_eq!(html, "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    }
+}
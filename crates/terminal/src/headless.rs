@@ -0,0 +1,88 @@
+use futures::channel::oneshot;
+use regex::Regex;
+
+use crate::TextSnapshot;
+
+/// Calls to [`crate::Terminal::wait_for_output`] that are waiting for the visible
+/// text to match their pattern.
+#[derive(Default)]
+pub(crate) struct OutputWaiters {
+    waiters: Vec<(Regex, oneshot::Sender<TextSnapshot>)>,
+}
+
+impl OutputWaiters {
+    /// Waits for a snapshot that matches `pattern`, resolving right away if
+    /// `snapshot` does. Fails once the waiters are dropped, as the terminal exits.
+    pub fn wait_for(
+        &mut self,
+        pattern: Regex,
+        snapshot: TextSnapshot,
+    ) -> oneshot::Receiver<TextSnapshot> {
+        let (tx, rx) = oneshot::channel();
+        if matches(&pattern, &snapshot) {
+            tx.send(snapshot).ok();
+        } else {
+            self.waiters.push((pattern, tx));
+        }
+        rx
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    /// Resolves the waiters whose pattern matches `snapshot`.
+    pub fn resolve(&mut self, snapshot: &TextSnapshot) {
+        let (matched, waiting) = std::mem::take(&mut self.waiters)
+            .into_iter()
+            .filter(|(_, tx)| !tx.is_canceled())
+            .partition::<Vec<_>, _>(|(pattern, _)| matches(pattern, snapshot));
+        self.waiters = waiting;
+        for (_, tx) in matched {
+            tx.send(snapshot.clone()).ok();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.waiters.clear();
+    }
+}
+
+fn matches(pattern: &Regex, snapshot: &TextSnapshot) -> bool {
+    pattern.is_match(&snapshot.lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    fn snapshot(text: &str) -> TextSnapshot {
+        TextSnapshot {
+            lines: text.lines().map(str::to_string).collect(),
+            ..TextSnapshot::default()
+        }
+    }
+
+    #[test]
+    fn test_output_waiters() {
+        let mut waiters = OutputWaiters::default();
+        let mut ready = waiters.wait_for(Regex::new(r"^\$").unwrap(), snapshot("$ "));
+        assert_eq!(ready.try_recv().unwrap(), Some(snapshot("$ ")));
+        assert!(waiters.is_empty());
+
+        let mut done = waiters.wait_for(Regex::new(r"done\n\$").unwrap(), snapshot("$ "));
+        let later = waiters.wait_for(Regex::new("never").unwrap(), snapshot("$ "));
+        waiters.resolve(&snapshot("$ make\nbuilding"));
+        assert_eq!(done.try_recv().unwrap(), None);
+        waiters.resolve(&snapshot("$ make\nbuilding\ndone\n$ "));
+        assert_eq!(
+            done.try_recv().unwrap(),
+            Some(snapshot("$ make\nbuilding\ndone\n$ "))
+        );
+
+        waiters.clear();
+        assert!(later.now_or_never().unwrap().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt::Write as _;
+
+use crate::ProcessInfo;
+
+/// What a terminal is running and the state the program put it in, to compare how
+/// a program behaves here with how it behaves in other terminal emulators.
+#[derive(Clone, Debug, Default)]
+pub struct TerminalInspection {
+    /// The program the terminal was started with, usually a shell.
+    pub shell: Option<ProcessInfo>,
+    /// The program the shell launched that is now in the foreground, if any.
+    pub foreground: Option<ProcessInfo>,
+    /// The `TERM` the program was started with.
+    pub term_variable: Option<String>,
+    pub columns: usize,
+    pub rows: usize,
+    /// The names of the modes the program turned on, such as `BRACKETED_PASTE`.
+    pub modes: Vec<&'static str>,
+}
+
+impl TerminalInspection {
+    /// A plain text report, one detail per line.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        write_process(&mut report, "Program", self.shell.as_ref());
+        if let Some(foreground) = &self.foreground {
+            report.push('\n');
+            write_process(&mut report, "Foreground program", Some(foreground));
+        }
+        report.push('\n');
+        writeln!(
+            report,
+            "TERM: {}",
+            self.term_variable.as_deref().unwrap_or("(not set)")
+        )
+        .ok();
+        writeln!(report, "Size: {} columns, {} rows", self.columns, self.rows).ok();
+        if self.modes.is_empty() {
+            writeln!(report, "Modes: (none)").ok();
+        } else {
+            writeln!(report, "Modes:").ok();
+            for mode in &self.modes {
+                writeln!(report, "  {mode}").ok();
+            }
+        }
+        report
+    }
+}
+
+fn write_process(report: &mut String, label: &str, process: Option<&ProcessInfo>) {
+    let Some(process) = process else {
+        writeln!(report, "{label}: (unknown)").ok();
+        return;
+    };
+    writeln!(report, "{label}: {}", process.name).ok();
+    writeln!(report, "  PID: {}", process.pid).ok();
+    writeln!(report, "  Arguments: {}", process.argv.join(" ")).ok();
+    writeln!(report, "  Working directory: {}", process.cwd.display()).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let inspection = TerminalInspection {
+            shell: Some(ProcessInfo {
+                name: "zsh".into(),
+                pid: 42,
+                cwd: PathBuf::from("/home/me"),
+                argv: vec!["-zsh".into(), "-l".into()],
+            }),
+            foreground: None,
+            term_variable: Some("xterm-256color".into()),
+            columns: 80,
+            rows: 24,
+            modes: vec!["SHOW_CURSOR", "BRACKETED_PASTE"],
+        };
+        assert_eq!(
+            inspection.report(),
+            "Program: zsh\n  PID: 42\n  Arguments: -zsh -l\n  Working directory: /home/me\n\n\
+             TERM: xterm-256color\nSize: 80 columns, 24 rows\nModes:\n  SHOW_CURSOR\n  BRACKETED_PASTE\n"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use alacritty_terminal::vte::ansi::Rgb as AlacRgb;
-use gpui::Rgba;
+use gpui::{Hsla, Rgba};
 
 //Convenience method to convert from a GPUI color to an alacritty Rgb
 pub fn to_alac_rgb(color: impl Into<Rgba>) -> AlacRgb {
@@ -9,3 +9,96 @@ pub fn to_alac_rgb(color: impl Into<Rgba>) -> AlacRgb {
     let b = ((color.b * color.a) * 255.) as u8;
     AlacRgb { r, g, b }
 }
+
+/// Returns `fg` with its lightness changed as little as possible so that its contrast
+/// ratio against `bg` is at least `minimum_ratio`, as defined by WCAG 2. Ratios range
+/// from 1 (no contrast) to 21 (black on white). If the ratio can't be met, the color
+/// with the most contrast is returned.
+pub fn ensure_minimum_contrast(fg: Hsla, bg: Hsla, minimum_ratio: f32) -> Hsla {
+    let bg_luminance = relative_luminance(bg);
+    let ratio = |color: Hsla| contrast_ratio(relative_luminance(color), bg_luminance);
+    if ratio(fg) >= minimum_ratio {
+        return fg;
+    }
+
+    let with_lightness = |l: f32| Hsla { l, ..fg };
+    let target = if ratio(with_lightness(1.)) >= ratio(with_lightness(0.)) {
+        1.
+    } else {
+        0.
+    };
+    if ratio(with_lightness(target)) < minimum_ratio {
+        return with_lightness(target);
+    }
+
+    // Luminance changes monotonically with lightness, so search for the lightness
+    // closest to the original one that still meets the ratio.
+    let (mut low, mut high) = (fg.l, target);
+    for _ in 0..16 {
+        let mid = (low + high) / 2.;
+        if ratio(with_lightness(mid)) >= minimum_ratio {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    with_lightness(high)
+}
+
+fn relative_luminance(color: Hsla) -> f32 {
+    let color = Rgba::from(color);
+    let channel = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use gpui::{black, hsla, white};
+
+    use super::*;
+
+    fn ratio(a: Hsla, b: Hsla) -> f32 {
+        contrast_ratio(relative_luminance(a), relative_luminance(b))
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((ratio(black(), white()) - 21.).abs() < 0.01);
+        assert!((ratio(white(), white()) - 1.).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ensure_minimum_contrast() {
+        let dark_gray = hsla(0., 0., 0.2, 1.);
+        let background = hsla(0., 0., 0.1, 1.);
+
+        // Already readable colors are left alone.
+        assert_eq!(ensure_minimum_contrast(white(), background, 4.5), white());
+
+        // Dark text on a dark background is lightened just enough.
+        let adjusted = ensure_minimum_contrast(dark_gray, background, 4.5);
+        assert!(adjusted.l > dark_gray.l);
+        assert!(ratio(adjusted, background) >= 4.5);
+        assert!(ratio(adjusted, background) < 4.6);
+        assert_eq!((adjusted.h, adjusted.s, adjusted.a), (0., 0., 1.));
+
+        // Light text on a light background is darkened.
+        let light_background = hsla(0., 0., 0.9, 1.);
+        let adjusted = ensure_minimum_contrast(hsla(0.6, 0.5, 0.8, 1.), light_background, 4.5);
+        assert!(adjusted.l < 0.8);
+        assert!(ratio(adjusted, light_background) >= 4.5);
+
+        // Unreachable ratios give the most contrast possible.
+        assert_eq!(ensure_minimum_contrast(dark_gray, background, 21.).l, 1.);
+    }
+}
@@ -0,0 +1,668 @@
+use std::ops::RangeInclusive;
+
+use alacritty_terminal::{
+    grid::{Dimensions, Scroll},
+    index::{Column, Line, Point as AlacPoint},
+    term::{cell::Flags, TermMode},
+    Term,
+};
+
+use crate::shell_integration::ShellMark;
+
+/// How many lines past the history limit alacritty is allowed to keep, so that
+/// lines only leave the top of the history when [`PromptMarks::trim_history`]
+/// drops them and counts them. Output that scrolls more than this between two
+/// trims makes alacritty drop lines itself, and the marks are cleared.
+pub(crate) const HISTORY_HEADROOM: usize = 4096;
+
+/// A mark the shell sent, and the cell the cursor was on when it did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PlacedMark {
+    mark: ShellMark,
+    /// Counted from the first line the terminal ever had, so that it doesn't
+    /// change as lines leave the top of the history.
+    line: usize,
+    column: usize,
+    /// Whether the output that follows a [`ShellMark::CommandExecuted`] mark is
+    /// collapsed onto one row of the view.
+    collapsed: bool,
+}
+
+/// The output of a command, from where it was executed to where it finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CommandOutput {
+    /// The index of the command's [`ShellMark::CommandExecuted`] mark.
+    executed: usize,
+    start: AlacPoint,
+    /// Exclusive, and `None` while the command runs.
+    end: Option<AlacPoint>,
+}
+
+impl CommandOutput {
+    /// The lines collapsing the output hides. The lines the command was typed on
+    /// and the next prompt is drawn on are kept, even when the output shares them.
+    fn lines(&self) -> Option<RangeInclusive<Line>> {
+        let end = self.end?;
+        let first = if self.start.column.0 > 0 {
+            self.start.line + 1
+        } else {
+            self.start.line
+        };
+        let last = end.line - 1;
+        (first <= last).then_some(first..=last)
+    }
+}
+
+/// The prompts and commands the shell's integration marked with OSC 133, held
+/// against the grid lines they were sent on.
+///
+/// The PTY reader parses the output itself and places each mark as the parser
+/// reaches it, then trims the history back to its limit, so that it knows how
+/// far lines moved. Shared between the reader and the terminal, and only locked
+/// while the terminal's lock is held.
+pub(crate) struct PromptMarks {
+    marks: Vec<PlacedMark>,
+    lines_scrolled_off: usize,
+    history_limit: usize,
+}
+
+impl PromptMarks {
+    pub fn new(history_limit: usize) -> Self {
+        Self {
+            marks: Vec::new(),
+            lines_scrolled_off: 0,
+            history_limit,
+        }
+    }
+
+    /// Marks the cell the cursor is on. Full-screen programs don't draw prompts,
+    /// so marks sent on the alternate screen are ignored.
+    pub fn push<T>(&mut self, mark: ShellMark, term: &Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+        let cursor = term.grid().cursor.point;
+        self.marks.push(PlacedMark {
+            mark,
+            line: self.line_from_start(term, cursor.line),
+            column: cursor.column.0,
+            collapsed: false,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Accounts for `lines` leaving the top of the history, dropping the marks
+    /// on them.
+    pub fn scroll_off(&mut self, lines: usize) {
+        self.lines_scrolled_off += lines;
+        let lines_scrolled_off = self.lines_scrolled_off;
+        self.marks
+            .retain(|placed| placed.line >= lines_scrolled_off);
+    }
+
+    /// Trims the history back to its limit, if output grew it past that.
+    pub fn trim_history<T>(&mut self, term: &mut Term<T>) {
+        let history_size = term.grid().history_size();
+        if history_size >= self.history_limit + HISTORY_HEADROOM {
+            // alacritty may have dropped lines, and doesn't say how many.
+            self.marks.clear();
+        }
+        if history_size <= self.history_limit {
+            return;
+        }
+        let grid = term.grid_mut();
+        grid.update_history(self.history_limit);
+        grid.update_history(self.history_limit + HISTORY_HEADROOM);
+        let topmost_line = term.topmost_line();
+        let vi_mode_cursor = &mut term.vi_mode_cursor.point;
+        vi_mode_cursor.line = vi_mode_cursor.line.max(topmost_line);
+        self.scroll_off(history_size - self.history_limit);
+    }
+
+    /// Changes the history limit, trimming the history to it. alacritty's limit
+    /// should be set to `history_limit` plus [`HISTORY_HEADROOM`] after this.
+    pub fn set_history_limit<T>(&mut self, history_limit: usize, term: &mut Term<T>) {
+        if term.mode().contains(TermMode::ALT_SCREEN) && history_limit < self.history_limit {
+            // The primary screen's history is out of reach, and alacritty trims it.
+            self.marks.clear();
+        }
+        self.history_limit = history_limit;
+        self.trim_history(term);
+    }
+
+    /// Resizes the terminal with `resize`, following the marks as their lines
+    /// rewrap. Each mark is found again by its logical line, counted up from the
+    /// cursor's, and its offset into it. Marks below the cursor's line, and every
+    /// mark when the primary screen rewraps behind the alternate one, are dropped.
+    pub fn resize<T>(&mut self, term: &mut Term<T>, resize: impl FnOnce(&mut Term<T>)) {
+        if term.mode().contains(TermMode::ALT_SCREEN) || self.marks.is_empty() {
+            self.marks.clear();
+            resize(term);
+            return;
+        }
+
+        let columns = term.columns();
+        let cursor_line = term.grid().cursor.point.line;
+        let starts = logical_line_starts(term);
+        let anchors = self
+            .marks
+            .iter()
+            .filter_map(|placed| {
+                let point = self.point(placed, term);
+                if point.line > cursor_line {
+                    return None;
+                }
+                let logical_line = starts.partition_point(|&start| start > point.line);
+                let start = starts[logical_line];
+                let offset = (point.line.0 - start.0) as usize * columns + point.column.0;
+                Some((*placed, logical_line, offset))
+            })
+            .collect::<Vec<_>>();
+
+        resize(term);
+
+        let columns = term.columns();
+        let starts = logical_line_starts(term);
+        self.marks = anchors
+            .into_iter()
+            .filter_map(|(placed, logical_line, offset)| {
+                let start = *starts.get(logical_line)?;
+                let end = match logical_line.checked_sub(1) {
+                    Some(next_line) => Line(starts[next_line].0 - 1),
+                    None => term.bottommost_line(),
+                };
+                let mut point = AlacPoint::new(
+                    Line(start.0 + (offset / columns) as i32),
+                    Column(offset % columns),
+                );
+                if point.line > end {
+                    point = AlacPoint::new(end, term.last_column());
+                }
+                Some(PlacedMark {
+                    line: self.line_from_start(term, point.line),
+                    column: point.column.0,
+                    ..placed
+                })
+            })
+            .collect();
+    }
+
+    /// The line of the last prompt above `line`.
+    pub fn previous_prompt<T>(&self, term: &Term<T>, line: Line) -> Option<Line> {
+        self.prompt_lines(term)
+            .filter(|&prompt| prompt < line)
+            .max()
+    }
+
+    /// The line of the first prompt below `line`.
+    pub fn next_prompt<T>(&self, term: &Term<T>, line: Line) -> Option<Line> {
+        self.prompt_lines(term)
+            .filter(|&prompt| prompt > line)
+            .min()
+    }
+
+    /// The line of the last command output boundary above `line`: where a
+    /// command's output starts or ends.
+    pub fn previous_boundary<T>(&self, term: &Term<T>, line: Line) -> Option<Line> {
+        self.boundary_lines(term)
+            .filter(|&boundary| boundary < line)
+            .max()
+    }
+
+    /// The line of the first command output boundary below `line`.
+    pub fn next_boundary<T>(&self, term: &Term<T>, line: Line) -> Option<Line> {
+        self.boundary_lines(term)
+            .filter(|&boundary| boundary > line)
+            .min()
+    }
+
+    /// Where the output of the last finished command starts, and where it ends,
+    /// exclusive, if it wrote any. Integrations that don't mark the end of a
+    /// command end its output at the next prompt.
+    pub fn last_command_output<T>(&self, term: &Term<T>) -> Option<(AlacPoint, AlacPoint)> {
+        let output = self
+            .command_outputs(term)
+            .filter(|output| output.end.is_some())
+            .last()?;
+        let end = output.end?;
+        (output.start < end).then_some((output.start, end))
+    }
+
+    /// Collapses the output of the last finished command that starts at or above
+    /// `line`, or expands it when it is collapsed. Returns whether there was one.
+    pub fn toggle_output<T>(&mut self, term: &Term<T>, line: Line) -> bool {
+        let executed = self
+            .command_outputs(term)
+            .filter(|output| output.lines().map_or(false, |lines| *lines.start() <= line))
+            .last()
+            .map(|output| output.executed);
+        let Some(executed) = executed else {
+            return false;
+        };
+        let placed = &mut self.marks[executed];
+        placed.collapsed = !placed.collapsed;
+        true
+    }
+
+    /// Expands the collapsed output `line` is hidden in, if any.
+    pub fn expand_output<T>(&mut self, term: &Term<T>, line: Line) {
+        let executed = self
+            .command_outputs(term)
+            .filter(|output| output.lines().map_or(false, |lines| lines.contains(&line)))
+            .map(|output| output.executed)
+            .collect::<Vec<_>>();
+        for executed in executed {
+            self.marks[executed].collapsed = false;
+        }
+    }
+
+    pub fn expand_all(&mut self) {
+        for placed in &mut self.marks {
+            placed.collapsed = false;
+        }
+    }
+
+    /// The lines on each row of the view, from the top: one line, or all the lines
+    /// of a collapsed output, which share a row. Empty when every row shows one
+    /// line, `display_offset` lines up from the grid line of the same index.
+    ///
+    /// The rows end at the bottom line alacritty would show. When the history runs
+    /// out above them, the rows below it fill the view instead.
+    pub fn view_rows<T>(&self, term: &Term<T>) -> Vec<RangeInclusive<Line>> {
+        let collapsed = self.collapsed_lines(term);
+        if collapsed.is_empty() {
+            return Vec::new();
+        }
+        let row_of = |line: Line| {
+            collapsed
+                .iter()
+                .find(|lines| lines.contains(&line))
+                .cloned()
+                .unwrap_or(line..=line)
+        };
+
+        let screen_lines = term.screen_lines();
+        let mut rows = Vec::with_capacity(screen_lines);
+        let mut line = display_bottom_line(term);
+        while rows.len() < screen_lines && line >= term.topmost_line() {
+            let row = row_of(line);
+            line = *row.start() - 1;
+            rows.push(row);
+        }
+        rows.reverse();
+        let mut line = rows.last().map_or(line, |row| *row.end() + 1);
+        while rows.len() < screen_lines && line <= term.bottommost_line() {
+            let row = row_of(line);
+            line = *row.end() + 1;
+            rows.push(row);
+        }
+
+        if rows.iter().all(|row| row.start() == row.end()) {
+            rows.clear();
+        }
+        rows
+    }
+
+    /// The first line on the top row of the view.
+    pub fn top_line<T>(&self, term: &Term<T>) -> Line {
+        self.view_rows(term)
+            .first()
+            .map_or(Line(-(term.grid().display_offset() as i32)), |row| {
+                *row.start()
+            })
+    }
+
+    /// The last line on the bottom row of the view.
+    pub fn bottom_line<T>(&self, term: &Term<T>) -> Line {
+        self.view_rows(term)
+            .last()
+            .map_or(display_bottom_line(term), |row| *row.end())
+    }
+
+    /// The display offset that shows `line` on the top row, or as close to it as
+    /// the history allows.
+    pub fn display_offset_for<T>(&self, term: &Term<T>, line: Line) -> usize {
+        let collapsed = self.collapsed_lines(term);
+        let mut bottom = line;
+        let mut next_line = line;
+        for _ in 0..term.screen_lines() {
+            if next_line > term.bottommost_line() {
+                break;
+            }
+            let row = collapsed
+                .iter()
+                .find(|lines| lines.contains(&next_line))
+                .cloned()
+                .unwrap_or(next_line..=next_line);
+            bottom = *row.start();
+            next_line = *row.end() + 1;
+        }
+        let display_offset = term.screen_lines() as i32 - 1 - bottom.0;
+        display_offset.clamp(0, term.grid().history_size() as i32) as usize
+    }
+
+    /// Scrolls the view off a collapsed output when its bottom row would fall
+    /// inside it: up to the output's row when the view was scrolled up from
+    /// `previous_display_offset`, and down past the output otherwise.
+    pub fn skip_collapsed<T>(&self, term: &mut Term<T>, previous_display_offset: usize) {
+        let bottom = display_bottom_line(term);
+        let Some(lines) = self
+            .collapsed_lines(term)
+            .into_iter()
+            .find(|lines| *lines.start() < bottom && bottom <= *lines.end())
+        else {
+            return;
+        };
+        let target = if term.grid().display_offset() > previous_display_offset {
+            *lines.start()
+        } else {
+            *lines.end() + 1
+        };
+        term.scroll_display(Scroll::Delta(bottom.0 - target.0));
+    }
+
+    /// The commands' outputs, in the order they were run.
+    fn command_outputs<'a, T>(
+        &'a self,
+        term: &'a Term<T>,
+    ) -> impl Iterator<Item = CommandOutput> + 'a {
+        self.marks
+            .iter()
+            .enumerate()
+            .filter(|(_, placed)| placed.mark == ShellMark::CommandExecuted)
+            .map(move |(executed, placed)| {
+                // The output ends at the next mark, unless the shell moved on to
+                // another prompt or command without finishing this one.
+                let end = self.marks.get(executed + 1).filter(|next| {
+                    matches!(
+                        next.mark,
+                        ShellMark::CommandFinished(_) | ShellMark::PromptStart
+                    )
+                });
+                CommandOutput {
+                    executed,
+                    start: self.point(placed, term),
+                    end: end.map(|end| self.point(end, term)),
+                }
+            })
+    }
+
+    /// The lines of the collapsed outputs, from the top. Collapsed outputs are on
+    /// the primary screen, and none are shown over the alternate one.
+    fn collapsed_lines<T>(&self, term: &Term<T>) -> Vec<RangeInclusive<Line>> {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return Vec::new();
+        }
+        self.command_outputs(term)
+            .filter(|output| self.marks[output.executed].collapsed)
+            .filter_map(|output| output.lines())
+            .collect()
+    }
+
+    fn boundary_lines<'a, T>(&'a self, term: &'a Term<T>) -> impl Iterator<Item = Line> + 'a {
+        self.marks
+            .iter()
+            .filter(|placed| {
+                matches!(
+                    placed.mark,
+                    ShellMark::CommandExecuted | ShellMark::CommandFinished(_)
+                )
+            })
+            .map(|placed| self.point(placed, term).line)
+    }
+
+    fn prompt_lines<'a, T>(&'a self, term: &'a Term<T>) -> impl Iterator<Item = Line> + 'a {
+        self.marks
+            .iter()
+            .filter(|placed| placed.mark == ShellMark::PromptStart)
+            .map(|placed| self.point(placed, term).line)
+    }
+
+    fn point<T>(&self, placed: &PlacedMark, term: &Term<T>) -> AlacPoint {
+        let scrollback_line = placed.line - self.lines_scrolled_off;
+        AlacPoint::new(
+            Line(scrollback_line as i32 - term.grid().history_size() as i32),
+            Column(placed.column),
+        )
+    }
+
+    fn line_from_start<T>(&self, term: &Term<T>, line: Line) -> usize {
+        self.lines_scrolled_off + (line.0 + term.grid().history_size() as i32) as usize
+    }
+}
+
+/// The grid line alacritty shows on the bottom row of the view, where the rows
+/// of the view end unless the history runs out above them.
+fn display_bottom_line<T>(term: &Term<T>) -> Line {
+    Line(term.screen_lines() as i32 - 1 - term.grid().display_offset() as i32)
+}
+
+/// The lines that begin a logical line, rather than continue a wrapped one, from
+/// the cursor's logical line up to the top of the history.
+fn logical_line_starts<T>(term: &Term<T>) -> Vec<Line> {
+    let grid = term.grid();
+    let last_column = term.last_column();
+    let topmost_line = term.topmost_line().0;
+    let wraps = |line: i32| {
+        grid[Line(line)][last_column]
+            .flags
+            .contains(Flags::WRAPLINE)
+    };
+    (topmost_line..=grid.cursor.point.line.0)
+        .rev()
+        .filter(|&line| line == topmost_line || !wraps(line - 1))
+        .map(Line)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config, vte::ansi::Processor};
+    use gpui::{px, size};
+
+    use crate::TerminalSize;
+
+    use super::*;
+
+    const HISTORY_LIMIT: usize = 4;
+
+    /// Ten columns and three lines, with room for four lines of history.
+    fn new_term() -> (Term<VoidListener>, PromptMarks) {
+        let config = Config {
+            scrolling_history: HISTORY_LIMIT + HISTORY_HEADROOM,
+            ..Config::default()
+        };
+        let dimensions = TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.)));
+        (
+            Term::new(config, &dimensions, VoidListener),
+            PromptMarks::new(HISTORY_LIMIT),
+        )
+    }
+
+    fn write(term: &mut Term<VoidListener>, marks: &mut PromptMarks, text: &str) {
+        let mut parser = Processor::new();
+        for byte in text.bytes() {
+            parser.advance(term, byte);
+        }
+        marks.trim_history(term);
+    }
+
+    /// Writes a prompt and a command with its output, marked as a shell does.
+    fn run_command(term: &mut Term<VoidListener>, marks: &mut PromptMarks, output: &str) {
+        marks.push(ShellMark::PromptStart, term);
+        write(term, marks, "$ ");
+        marks.push(ShellMark::CommandStart, term);
+        write(term, marks, "cmd\r\n");
+        marks.push(ShellMark::CommandExecuted, term);
+        write(term, marks, output);
+        marks.push(ShellMark::CommandFinished(Some(0)), term);
+    }
+
+    #[test]
+    fn test_prompts_follow_trimmed_history() {
+        let (mut term, mut marks) = new_term();
+        run_command(&mut term, &mut marks, "one\r\n");
+        run_command(&mut term, &mut marks, "two\r\n");
+        marks.push(ShellMark::PromptStart, &term);
+        write(&mut term, &mut marks, "$ ");
+
+        // Five lines were written to a three line screen.
+        assert_eq!(term.grid().history_size(), 2);
+        assert_eq!(marks.previous_prompt(&term, Line(2)), Some(Line(0)));
+        assert_eq!(marks.previous_prompt(&term, Line(0)), Some(Line(-2)));
+        assert_eq!(marks.next_prompt(&term, Line(-2)), Some(Line(0)));
+        assert_eq!(marks.next_prompt(&term, Line(0)), Some(Line(2)));
+        assert_eq!(marks.next_prompt(&term, Line(2)), None);
+
+        // Four more lines fill the history, and two go past its limit, taking
+        // the first prompt with them.
+        write(&mut term, &mut marks, "\r\n\r\n\r\n\r\n");
+        assert_eq!(term.grid().history_size(), HISTORY_LIMIT);
+        assert_eq!(marks.previous_prompt(&term, Line(0)), Some(Line(-2)));
+        assert_eq!(marks.previous_prompt(&term, Line(-2)), Some(Line(-4)));
+        assert_eq!(marks.previous_prompt(&term, Line(-4)), None);
+
+        marks.scroll_off(term.grid().history_size());
+        assert_eq!(marks.previous_prompt(&term, Line(2)), None);
+    }
+
+    #[test]
+    fn test_last_command_output() {
+        let (mut term, mut marks) = new_term();
+        assert_eq!(marks.last_command_output(&term), None);
+
+        run_command(&mut term, &mut marks, "out\r\nput\r\n");
+        marks.push(ShellMark::PromptStart, &term);
+        write(&mut term, &mut marks, "$ ");
+        marks.push(ShellMark::CommandStart, &term);
+        write(&mut term, &mut marks, "sleep\r\n");
+        marks.push(ShellMark::CommandExecuted, &term);
+
+        // The running command has no output yet, so the finished one's is used.
+        assert_eq!(
+            marks.last_command_output(&term),
+            Some((
+                AlacPoint::new(Line(-1), Column(0)),
+                AlacPoint::new(Line(1), Column(0))
+            ))
+        );
+
+        // A command with no output has nothing to select.
+        marks.push(ShellMark::CommandFinished(None), &term);
+        assert_eq!(marks.last_command_output(&term), None);
+    }
+
+    #[test]
+    fn test_command_output_boundaries() {
+        let (mut term, mut marks) = new_term();
+        run_command(&mut term, &mut marks, "one\r\n");
+        run_command(&mut term, &mut marks, "two\r\n");
+        marks.push(ShellMark::PromptStart, &term);
+        write(&mut term, &mut marks, "$ ");
+
+        // The outputs start on lines -1 and 1, and end on lines 0 and 2.
+        assert_eq!(marks.previous_boundary(&term, Line(2)), Some(Line(1)));
+        assert_eq!(marks.previous_boundary(&term, Line(1)), Some(Line(0)));
+        assert_eq!(marks.previous_boundary(&term, Line(-1)), None);
+        assert_eq!(marks.next_boundary(&term, Line(-2)), Some(Line(-1)));
+        assert_eq!(marks.next_boundary(&term, Line(0)), Some(Line(1)));
+        assert_eq!(marks.next_boundary(&term, Line(2)), None);
+    }
+
+    #[test]
+    fn test_collapsed_output_shares_a_row() {
+        let (mut term, mut marks) = new_term();
+        run_command(&mut term, &mut marks, "a\r\nb\r\nc\r\nd\r\ne\r\n");
+        marks.push(ShellMark::PromptStart, &term);
+        write(&mut term, &mut marks, "$ ");
+        assert_eq!(term.grid().history_size(), HISTORY_LIMIT);
+        assert_eq!(marks.view_rows(&term), Vec::new());
+        assert_eq!(marks.display_offset_for(&term, Line(-4)), 4);
+
+        // The output is on lines -3 to 1, and the next prompt on line 2.
+        assert!(marks.toggle_output(&term, Line(2)));
+        let rows = vec![Line(-4)..=Line(-4), Line(-3)..=Line(1), Line(2)..=Line(2)];
+        assert_eq!(marks.view_rows(&term), rows);
+        assert_eq!(marks.top_line(&term), Line(-4));
+        assert_eq!(marks.bottom_line(&term), Line(2));
+        assert_eq!(marks.display_offset_for(&term, Line(-4)), 0);
+
+        // Scrolling up into the output finds nothing more above it to show.
+        term.scroll_display(Scroll::Delta(1));
+        marks.skip_collapsed(&mut term, 0);
+        assert_eq!(marks.view_rows(&term), rows);
+
+        // Scrolling down from there skips the rest of the output.
+        let display_offset = term.grid().display_offset();
+        term.scroll_display(Scroll::Delta(-1));
+        marks.skip_collapsed(&mut term, display_offset);
+        assert_eq!(term.grid().display_offset(), 0);
+
+        marks.expand_output(&term, Line(0));
+        assert_eq!(marks.view_rows(&term), Vec::new());
+        assert!(marks.toggle_output(&term, Line(2)));
+        marks.expand_all();
+        assert_eq!(marks.view_rows(&term), Vec::new());
+
+        // Outputs that start below the line aren't toggled.
+        assert!(!marks.toggle_output(&term, Line(-4)));
+    }
+
+    #[test]
+    fn test_marks_follow_rewrapped_lines() {
+        let (mut term, mut marks) = new_term();
+        run_command(&mut term, &mut marks, "0123456789abcde\r\n");
+        marks.push(ShellMark::PromptStart, &term);
+        write(&mut term, &mut marks, "$ ");
+        // The output wrapped onto two lines.
+        assert_eq!(
+            marks.last_command_output(&term),
+            Some((
+                AlacPoint::new(Line(0), Column(0)),
+                AlacPoint::new(Line(2), Column(0))
+            ))
+        );
+
+        // Twenty columns unwrap it onto one line.
+        let wide = TerminalSize::new(px(10.), px(10.), size(px(200.), px(30.)));
+        marks.resize(&mut term, |term| term.resize(wide));
+        assert_eq!(
+            marks.last_command_output(&term),
+            Some((
+                AlacPoint::new(Line(1), Column(0)),
+                AlacPoint::new(Line(2), Column(0))
+            ))
+        );
+        assert_eq!(marks.previous_prompt(&term, Line(2)), Some(Line(0)));
+
+        // Five columns wrap it onto three lines.
+        let narrow = TerminalSize::new(px(10.), px(10.), size(px(50.), px(30.)));
+        marks.resize(&mut term, |term| term.resize(narrow));
+        assert_eq!(
+            marks.last_command_output(&term),
+            Some((
+                AlacPoint::new(Line(-1), Column(0)),
+                AlacPoint::new(Line(2), Column(0))
+            ))
+        );
+        assert_eq!(marks.previous_prompt(&term, Line(2)), Some(Line(-2)));
+    }
+
+    #[test]
+    fn test_marks_are_dropped_when_history_overflows() {
+        let (mut term, mut marks) = new_term();
+        run_command(&mut term, &mut marks, "out\r\n");
+        assert_eq!(marks.marks.len(), 4);
+
+        let mut parser = Processor::new();
+        for _ in 0..HISTORY_LIMIT + HISTORY_HEADROOM {
+            parser.advance(&mut term, b'\n');
+        }
+        marks.trim_history(&mut term);
+        assert!(marks.marks.is_empty());
+        assert_eq!(term.grid().history_size(), HISTORY_LIMIT);
+    }
+}
@@ -67,10 +67,25 @@ impl ProcessIdGetter {
 #[derive(Clone, Debug)]
 pub struct ProcessInfo {
     pub name: String,
+    pub pid: u32,
     pub cwd: PathBuf,
     pub argv: Vec<String>,
 }
 
+fn process_info(process: &Process) -> ProcessInfo {
+    let cwd = process
+        .cwd()
+        .take()
+        .map_or(PathBuf::new(), |p| p.to_owned());
+
+    ProcessInfo {
+        name: process.name().to_owned(),
+        pid: process.pid().as_u32(),
+        cwd,
+        argv: process.cmd().to_vec(),
+    }
+}
+
 /// Fetches Zed-relevant Pseudo-Terminal (PTY) process information
 pub struct PtyProcessInfo {
     system: System,
@@ -109,21 +124,60 @@ impl PtyProcessInfo {
     }
 
     fn load(&mut self) -> Option<ProcessInfo> {
-        let process = self.refresh()?;
-        let cwd = process
-            .cwd()
-            .take()
-            .map_or(PathBuf::new(), |p| p.to_owned());
-
-        let info = ProcessInfo {
-            name: process.name().to_owned(),
-            cwd,
-            argv: process.cmd().to_vec(),
-        };
+        let info = process_info(self.refresh()?);
         self.current = Some(info.clone());
         Some(info)
     }
 
+    /// Freshly queries the program in the foreground, which is the shell itself while
+    /// it waits for a command
+    pub fn foreground_process_or_shell(&mut self) -> Option<ProcessInfo> {
+        self.refresh().map(process_info)
+    }
+
+    /// Freshly queries the shell the terminal was started with
+    pub fn shell_process(&mut self) -> Option<ProcessInfo> {
+        let pid = Pid::from_u32(self.pid_getter.fallback_pid);
+        if self
+            .system
+            .refresh_process_specifics(pid, self.refresh_kind)
+        {
+            self.system.process(pid).map(process_info)
+        } else {
+            None
+        }
+    }
+
+    /// Freshly queries the program in the foreground, if it is not the shell itself
+    pub fn foreground_process(&mut self) -> Option<ProcessInfo> {
+        if self.is_shell_in_foreground() {
+            return None;
+        }
+        self.refresh().map(process_info)
+    }
+
+    /// Whether the shell itself, rather than a program it launched, is in the foreground
+    #[cfg(unix)]
+    pub fn is_shell_in_foreground(&mut self) -> bool {
+        self.pid_getter.pid() == Some(Pid::from_u32(self.pid_getter.fallback_pid))
+    }
+
+    /// Whether the shell itself, rather than a program it launched, is in the foreground.
+    /// ConPTY has no foreground process group, so this checks whether the shell has
+    /// started any processes. Finding them needs the whole process list, which is
+    /// refreshed without the details `refresh_kind` asks for.
+    #[cfg(windows)]
+    pub fn is_shell_in_foreground(&mut self) -> bool {
+        let shell_pid = Pid::from_u32(self.pid_getter.fallback_pid);
+        self.system
+            .refresh_processes_specifics(ProcessRefreshKind::new());
+        !self
+            .system
+            .processes()
+            .values()
+            .any(|process| process.parent() == Some(shell_pid))
+    }
+
     /// Updates the cached process info, returns whether the Zed-relevant info has changed
     pub fn has_changed(&mut self) -> bool {
         let current = self.load();
@@ -0,0 +1,225 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    selection::SelectionRange,
+    Term,
+};
+use anyhow::{anyhow, Context as _, Result};
+use serde_json::json;
+
+use crate::formatted_copy;
+
+/// Writes a terminal's PTY output, byte for byte and timed as it was read, to an
+/// [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file, which
+/// `asciinema play` and the asciinema web player can replay.
+///
+/// The PTY reader hands each read to the recording, and a thread of its own writes
+/// the events out, so that neither the reader nor the terminal waits on the file.
+/// A recording starts with the screen as it was, as the output before it is gone.
+pub(crate) struct Recording {
+    path: PathBuf,
+    started_at: Instant,
+    events_tx: mpsc::Sender<(Duration, RecordedEvent)>,
+    writer: thread::JoinHandle<Result<()>>,
+}
+
+enum RecordedEvent {
+    Output(Vec<u8>),
+    Resize { columns: usize, lines: usize },
+}
+
+impl Recording {
+    pub fn start<T>(path: PathBuf, term: &Term<T>, title: &str) -> Result<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("creating terminal recording at {path:?}"))?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let header = json!({
+            "version": 2,
+            "width": term.columns(),
+            "height": term.screen_lines(),
+            "timestamp": timestamp,
+            "title": title,
+        });
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{header}")?;
+
+        let (events_tx, events_rx) = mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("terminal recording".into())
+            .spawn(move || write_events(writer, events_rx))?;
+        let recording = Self {
+            path,
+            started_at: Instant::now(),
+            events_tx,
+            writer,
+        };
+        recording.record(RecordedEvent::Output(screen_frame(term).into_bytes()));
+        Ok(recording)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records output read from the PTY.
+    pub fn record_output(&self, bytes: &[u8]) {
+        self.record(RecordedEvent::Output(bytes.to_vec()));
+    }
+
+    pub fn record_resize(&self, columns: usize, lines: usize) {
+        self.record(RecordedEvent::Resize { columns, lines });
+    }
+
+    /// Waits for the events recorded so far to be written, which may block.
+    pub fn finish(self) -> Result<PathBuf> {
+        drop(self.events_tx);
+        self.writer
+            .join()
+            .map_err(|_| anyhow!("writing terminal recording to {:?} panicked", self.path))??;
+        Ok(self.path)
+    }
+
+    fn record(&self, event: RecordedEvent) {
+        // Once writing failed, the error is reported when the recording finishes.
+        self.events_tx.send((self.started_at.elapsed(), event)).ok();
+    }
+}
+
+fn write_events(
+    mut writer: BufWriter<File>,
+    events_rx: mpsc::Receiver<(Duration, RecordedEvent)>,
+) -> Result<()> {
+    // Output that ends partway through a character, held until the rest arrives.
+    let mut pending = Vec::new();
+    for (time, event) in events_rx {
+        let time = time.as_secs_f64();
+        match event {
+            RecordedEvent::Output(bytes) => {
+                pending.extend_from_slice(&bytes);
+                let text_len = pending.len() - incomplete_char_len(&pending);
+                if text_len > 0 {
+                    let text = String::from_utf8_lossy(&pending[..text_len]);
+                    writeln!(writer, "{}", json!([time, "o", text]))?;
+                    pending.drain(..text_len);
+                }
+            }
+            RecordedEvent::Resize { columns, lines } => {
+                writeln!(
+                    writer,
+                    "{}",
+                    json!([time, "r", format!("{columns}x{lines}")])
+                )?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// How many bytes at the end of `bytes` begin a UTF-8 character without finishing
+/// it. Reads end wherever the PTY's buffer did, and asciicast output is text.
+fn incomplete_char_len(bytes: &[u8]) -> usize {
+    for (len, &byte) in bytes.iter().rev().take(4).enumerate() {
+        let len = len + 1;
+        let is_continuation = byte & 0b1100_0000 == 0b1000_0000;
+        if !is_continuation {
+            let char_len = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            return if char_len > len { len } else { 0 };
+        }
+    }
+    0
+}
+
+/// Output that clears the screen, draws the visible lines with their colors and
+/// attributes, and puts the cursor back where it was.
+fn screen_frame<T>(term: &Term<T>) -> String {
+    let screen = SelectionRange::new(
+        AlacPoint::new(Line(0), Column(0)),
+        AlacPoint::new(term.bottommost_line(), term.last_column()),
+        true,
+    );
+    let lines = formatted_copy::ansi_text(term, &screen);
+    let cursor = term.grid().cursor.point;
+    format!(
+        "\x1b[H\x1b[2J{}\x1b[{};{}H",
+        lines.replace('\n', "\r\n"),
+        cursor.line.0 + 1,
+        cursor.column.0 + 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::VoidListener, term::Config, vte::ansi::Processor};
+    use gpui::{px, size};
+
+    use crate::TerminalSize;
+
+    use super::*;
+
+    #[test]
+    fn test_incomplete_char_len() {
+        assert_eq!(incomplete_char_len(b""), 0);
+        assert_eq!(incomplete_char_len(b"ls\r\n"), 0);
+        let check_mark = "✓".as_bytes();
+        assert_eq!(incomplete_char_len(check_mark), 0);
+        assert_eq!(incomplete_char_len(&check_mark[..1]), 1);
+        assert_eq!(incomplete_char_len(&check_mark[..2]), 2);
+        // Stray continuation bytes are written out, to be replaced.
+        assert_eq!(incomplete_char_len(&check_mark[1..]), 0);
+    }
+
+    #[test]
+    fn test_records_output_as_read() {
+        // Ten columns and three lines.
+        let dimensions = TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.)));
+        let mut term = Term::new(Config::default(), &dimensions, VoidListener);
+        let mut parser = Processor::new();
+        for byte in b"$ " {
+            parser.advance(&mut term, *byte);
+        }
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("recording.cast");
+        let recording = Recording::start(path.clone(), &term, "test").unwrap();
+        let check_mark = "✓".as_bytes();
+        recording.record_output(b"ls\r\n\x1b[1m");
+        recording.record_output(&check_mark[..1]);
+        recording.record_output(&check_mark[1..]);
+        recording.record_resize(20, 5);
+        assert_eq!(recording.finish().unwrap(), path);
+
+        let cast = std::fs::read_to_string(&path).unwrap();
+        let events = cast
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(events[0]["version"], 2);
+        assert_eq!(events[0]["width"], 10);
+        assert_eq!(events[0]["height"], 3);
+        assert_eq!(events[0]["title"], "test");
+        assert_eq!(events[1][1], "o");
+        assert!(events[1][2].as_str().unwrap().starts_with("\x1b[H\x1b[2J$"));
+        let rest = events[2..]
+            .iter()
+            .map(|event| (event[1].as_str().unwrap(), event[2].as_str().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(rest, [("o", "ls\r\n\x1b[1m"), ("o", "✓"), ("r", "20x5")]);
+    }
+}
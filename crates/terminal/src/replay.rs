@@ -0,0 +1,259 @@
+use std::time::{Duration, Instant};
+
+use alacritty_terminal::{
+    event::EventListener,
+    vte::ansi::{Handler, Processor},
+    Term,
+};
+use anyhow::{anyhow, bail, Context as _, Result};
+use gpui::{Size, Task};
+use serde::Deserialize;
+
+use crate::TerminalSize;
+
+/// A parsed [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) recording.
+pub struct Cast {
+    pub title: Option<String>,
+    columns: usize,
+    lines: usize,
+    events: Vec<CastEvent>,
+}
+
+struct CastEvent {
+    time: Duration,
+    kind: CastEventKind,
+}
+
+enum CastEventKind {
+    Output(String),
+    Resize { columns: usize, lines: usize },
+}
+
+#[derive(Deserialize)]
+struct CastHeader {
+    version: u32,
+    width: usize,
+    height: usize,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+impl Cast {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().context("empty recording")?;
+        let header: CastHeader =
+            serde_json::from_str(header).context("parsing the recording header")?;
+        if header.version != 2 {
+            bail!("unsupported asciicast version {}", header.version);
+        }
+
+        let mut events = Vec::new();
+        for (ix, line) in lines.enumerate() {
+            let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+                .with_context(|| format!("parsing event {} of the recording", ix + 1))?;
+            let kind = match code.as_str() {
+                "o" => CastEventKind::Output(data),
+                "r" => {
+                    let (columns, lines) = data
+                        .split_once('x')
+                        .and_then(|(columns, lines)| {
+                            Some((columns.parse().ok()?, lines.parse().ok()?))
+                        })
+                        .ok_or_else(|| anyhow!("invalid resize event {data:?}"))?;
+                    CastEventKind::Resize { columns, lines }
+                }
+                // Input and marker events don't change what the terminal shows.
+                _ => continue,
+            };
+            events.push(CastEvent {
+                time: Duration::try_from_secs_f64(time).unwrap_or_default(),
+                kind,
+            });
+        }
+
+        Ok(Self {
+            title: header.title,
+            columns: header.width.max(1),
+            lines: header.height.max(1),
+            events,
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.events
+            .last()
+            .map_or(Duration::ZERO, |event| event.time)
+    }
+}
+
+/// Where a replay is, and whether it is playing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayProgress {
+    pub position: Duration,
+    pub duration: Duration,
+    pub playing: bool,
+}
+
+enum Clock {
+    Paused(Duration),
+    Playing { started_at: Instant },
+}
+
+/// Plays a [`Cast`] back into a terminal grid that isn't connected to a PTY.
+pub(crate) struct Replay {
+    cast: Cast,
+    pub(crate) title: String,
+    parser: Processor,
+    next_event: usize,
+    columns: usize,
+    lines: usize,
+    clock: Clock,
+    pub(crate) playback: Option<Task<()>>,
+}
+
+impl Replay {
+    pub fn new(cast: Cast, title: String) -> Self {
+        Self {
+            columns: cast.columns,
+            lines: cast.lines,
+            cast,
+            title,
+            parser: Processor::new(),
+            next_event: 0,
+            clock: Clock::Paused(Duration::ZERO),
+            playback: None,
+        }
+    }
+
+    pub fn progress(&self) -> ReplayProgress {
+        ReplayProgress {
+            position: self.position(),
+            duration: self.cast.duration(),
+            playing: matches!(self.clock, Clock::Playing { .. }),
+        }
+    }
+
+    pub fn position(&self) -> Duration {
+        match self.clock {
+            Clock::Paused(position) => position,
+            Clock::Playing { started_at } => started_at.elapsed().min(self.cast.duration()),
+        }
+    }
+
+    pub fn play(&mut self) {
+        let position = self.position();
+        self.clock = Clock::Playing {
+            started_at: Instant::now() - position,
+        };
+    }
+
+    pub fn pause(&mut self) {
+        self.clock = Clock::Paused(self.position());
+        self.playback = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.clock, Clock::Playing { .. })
+    }
+
+    pub fn next_event_time(&self) -> Option<Duration> {
+        Some(self.cast.events.get(self.next_event)?.time)
+    }
+
+    /// The size to lay the grid out at: the recording's dimensions in cells of the
+    /// view's size, so that output wraps the way it did when it was recorded.
+    pub fn fit(&self, size: TerminalSize) -> TerminalSize {
+        TerminalSize {
+            size: Size {
+                width: size.cell_width * self.columns as f32,
+                height: size.line_height * self.lines as f32,
+            },
+            ..size
+        }
+    }
+
+    /// Moves the playback position, replaying from the start when going backwards.
+    pub fn seek<T: EventListener>(
+        &mut self,
+        position: Duration,
+        term: &mut Term<T>,
+        size: TerminalSize,
+    ) {
+        let position = position.min(self.cast.duration());
+        if position < self.position() {
+            term.reset_state();
+            self.parser = Processor::new();
+            self.next_event = 0;
+            self.columns = self.cast.columns;
+            self.lines = self.cast.lines;
+            term.resize(self.fit(size));
+        }
+        self.clock = match self.clock {
+            Clock::Paused(_) => Clock::Paused(position),
+            Clock::Playing { .. } => Clock::Playing {
+                started_at: Instant::now() - position,
+            },
+        };
+        self.apply_until(position, term, size);
+    }
+
+    /// Feeds every event up to `position` into the grid.
+    pub fn apply_until<T: EventListener>(
+        &mut self,
+        position: Duration,
+        term: &mut Term<T>,
+        size: TerminalSize,
+    ) {
+        while let Some(event) = self.cast.events.get(self.next_event) {
+            if event.time > position {
+                break;
+            }
+            match &event.kind {
+                CastEventKind::Output(data) => {
+                    for byte in data.bytes() {
+                        self.parser.advance(term, byte);
+                    }
+                }
+                CastEventKind::Resize { columns, lines } => {
+                    self.columns = (*columns).max(1);
+                    self.lines = (*lines).max(1);
+                    term.resize(self.fit(size));
+                }
+            }
+            self.next_event += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cast() {
+        let cast = Cast::parse(
+            r#"{"version": 2, "width": 80, "height": 24, "title": "demo"}
+[0.5, "o", "hello"]
+[0.75, "i", "x"]
+[1.0, "r", "100x30"]
+[2.25, "o", "\r\nworld"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(cast.title.as_deref(), Some("demo"));
+        assert_eq!((cast.columns, cast.lines), (80, 24));
+        assert_eq!(cast.events.len(), 3);
+        assert!(matches!(
+            cast.events[1].kind,
+            CastEventKind::Resize {
+                columns: 100,
+                lines: 30
+            }
+        ));
+        assert_eq!(cast.duration(), Duration::from_millis(2250));
+
+        assert!(Cast::parse(r#"{"version": 1, "width": 80, "height": 24}"#).is_err());
+        assert!(Cast::parse("").is_err());
+    }
+}
@@ -0,0 +1,603 @@
+use std::{io, mem, sync::Arc, time::Instant};
+
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, OnResize, WindowSize},
+    sync::FairMutex,
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+    vte::ansi::Processor,
+    Term,
+};
+use futures::channel::mpsc::UnboundedSender;
+use parking_lot::Mutex;
+use polling::{Event, PollMode, Poller};
+
+use crate::{prompt_marks::PromptMarks, recording::Recording, ZedListener};
+
+/// OSC 133 sequences longer than this aren't ones that are understood, and are
+/// skipped instead of collected.
+const MAX_OSC_LEN: usize = 32;
+
+/// How much output is parsed under the terminal's lock before it's let go, as
+/// alacritty's event loop does.
+const MAX_LOCKED_READ: usize = u16::MAX as usize;
+
+/// How much output is parsed between trims of the history. A byte scrolls one
+/// line at most, bar the few sequences that scroll many, so this stays well
+/// within [`crate::prompt_marks::HISTORY_HEADROOM`].
+const MAX_PARSE_LEN: usize = 1024;
+
+/// A semantic prompt mark a shell sends with OSC 133, from its shell integration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ShellMark {
+    /// `A`: the prompt is about to be drawn.
+    PromptStart,
+    /// `B`: the prompt is drawn, and what follows is typed.
+    CommandStart,
+    /// `C`: the command was submitted and its output follows.
+    CommandExecuted,
+    /// `D`: the command finished, with its exit code when the shell sends it.
+    CommandFinished(Option<i32>),
+}
+
+/// Reads the shell's output for alacritty's event loop, and parses it into the
+/// terminal itself, stopping at each OSC 133 mark to place it where the cursor
+/// is. alacritty's parser drops the marks, and once it has parsed a read, where
+/// in the grid they were is lost.
+///
+/// The event loop still polls the PTY and writes to it, but each read it makes
+/// comes back empty, so it never parses anything or takes the terminal's lock.
+/// Marks are also sent on `marks_tx`, to time commands by, and the output is
+/// handed to the terminal's recording, if it has one, as it was read.
+pub(crate) struct ShellIntegrationPty<P> {
+    pty: P,
+    term: Arc<FairMutex<Term<ZedListener>>>,
+    listener: ZedListener,
+    parser: OutputParser,
+    prompt_marks: Arc<Mutex<PromptMarks>>,
+    marks_tx: UnboundedSender<ShellMark>,
+    recording: Arc<Mutex<Option<Recording>>>,
+}
+
+impl<P> ShellIntegrationPty<P> {
+    pub fn new(
+        pty: P,
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        listener: ZedListener,
+        prompt_marks: Arc<Mutex<PromptMarks>>,
+        marks_tx: UnboundedSender<ShellMark>,
+        recording: Arc<Mutex<Option<Recording>>>,
+    ) -> Self {
+        Self {
+            pty,
+            term,
+            listener,
+            parser: OutputParser::new(),
+            prompt_marks,
+            marks_tx,
+            recording,
+        }
+    }
+}
+
+impl<P: EventedReadWrite> io::Read for ShellIntegrationPty<P> {
+    /// Parses what's available, and reports nothing read. Errors are passed on
+    /// to the event loop, unless there was output before them.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let term = self.term.clone();
+        let mut term_lock = None;
+        let mut parsed = false;
+
+        // The event loop doesn't know about synchronized updates parsed here, so
+        // one that times out is applied with the next output.
+        if self.parser.sync_timed_out() {
+            let term = term_lock.get_or_insert_with(|| term.lock_unfair());
+            self.parser
+                .stop_sync(&mut **term, &mut self.prompt_marks.lock());
+            parsed = true;
+        }
+
+        let mut read_total = 0;
+        let result = loop {
+            let read = match self.pty.reader().read(buf) {
+                Ok(0) => break Ok(0),
+                Ok(read) => read,
+                Err(error)
+                    if read_total > 0
+                        && matches!(
+                            error.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                        ) =>
+                {
+                    break Ok(0)
+                }
+                Err(error) => break Err(error),
+            };
+            let term = term_lock.get_or_insert_with(|| term.lock_unfair());
+            // Recorded under the terminal's lock, so that a recording starting with
+            // the screen as it is gets all the output parsed after it.
+            if let Some(recording) = self.recording.lock().as_ref() {
+                recording.record_output(&buf[..read]);
+            }
+            let marks_tx = &self.marks_tx;
+            self.parser.parse(
+                &buf[..read],
+                &mut **term,
+                &mut self.prompt_marks.lock(),
+                |mark| {
+                    marks_tx.unbounded_send(mark).ok();
+                },
+            );
+            parsed = true;
+            read_total += read;
+            if read_total >= MAX_LOCKED_READ {
+                break Ok(0);
+            }
+        };
+
+        drop(term_lock);
+        if parsed {
+            self.listener.send_event(AlacTermEvent::Wakeup);
+        }
+        result
+    }
+}
+
+impl<P: EventedReadWrite> EventedReadWrite for ShellIntegrationPty<P> {
+    type Reader = Self;
+    type Writer = P::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl<P: EventedPty> EventedPty for ShellIntegrationPty<P> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl<P: OnResize> OnResize for ShellIntegrationPty<P> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
+
+/// Parses output into a terminal, splitting it at what the scanner finds, so
+/// that the marks are placed where the cursor is when the parser reaches them.
+struct OutputParser {
+    parser: Processor,
+    scanner: MarkScanner,
+    events: Vec<(usize, ScanEvent)>,
+    /// What was found in a synchronized update, which the parser buffers and
+    /// applies at once when the update ends. Its marks land where the update
+    /// leaves the cursor.
+    pending: Vec<ScanEvent>,
+}
+
+impl OutputParser {
+    fn new() -> Self {
+        Self {
+            parser: Processor::new(),
+            scanner: MarkScanner::default(),
+            events: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    fn parse<T: EventListener>(
+        &mut self,
+        bytes: &[u8],
+        term: &mut Term<T>,
+        prompt_marks: &mut PromptMarks,
+        mut on_mark: impl FnMut(ShellMark),
+    ) {
+        let mut events = mem::take(&mut self.events);
+        self.scanner
+            .scan(bytes, |index, event| events.push((index, event)));
+
+        let mut parsed = 0;
+        for (index, event) in events.drain(..) {
+            // The last byte of a sequence is the one that applies it.
+            self.advance(&bytes[parsed..index], term, prompt_marks);
+            let history_size = term.grid().history_size();
+            self.advance(&bytes[index..=index], term, prompt_marks);
+            parsed = index + 1;
+
+            if let ScanEvent::Mark(mark) = event {
+                on_mark(mark);
+            }
+            if self.parser.sync_bytes_count() > 0 {
+                self.pending.push(event);
+                continue;
+            }
+            match event {
+                ScanEvent::Mark(mark) => prompt_marks.push(mark, term),
+                ScanEvent::ClearHistory => prompt_marks.scroll_off(history_size),
+                ScanEvent::Reset => prompt_marks.clear(),
+            }
+        }
+        self.advance(&bytes[parsed..], term, prompt_marks);
+        self.events = events;
+    }
+
+    fn advance<T: EventListener>(
+        &mut self,
+        bytes: &[u8],
+        term: &mut Term<T>,
+        prompt_marks: &mut PromptMarks,
+    ) {
+        for chunk in bytes.chunks(MAX_PARSE_LEN) {
+            for &byte in chunk {
+                self.parser.advance(term, byte);
+            }
+            self.apply_pending(term, prompt_marks);
+            prompt_marks.trim_history(term);
+        }
+    }
+
+    fn sync_timed_out(&self) -> bool {
+        self.parser
+            .sync_timeout()
+            .sync_timeout()
+            .map_or(false, |timeout| timeout <= Instant::now())
+    }
+
+    fn stop_sync<T: EventListener>(&mut self, term: &mut Term<T>, prompt_marks: &mut PromptMarks) {
+        self.parser.stop_sync(term);
+        self.apply_pending(term, prompt_marks);
+        prompt_marks.trim_history(term);
+    }
+
+    fn apply_pending<T>(&mut self, term: &Term<T>, prompt_marks: &mut PromptMarks) {
+        if self.pending.is_empty() || self.parser.sync_bytes_count() > 0 {
+            return;
+        }
+        for event in self.pending.drain(..) {
+            match event {
+                ScanEvent::Mark(mark) => prompt_marks.push(mark, term),
+                // How much history there was when they applied is gone.
+                ScanEvent::ClearHistory | ScanEvent::Reset => prompt_marks.clear(),
+            }
+        }
+    }
+}
+
+/// What the scanner finds in the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanEvent {
+    Mark(ShellMark),
+    /// `CSI 3 J`, which clears the history.
+    ClearHistory,
+    /// `ESC c`, which resets the terminal, history and all.
+    Reset,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    /// An ESC inside an OSC, which ends it when followed by `\`.
+    OscEscape,
+}
+
+/// Finds OSC 133 marks, and the sequences that clear the history, in a stream
+/// of output, which may split them across reads.
+#[derive(Default)]
+struct MarkScanner {
+    state: ScanState,
+    /// The body of the OSC, or the parameters of the CSI, being scanned.
+    sequence: Vec<u8>,
+    /// Whether the sequence being scanned got too long to be one of them.
+    overflowed: bool,
+}
+
+impl MarkScanner {
+    /// Calls `on_event` with what's found, and the index of its last byte.
+    fn scan(&mut self, bytes: &[u8], mut on_event: impl FnMut(usize, ScanEvent)) {
+        let mut index = 0;
+        while index < bytes.len() {
+            let byte = bytes[index];
+            self.state = match (self.state, byte) {
+                (ScanState::Ground, 0x1b) => ScanState::Escape,
+                (ScanState::Ground, _) => {
+                    // Most output has no escapes in it at all.
+                    match bytes[index..].iter().position(|&byte| byte == 0x1b) {
+                        Some(escape) => {
+                            index += escape;
+                            ScanState::Escape
+                        }
+                        None => break,
+                    }
+                }
+                (ScanState::Escape | ScanState::OscEscape, b']') => {
+                    self.start_sequence();
+                    ScanState::Osc
+                }
+                (ScanState::Escape | ScanState::OscEscape, b'[') => {
+                    self.start_sequence();
+                    ScanState::Csi
+                }
+                (ScanState::Escape | ScanState::OscEscape, b'c') => {
+                    on_event(index, ScanEvent::Reset);
+                    ScanState::Ground
+                }
+                (ScanState::Escape | ScanState::OscEscape, 0x1b) => ScanState::Escape,
+                (ScanState::OscEscape, b'\\') => {
+                    self.finish_osc(index, &mut on_event);
+                    ScanState::Ground
+                }
+                (ScanState::Escape | ScanState::OscEscape, _) => ScanState::Ground,
+                (ScanState::Csi, 0x40..=0x7e) => {
+                    if byte == b'J' && !self.overflowed && self.sequence == b"3" {
+                        on_event(index, ScanEvent::ClearHistory);
+                    }
+                    ScanState::Ground
+                }
+                (ScanState::Csi, 0x1b) => ScanState::Escape,
+                // CAN and SUB cancel the sequence.
+                (ScanState::Csi, 0x18 | 0x1a) => ScanState::Ground,
+                (ScanState::Csi, 0x20..=0x3f) => {
+                    self.push(byte);
+                    ScanState::Csi
+                }
+                // Other control characters are executed as the sequence goes on.
+                (ScanState::Csi, _) => ScanState::Csi,
+                (ScanState::Osc, 0x07) => {
+                    self.finish_osc(index, &mut on_event);
+                    ScanState::Ground
+                }
+                (ScanState::Osc, 0x1b) => ScanState::OscEscape,
+                (ScanState::Osc, _) => {
+                    self.push(byte);
+                    ScanState::Osc
+                }
+            };
+            index += 1;
+        }
+    }
+
+    fn start_sequence(&mut self) {
+        self.sequence.clear();
+        self.overflowed = false;
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.sequence.len() < MAX_OSC_LEN {
+            self.sequence.push(byte);
+        } else {
+            self.overflowed = true;
+        }
+    }
+
+    fn finish_osc(&mut self, index: usize, on_event: &mut impl FnMut(usize, ScanEvent)) {
+        if !self.overflowed {
+            if let Some(mark) = parse_mark(&self.sequence) {
+                on_event(index, ScanEvent::Mark(mark));
+            }
+        }
+        self.sequence.clear();
+    }
+}
+
+/// Parses the body of an OSC, like `133;D;0`, into a mark.
+fn parse_mark(osc: &[u8]) -> Option<ShellMark> {
+    let mut params = osc.strip_prefix(b"133;")?.split(|&byte| byte == b';');
+    let mark = match params.next()? {
+        b"A" => ShellMark::PromptStart,
+        b"B" => ShellMark::CommandStart,
+        b"C" => ShellMark::CommandExecuted,
+        b"D" => ShellMark::CommandFinished(
+            params
+                .next()
+                .and_then(|code| std::str::from_utf8(code).ok())
+                .and_then(|code| code.parse().ok()),
+        ),
+        _ => return None,
+    };
+    Some(mark)
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::{Column, Line, Point as AlacPoint},
+        term::Config,
+    };
+    use gpui::{px, size};
+
+    use crate::{prompt_marks::HISTORY_HEADROOM, TerminalSize};
+
+    use super::*;
+
+    const HISTORY_LIMIT: usize = 4;
+
+    fn scan_chunks(chunks: &[&[u8]]) -> Vec<ShellMark> {
+        let mut scanner = MarkScanner::default();
+        let mut marks = Vec::new();
+        for chunk in chunks {
+            scanner.scan(chunk, |_, event| {
+                if let ScanEvent::Mark(mark) = event {
+                    marks.push(mark);
+                }
+            });
+        }
+        marks
+    }
+
+    #[test]
+    fn test_finds_marks_with_either_terminator() {
+        assert_eq!(
+            scan_chunks(&[b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07out\r\n"]),
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::CommandExecuted
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reads_exit_codes() {
+        assert_eq!(
+            scan_chunks(&[b"\x1b]133;D;0\x07\x1b]133;D;127;aid=1\x07\x1b]133;D\x07"]),
+            vec![
+                ShellMark::CommandFinished(Some(0)),
+                ShellMark::CommandFinished(Some(127)),
+                ShellMark::CommandFinished(None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_finds_marks_split_across_reads() {
+        assert_eq!(
+            scan_chunks(&[b"output\x1b", b"]13", b"3;D;", b"1\x1b", b"\\"]),
+            vec![ShellMark::CommandFinished(Some(1))]
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_sequences() {
+        assert_eq!(
+            scan_chunks(&[
+                b"\x1b]0;title\x07\x1b[1;31mred\x1b[0m",
+                b"\x1b]133;Z\x07\x1b]1337;133;A\x07",
+            ]),
+            Vec::new()
+        );
+        let long_title = format!("\x1b]0;{}\x07\x1b]133;A\x07", "x".repeat(100));
+        assert_eq!(
+            scan_chunks(&[long_title.as_bytes()]),
+            vec![ShellMark::PromptStart]
+        );
+    }
+
+    #[test]
+    fn test_reports_where_sequences_end() {
+        let mut scanner = MarkScanner::default();
+        let mut events = Vec::new();
+        scanner.scan(
+            b"ab\x1b[3Jcd\x1bc\x1b]133;A\x07\x1b[2J\x1b[?3J",
+            |index, event| events.push((index, event)),
+        );
+        assert_eq!(
+            events,
+            vec![
+                (5, ScanEvent::ClearHistory),
+                (9, ScanEvent::Reset),
+                (17, ScanEvent::Mark(ShellMark::PromptStart))
+            ]
+        );
+    }
+
+    /// Ten columns and three lines, with room for four lines of history.
+    fn new_term() -> (Term<VoidListener>, PromptMarks, OutputParser) {
+        let config = Config {
+            scrolling_history: HISTORY_LIMIT + HISTORY_HEADROOM,
+            ..Config::default()
+        };
+        let dimensions = TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.)));
+        (
+            Term::new(config, &dimensions, VoidListener),
+            PromptMarks::new(HISTORY_LIMIT),
+            OutputParser::new(),
+        )
+    }
+
+    #[test]
+    fn test_places_marks_where_the_cursor_is() {
+        let (mut term, mut prompt_marks, mut parser) = new_term();
+        let mut marks = Vec::new();
+        parser.parse(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07out\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ ",
+            &mut term,
+            &mut prompt_marks,
+            |mark| marks.push(mark),
+        );
+        assert_eq!(marks.len(), 5);
+        assert_eq!(
+            prompt_marks.last_command_output(&term),
+            Some((
+                AlacPoint::new(Line(1), Column(0)),
+                AlacPoint::new(Line(2), Column(0))
+            ))
+        );
+        assert_eq!(prompt_marks.previous_prompt(&term, Line(2)), Some(Line(0)));
+
+        // `clear` scrolls the screen into the history, then clears that.
+        parser.parse(
+            b"\x1b[H\x1b[2J\x1b[3J",
+            &mut term,
+            &mut prompt_marks,
+            |_| {},
+        );
+        assert_eq!(term.grid().history_size(), 0);
+        assert_eq!(prompt_marks.last_command_output(&term), None);
+        assert_eq!(prompt_marks.previous_prompt(&term, Line(3)), None);
+    }
+
+    #[test]
+    fn test_keeps_history_within_its_limit() {
+        let (mut term, mut prompt_marks, mut parser) = new_term();
+        parser.parse(
+            b"\x1b]133;A\x07$ \r\n",
+            &mut term,
+            &mut prompt_marks,
+            |_| {},
+        );
+        parser.parse(b"out\r\n", &mut term, &mut prompt_marks, |_| {});
+        assert_eq!(prompt_marks.previous_prompt(&term, Line(2)), Some(Line(0)));
+
+        parser.parse(
+            "out\r\n".repeat(MAX_PARSE_LEN).as_bytes(),
+            &mut term,
+            &mut prompt_marks,
+            |_| {},
+        );
+        assert_eq!(term.grid().history_size(), HISTORY_LIMIT);
+        assert_eq!(prompt_marks.previous_prompt(&term, Line(2)), None);
+    }
+
+    #[test]
+    fn test_places_marks_in_synchronized_updates_when_they_end() {
+        let (mut term, mut prompt_marks, mut parser) = new_term();
+        parser.parse(
+            b"\x1b[?2026h\x1b]133;A\x07$ \x1b[?2026l",
+            &mut term,
+            &mut prompt_marks,
+            |_| {},
+        );
+        assert_eq!(prompt_marks.next_prompt(&term, Line(-1)), Some(Line(0)));
+    }
+}
@@ -2,26 +2,41 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod command_text;
+mod formatted_copy;
+mod headless;
+mod inspect;
+mod prompt_marks;
 mod pty_info;
+mod recording;
+mod replay;
+mod shell_integration;
 pub mod terminal_settings;
+mod text_snapshot;
+mod wsl;
 
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
     grid::{Dimensions, Scroll as AlacScroll},
-    index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
+    index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint, Side},
     selection::{Selection, SelectionRange, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Cell,
+        cell::{Cell, Flags},
+        color::Colors,
         search::{Match, RegexIter, RegexSearch},
-        Config, RenderableCursor, TermMode,
+        Config, RenderableCursor, TermDamage, TermMode,
     },
     tty::{self},
-    vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode},
+    vi_mode::ViMotion,
+    vte::ansi::{
+        Attr, ClearMode, Color as AnsiColor, CursorShape, CursorStyle, Handler, NamedColor,
+        NamedPrivateMode, PrivateMode, Rgb,
+    },
     Term,
 };
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
@@ -29,35 +44,55 @@ use futures::{
 };
 
 use mappings::mouse::{
-    alt_scroll, grid_point, grid_point_and_side, mouse_button_report, mouse_moved_report,
-    scroll_report,
+    alt_scroll, grid_point_and_side, mouse_button_report, mouse_moved_report, scroll_report,
 };
 
 use collections::{HashMap, VecDeque};
+pub use formatted_copy::CopyFormat;
 use futures::StreamExt;
+use headless::OutputWaiters;
+pub use inspect::TerminalInspection;
+use parking_lot::Mutex;
+use prompt_marks::{PromptMarks, HISTORY_HEADROOM};
+pub use pty_info::ProcessInfo;
 use pty_info::PtyProcessInfo;
+use recording::Recording;
+use regex::{Captures, Regex};
+use replay::Replay;
+pub use replay::{Cast, ReplayProgress};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{ShellIntegrationPty, ShellMark};
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, TaskId};
-use terminal_settings::{AlternateScroll, TerminalBlink, TerminalSettings};
-use theme::{ActiveTheme, Theme};
-use util::truncate_and_trailoff;
+use terminal_settings::{
+    AlternateScroll, PasteSanitization, TerminalBell, TerminalBlink, TerminalColors,
+    TerminalCursorShape, TerminalLinkModifier, TerminalLinkRule, TerminalSettings,
+};
+pub use text_snapshot::{TextChange, TextSnapshot};
+use theme::{ActiveTheme, SystemAppearance, Theme};
+use util::{truncate_and_trailoff, ResultExt};
+pub use wsl::wsl_profiles;
 
 use std::{
+    cell::RefCell,
     cmp::{self, min},
     fmt::Display,
+    future::Future,
     ops::{Deref, Index, RangeInclusive},
     path::PathBuf,
-    sync::Arc,
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, LazyLock,
+    },
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
 use gpui::{
-    actions, black, px, AnyWindowHandle, AppContext, Bounds, ClipboardItem, EventEmitter, Hsla,
-    Keystroke, ModelContext, Modifiers, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    Pixels, Point, Rgba, ScrollWheelEvent, Size, Task, TouchPhase,
+    actions, black, impl_actions, px, AnyWindowHandle, AppContext, Bounds, ClipboardItem, EntityId,
+    EventEmitter, Hsla, Keystroke, ModelContext, Modifiers, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Pixels, Point, Rgba, ScrollWheelEvent, Size, Task, TouchPhase,
 };
 
 use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
@@ -66,6 +101,8 @@ actions!(
     terminal,
     [
         Clear,
+        ClearScreen,
+        ClearScrollback,
         Copy,
         Paste,
         ShowCharacterPalette,
@@ -76,9 +113,78 @@ actions!(
         ScrollPageDown,
         ScrollToTop,
         ScrollToBottom,
+        ScrollToPreviousCommand,
+        ScrollToNextCommand,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        SelectLastCommandOutput,
+        ToggleCommandOutput,
+        ExpandAllCommandOutput,
+        ToggleCopyMode,
+        CopyModeToggleSelection,
+        CopyModeToggleLineSelection,
+        CopyModeYank,
     ]
 );
 
+/// Moves the copy mode cursor.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CopyModeMove(pub CopyModeMotion);
+
+/// Copies the selection along with its colors and text attributes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct CopyWithFormatting {
+    #[serde(default)]
+    pub format: CopyFormat,
+}
+
+impl_actions!(terminal, [CopyModeMove, CopyWithFormatting]);
+
+/// A cursor movement available in copy mode, mirroring the vi motions.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyModeMotion {
+    Up,
+    Down,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    WordRightEnd,
+    LineStart,
+    LineEnd,
+    FirstOccupied,
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    ParagraphUp,
+    ParagraphDown,
+    Bracket,
+}
+
+impl From<CopyModeMotion> for ViMotion {
+    fn from(motion: CopyModeMotion) -> Self {
+        match motion {
+            CopyModeMotion::Up => ViMotion::Up,
+            CopyModeMotion::Down => ViMotion::Down,
+            CopyModeMotion::Left => ViMotion::Left,
+            CopyModeMotion::Right => ViMotion::Right,
+            CopyModeMotion::WordLeft => ViMotion::SemanticLeft,
+            CopyModeMotion::WordRight => ViMotion::SemanticRight,
+            CopyModeMotion::WordRightEnd => ViMotion::SemanticRightEnd,
+            CopyModeMotion::LineStart => ViMotion::First,
+            CopyModeMotion::LineEnd => ViMotion::Last,
+            CopyModeMotion::FirstOccupied => ViMotion::FirstOccupied,
+            CopyModeMotion::ScreenTop => ViMotion::High,
+            CopyModeMotion::ScreenMiddle => ViMotion::Middle,
+            CopyModeMotion::ScreenBottom => ViMotion::Low,
+            CopyModeMotion::ParagraphUp => ViMotion::ParagraphUp,
+            CopyModeMotion::ParagraphDown => ViMotion::ParagraphDown,
+            CopyModeMotion::Bracket => ViMotion::Bracket,
+        }
+    }
+}
+
 ///Scrolling is unbearably sluggish by default. Alacritty supports a configurable
 ///Scroll multiplier that is set to 3 by default. This will be removed when I
 ///Implement scroll bars.
@@ -87,6 +193,8 @@ const SCROLL_MULTIPLIER: f32 = 4.;
 #[cfg(not(target_os = "macos"))]
 const SCROLL_MULTIPLIER: f32 = 1.;
 const MAX_SEARCH_LINES: usize = 100;
+const MAX_COMMAND_HISTORY: usize = 1000;
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(100);
 const DEBUG_TERMINAL_WIDTH: Pixels = px(500.);
 const DEBUG_TERMINAL_HEIGHT: Pixels = px(30.);
 const DEBUG_CELL_WIDTH: Pixels = px(5.);
@@ -95,7 +203,8 @@ const DEBUG_LINE_HEIGHT: Pixels = px(5.);
 ///Upward flowing events, for changing the title and such
 #[derive(Clone, Debug)]
 pub enum Event {
-    TitleChanged,
+    /// The title derived from the `title` setting changed to this, untruncated.
+    TitleChanged(String),
     BreadcrumbsChanged,
     CloseTerminal,
     Bell,
@@ -104,6 +213,16 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// Visible text changed, see [`Terminal::set_report_text_changes`].
+    TextChanged(TextChange),
+    /// The terminal with this id rang its bell or printed output while it was not
+    /// focused, or was focused and so had those cleared.
+    ActivityChanged(EntityId),
+    /// The mouse moved onto the cell at this point, or off the grid.
+    HoveredCellChanged(Option<AlacPoint>),
+    /// The user pasted this text with the mouse. It is left to the view, which pastes
+    /// it the same way as the `Paste` action.
+    PasteRequested(String),
 }
 
 #[derive(Clone, Debug)]
@@ -115,6 +234,14 @@ pub struct PathLikeTarget {
     pub terminal_dir: Option<PathBuf>,
 }
 
+#[derive(Clone, Debug)]
+pub struct CommitTarget {
+    /// A full or abbreviated commit SHA, which may not exist in any repository.
+    pub sha: String,
+    /// Current working directory of the terminal
+    pub terminal_dir: Option<PathBuf>,
+}
+
 /// A string inside terminal, potentially useful as a URI that can be opened.
 #[derive(Clone, Debug)]
 pub enum MaybeNavigationTarget {
@@ -123,29 +250,70 @@ pub enum MaybeNavigationTarget {
     /// File system path, absolute or relative, existing or not.
     /// Might have line and column number(s) attached as `file.rs:1:23`
     PathLike(PathLikeTarget),
+    /// A 7 to 40 character hex string, which might name a commit in the
+    /// repository the terminal is in.
+    Commit(CommitTarget),
 }
 
 #[derive(Clone)]
 enum InternalEvent {
     Resize(TerminalSize),
     Clear,
+    ClearScreen,
+    ClearScrollback,
     // FocusNextMatch,
     Scroll(AlacScroll),
     ScrollToAlacPoint(AlacPoint),
+    ScrollToPreviousCommand,
+    ScrollToNextCommand,
+    ScrollToPreviousPrompt,
+    ScrollToNextPrompt,
+    SelectLastCommandOutput,
+    ToggleCommandOutput,
+    /// Expands the collapsed command output a line is hidden in.
+    ExpandCommandOutput(Line),
+    ExpandAllCommandOutput,
     SetSelection(Option<(Selection, AlacPoint)>),
-    UpdateSelection(Point<Pixels>),
+    /// Moves the end of the selection to a position, after switching it to the given
+    /// type, if any.
+    UpdateSelection(Point<Pixels>, Option<SelectionType>),
     // Adjusted mouse position, should open
     FindHyperlink(Point<Pixels>, bool),
     Copy,
+    CopyWithFormatting(CopyFormat),
+    ToggleViMode,
+    ViMotion(ViMotion),
+    ToggleViSelection(SelectionType),
 }
 
 ///A translation struct for Alacritty to communicate with us from their event loop
 #[derive(Clone)]
-pub struct ZedListener(pub UnboundedSender<AlacTermEvent>);
+pub struct ZedListener {
+    events_tx: UnboundedSender<AlacTermEvent>,
+    /// Set while a wakeup is queued that the terminal hasn't picked up yet.
+    wakeup_pending: Arc<AtomicBool>,
+}
+
+impl ZedListener {
+    pub fn new(events_tx: UnboundedSender<AlacTermEvent>) -> Self {
+        Self {
+            events_tx,
+            wakeup_pending: Arc::default(),
+        }
+    }
+}
 
 impl EventListener for ZedListener {
     fn send_event(&self, event: AlacTermEvent) {
-        self.0.unbounded_send(event).ok();
+        // The PTY reader sends a wakeup after each read. Until the terminal picks up
+        // the queued one, which redraws whatever was read by then, more are redundant,
+        // and flooding the channel with them only makes the UI fall further behind.
+        if matches!(event, AlacTermEvent::Wakeup)
+            && self.wakeup_pending.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        self.events_tx.unbounded_send(event).ok();
     }
 }
 
@@ -272,6 +440,7 @@ impl TerminalError {
             Shell::System => "<system shell>".to_string(),
             Shell::Program(p) => p.to_string(),
             Shell::WithArguments { program, args } => format!("{} {}", program, args.join(" ")),
+            Shell::Remote { .. } => format!("ssh {}", self.shell.ssh_args().unwrap().join(" ")),
         }
     }
 
@@ -280,6 +449,7 @@ impl TerminalError {
             Shell::System => "<system defined shell>".to_string(),
             Shell::Program(s) => s.to_string(),
             Shell::WithArguments { program, args } => format!("{} {}", program, args.join(" ")),
+            Shell::Remote { .. } => format!("ssh {}", self.shell.ssh_args().unwrap().join(" ")),
         }
     }
 }
@@ -301,9 +471,159 @@ impl Display for TerminalError {
 const DEFAULT_SCROLL_HISTORY_LINES: usize = 10_000;
 const MAX_SCROLL_HISTORY_LINES: usize = 100_000;
 
+fn scrolling_history(is_task: bool, max_scroll_history_lines: Option<usize>) -> usize {
+    if is_task {
+        // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling.
+        // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
+        // cause excessive memory usage over time.
+        MAX_SCROLL_HISTORY_LINES
+    } else {
+        max_scroll_history_lines
+            .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
+            .min(MAX_SCROLL_HISTORY_LINES)
+    }
+}
+
+fn terminal_config(
+    is_task: bool,
+    max_scroll_history_lines: Option<usize>,
+    cursor_shape: TerminalCursorShape,
+    word_characters: &str,
+) -> Config {
+    let shape = match cursor_shape {
+        TerminalCursorShape::Block => CursorShape::Block,
+        TerminalCursorShape::Bar => CursorShape::Beam,
+        TerminalCursorShape::Underline => CursorShape::Underline,
+        TerminalCursorShape::Hollow => CursorShape::HollowBlock,
+    };
+    Config {
+        // The PTY reader trims the history back to the limit itself, see `PromptMarks`.
+        scrolling_history: scrolling_history(is_task, max_scroll_history_lines) + HISTORY_HEADROOM,
+        default_cursor_style: CursorStyle {
+            shape,
+            blinking: false,
+        },
+        semantic_escape_chars: semantic_escape_chars(word_characters),
+        ..Config::default()
+    }
+}
+
+/// Writes previously saved output into the terminal as dimmed text.
+fn write_restored_scrollback<T: EventListener>(term: &mut Term<T>, scrollback: &str) {
+    term.terminal_attribute(Attr::Dim);
+    for line in scrollback.lines() {
+        for c in line.chars().filter(|c| !c.is_control()) {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+    }
+    term.terminal_attribute(Attr::Reset);
+}
+
+///Alacritty splits words on a set of escape characters, whereas the setting lists the
+///punctuation that belongs to words. Every other ASCII punctuation character, plus
+///whitespace and the box drawing separator alacritty uses by default, ends a word.
+fn semantic_escape_chars(word_characters: &str) -> String {
+    let mut escape_chars = String::from(" \t│");
+    escape_chars.extend(
+        (0..128u8)
+            .map(char::from)
+            .filter(|c| c.is_ascii_punctuation() && !word_characters.contains(*c)),
+    );
+    escape_chars
+}
+
+///Parses the keystrokes of the `key_bindings` setting, logging and skipping invalid ones.
+fn parse_key_bindings(bindings: &HashMap<String, String>) -> Vec<(Keystroke, Vec<u8>)> {
+    bindings
+        .iter()
+        .filter_map(|(keystroke, sequence)| {
+            Some((
+                Keystroke::parse(keystroke).log_err()?,
+                parse_key_sequence(sequence),
+            ))
+        })
+        .collect()
+}
+
+///Turns `\xNN` escapes in a `key_bindings` sequence into the bytes they name, so that
+///sequences that aren't valid UTF-8 can be sent. Other text is sent as is.
+fn parse_key_sequence(sequence: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(sequence.len());
+    let mut rest = sequence;
+    while let Some(index) = rest.find("\\x") {
+        bytes.extend_from_slice(rest[..index].as_bytes());
+        let escape = &rest[index..];
+        match escape
+            .get(2..4)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            Some(byte) => {
+                bytes.push(byte);
+                rest = &escape[4..];
+            }
+            None => {
+                bytes.extend_from_slice(b"\\x");
+                rest = &escape[2..];
+            }
+        }
+    }
+    bytes.extend_from_slice(rest.as_bytes());
+    bytes
+}
+
+///Finds the sequence the `key_bindings` setting sends for a keystroke.
+fn key_binding_sequence<'a>(
+    bindings: &'a [(Keystroke, Vec<u8>)],
+    keystroke: &Keystroke,
+) -> Option<&'a [u8]> {
+    bindings
+        .iter()
+        .find(|(binding, _)| {
+            binding.key == keystroke.key && binding.modifiers == keystroke.modifiers
+        })
+        .map(|(_, sequence)| sequence.as_slice())
+}
+
+struct LinkRule {
+    search: RegexSearch,
+    regex: Regex,
+    url: String,
+}
+
+///Compiles the `link_rules` setting, logging and skipping rules with invalid regexes.
+fn parse_link_rules(rules: &[TerminalLinkRule]) -> Vec<LinkRule> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            Some(LinkRule {
+                search: RegexSearch::new(&rule.regex).log_err()?,
+                regex: Regex::new(&rule.regex).log_err()?,
+                url: rule.url.clone(),
+            })
+        })
+        .collect()
+}
+
+///Fills in a link rule's URL: `{0}` is the whole match and `{1}`, `{2}`, ... its groups.
+fn expand_link_url(template: &str, captures: &Captures) -> String {
+    let mut url = template.to_string();
+    for (ix, group) in captures.iter().enumerate() {
+        url = url.replace(
+            &format!("{{{ix}}}"),
+            group.map_or("", |group| group.as_str()),
+        );
+    }
+    url
+}
+
 pub struct TerminalBuilder {
     terminal: Terminal,
     events_rx: UnboundedReceiver<AlacTermEvent>,
+    wakeup_pending: Arc<AtomicBool>,
+    shell_marks_rx: Option<UnboundedReceiver<ShellMark>>,
 }
 
 impl TerminalBuilder {
@@ -312,13 +632,100 @@ impl TerminalBuilder {
         working_directory: Option<PathBuf>,
         task: Option<TaskState>,
         shell: Shell,
-        mut env: HashMap<String, String>,
+        env: HashMap<String, String>,
         blink_settings: Option<TerminalBlink>,
+        cursor_shape: TerminalCursorShape,
+        word_characters: &str,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        scroll_to_bottom_on_input: bool,
+        scroll_to_bottom_on_output: bool,
+        restored_scrollback: Option<String>,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
         cx: &mut AppContext,
+    ) -> Result<TerminalBuilder> {
+        Self::spawn(
+            working_directory,
+            task,
+            shell,
+            env,
+            blink_settings,
+            cursor_shape,
+            word_characters,
+            alternate_scroll,
+            max_scroll_history_lines,
+            scroll_to_bottom_on_input,
+            scroll_to_bottom_on_output,
+            restored_scrollback,
+            TerminalSize::default(),
+            window.window_id().as_u64(),
+            completion_tx,
+            cx,
+        )
+    }
+
+    /// Creates a terminal that runs `shell` without a window to show it in, for
+    /// integration tests and scripted automation. The grid is laid out at `size`
+    /// from the start, and the terminal still needs a model from
+    /// [`TerminalBuilder::subscribe`] to process the program's output. Write to it
+    /// with [`Terminal::input`], wait for output with [`Terminal::wait_for_output`]
+    /// and read the screen with [`Terminal::text_snapshot`].
+    pub fn headless(
+        working_directory: Option<PathBuf>,
+        shell: Shell,
+        env: HashMap<String, String>,
+        size: TerminalSize,
+        cx: &mut AppContext,
+    ) -> Result<TerminalBuilder> {
+        let settings = TerminalSettings::get_global(cx);
+        let cursor_shape = settings.cursor_shape;
+        let word_characters = settings.word_characters.clone();
+        let alternate_scroll = settings.alternate_scroll;
+        let max_scroll_history_lines = settings.max_scroll_history_lines;
+        let scroll_to_bottom_on_input = settings.scroll_to_bottom_on_input;
+        let scroll_to_bottom_on_output = settings.scroll_to_bottom_on_output;
+        let (completion_tx, _) = smol::channel::unbounded();
+        let mut builder = Self::spawn(
+            working_directory,
+            None,
+            shell,
+            env,
+            None,
+            cursor_shape,
+            &word_characters,
+            alternate_scroll,
+            max_scroll_history_lines,
+            scroll_to_bottom_on_input,
+            scroll_to_bottom_on_output,
+            None,
+            size,
+            0,
+            completion_tx,
+            cx,
+        )?;
+        builder.terminal.last_content.size = size;
+        Ok(builder)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn(
+        working_directory: Option<PathBuf>,
+        task: Option<TaskState>,
+        shell: Shell,
+        mut env: HashMap<String, String>,
+        blink_settings: Option<TerminalBlink>,
+        cursor_shape: TerminalCursorShape,
+        word_characters: &str,
+        alternate_scroll: AlternateScroll,
+        max_scroll_history_lines: Option<usize>,
+        scroll_to_bottom_on_input: bool,
+        scroll_to_bottom_on_output: bool,
+        restored_scrollback: Option<String>,
+        size: TerminalSize,
+        window_id: u64,
+        completion_tx: Sender<()>,
+        cx: &mut AppContext,
     ) -> Result<TerminalBuilder> {
         // TODO: Properly set the current locale,
         env.entry("LC_ALL".to_string())
@@ -340,6 +747,11 @@ impl TerminalBuilder {
                 Shell::WithArguments { program, args } => {
                     Some(alacritty_terminal::tty::Shell::new(program, args))
                 }
+                Shell::Remote { .. } => {
+                    let mut args = vec!["-t".to_string()];
+                    args.extend(shell.ssh_args().unwrap_or_default());
+                    Some(alacritty_terminal::tty::Shell::new("ssh".to_string(), args))
+                }
             };
 
             alacritty_terminal::tty::Options {
@@ -352,31 +764,25 @@ impl TerminalBuilder {
 
         // Setup Alacritty's env, which modifies the current process's environment
         alacritty_terminal::tty::setup_env();
+        let term_variable = pty_options
+            .env
+            .get("TERM")
+            .cloned()
+            .or_else(|| std::env::var("TERM").ok());
 
-        let scrolling_history = if task.is_some() {
-            // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling.
-            // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
-            // cause excessive memory usage over time.
-            MAX_SCROLL_HISTORY_LINES
-        } else {
-            max_scroll_history_lines
-                .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
-                .min(MAX_SCROLL_HISTORY_LINES)
-        };
-        let config = Config {
-            scrolling_history,
-            ..Config::default()
-        };
+        let config = terminal_config(
+            task.is_some(),
+            max_scroll_history_lines,
+            cursor_shape,
+            word_characters,
+        );
 
         //Spawn a task so the Alacritty EventLoop can communicate with us in a view context
         //TODO: Remove with a bounded sender which can be dispatched on &self
         let (events_tx, events_rx) = unbounded();
+        let listener = ZedListener::new(events_tx);
         //Set up the terminal...
-        let mut term = Term::new(
-            config,
-            &TerminalSize::default(),
-            ZedListener(events_tx.clone()),
-        );
+        let mut term = Term::new(config, &size, listener.clone());
 
         //Start off blinking if we need to
         if let Some(TerminalBlink::On) = blink_settings {
@@ -388,14 +794,15 @@ impl TerminalBuilder {
             term.unset_private_mode(PrivateMode::Named(NamedPrivateMode::AlternateScroll));
         }
 
+        // Written before the PTY is read from, so that it ends up above the shell's output.
+        if let Some(scrollback) = restored_scrollback {
+            write_restored_scrollback(&mut term, &scrollback);
+        }
+
         let term = Arc::new(FairMutex::new(term));
 
         //Setup the pty...
-        let pty = match tty::new(
-            &pty_options,
-            TerminalSize::default().into(),
-            window.window_id().as_u64(),
-        ) {
+        let pty = match tty::new(&pty_options, size.into(), window_id) {
             Ok(pty) => pty,
             Err(error) => {
                 bail!(TerminalError {
@@ -407,32 +814,118 @@ impl TerminalBuilder {
         };
 
         let pty_info = PtyProcessInfo::new(&pty);
+        let prompt_marks = Arc::new(Mutex::new(PromptMarks::new(scrolling_history(
+            task.is_some(),
+            max_scroll_history_lines,
+        ))));
+        let (shell_marks_tx, shell_marks_rx) = unbounded();
+        let recording = Arc::new(Mutex::new(None));
+        let pty = ShellIntegrationPty::new(
+            pty,
+            term.clone(),
+            listener.clone(),
+            prompt_marks.clone(),
+            shell_marks_tx,
+            recording.clone(),
+        );
 
         //And connect them together
-        let event_loop = EventLoop::new(
-            term.clone(),
-            ZedListener(events_tx.clone()),
-            pty,
-            pty_options.hold,
-            false,
-        )?;
+        let event_loop =
+            EventLoop::new(term.clone(), listener.clone(), pty, pty_options.hold, false)?;
 
         //Kick things off
         let pty_tx = event_loop.channel();
         let _io_thread = event_loop.spawn(); // DANGER
 
+        let mut builder = Self::from_parts(
+            term,
+            events_rx,
+            listener.wakeup_pending,
+            prompt_marks,
+            recording,
+            Some((Notifier(pty_tx), pty_info)),
+            task,
+            alternate_scroll,
+            scroll_to_bottom_on_input,
+            scroll_to_bottom_on_output,
+            completion_tx,
+            cx,
+        );
+        builder.terminal.term_variable = term_variable;
+        builder.shell_marks_rx = Some(shell_marks_rx);
+        Ok(builder)
+    }
+
+    /// Creates a terminal that plays back a recording instead of running a shell.
+    /// Nothing typed into it goes anywhere.
+    pub fn new_replay(cast: Cast, title: String, cx: &AppContext) -> TerminalBuilder {
+        let settings = TerminalSettings::get_global(cx);
+        let config = terminal_config(
+            false,
+            settings.max_scroll_history_lines,
+            settings.cursor_shape,
+            &settings.word_characters,
+        );
+        let replay = Replay::new(cast, title);
+        let (events_tx, events_rx) = unbounded();
+        let listener = ZedListener::new(events_tx);
+        let wakeup_pending = listener.wakeup_pending.clone();
+        let term = Term::new(config, &replay.fit(TerminalSize::default()), listener);
+        let alternate_scroll = settings.alternate_scroll;
+        let scroll_to_bottom_on_input = settings.scroll_to_bottom_on_input;
+        let scroll_to_bottom_on_output = settings.scroll_to_bottom_on_output;
+        let (completion_tx, _) = smol::channel::unbounded();
+
+        let prompt_marks =
+            PromptMarks::new(scrolling_history(false, settings.max_scroll_history_lines));
+        let mut builder = Self::from_parts(
+            Arc::new(FairMutex::new(term)),
+            events_rx,
+            wakeup_pending,
+            Arc::new(Mutex::new(prompt_marks)),
+            Arc::default(),
+            None,
+            None,
+            alternate_scroll,
+            scroll_to_bottom_on_input,
+            scroll_to_bottom_on_output,
+            completion_tx,
+            cx,
+        );
+        builder.terminal.replay = Some(replay);
+        builder
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_parts(
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        events_rx: UnboundedReceiver<AlacTermEvent>,
+        wakeup_pending: Arc<AtomicBool>,
+        prompt_marks: Arc<Mutex<PromptMarks>>,
+        recording: Arc<Mutex<Option<Recording>>>,
+        pty: Option<(Notifier, PtyProcessInfo)>,
+        task: Option<TaskState>,
+        alternate_scroll: AlternateScroll,
+        scroll_to_bottom_on_input: bool,
+        scroll_to_bottom_on_output: bool,
+        completion_tx: Sender<()>,
+        cx: &AppContext,
+    ) -> TerminalBuilder {
+        let (pty_tx, pty_info) = pty.unzip();
         let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
         // Optional suffix matches MSBuild diagnostic suffixes for path parsing in PathLikeWithPosition
         // https://learn.microsoft.com/en-us/visualstudio/msbuild/msbuild-diagnostic-format-for-tasks
         let word_regex =
             RegexSearch::new(r#"[\$\+\w.\[\]:/\\@\-~]+(?:\((?:\d+|\d+,\d+)\))?"#).unwrap();
 
+        let settings = TerminalSettings::get_global(cx);
         let terminal = Terminal {
             task,
-            pty_tx: Notifier(pty_tx),
+            pty_tx,
             completion_tx,
             term,
             events: VecDeque::with_capacity(10), //Should never get this high.
+            pending_copy_mode_toggles: 0,
             last_content: Default::default(),
             last_mouse: None,
             matches: Vec::new(),
@@ -441,24 +934,77 @@ impl TerminalBuilder {
             breadcrumb_text: String::new(),
             scroll_px: px(0.),
             last_mouse_position: None,
+            hovered_cell: None,
             next_link_id: 0,
             selection_phase: SelectionPhase::Ended,
-            secondary_pressed: false,
+            link_modifier: settings.link_modifier,
+            link_modifier_pressed: settings.link_modifier == TerminalLinkModifier::None,
             hovered_word: false,
             url_regex,
             word_regex,
+            alternate_scroll,
+            scroll_to_bottom_on_input,
+            scroll_to_bottom_on_output,
+            scroll_multiplier: settings.scroll_multiplier,
+            fast_scroll_multiplier: settings.fast_scroll_multiplier,
+            paste_sanitization: settings.paste_sanitization,
+            key_bindings: parse_key_bindings(&settings.key_bindings),
+            title_template: settings.title.clone(),
+            link_rules: parse_link_rules(&settings.link_rules),
+            prompt_marks,
+            command_start_column: None,
+            command_history: Vec::new(),
+            submitted_command: None,
+            running_command: None,
+            long_command_notification: settings
+                .long_command_notification_seconds
+                .map(Duration::from_secs),
+            started_at: Instant::now(),
+            window_active: true,
+            child_exit_code: None,
+            has_exited: false,
+            recording,
+            last_text_snapshot: None,
+            replay: None,
+            theme_cache: RefCell::new(None),
+            background_report: None,
+            output_waiters: OutputWaiters::default(),
+            remote_destination: None,
+            focused: false,
+            was_focused: false,
+            has_bell: false,
+            has_unread_output: false,
+            read_only: false,
+            term_variable: None,
+            pending_size: None,
+            resize_task: None,
         };
 
-        Ok(TerminalBuilder {
+        TerminalBuilder {
             terminal,
             events_rx,
-        })
+            wakeup_pending,
+            shell_marks_rx: None,
+        }
     }
 
     pub fn subscribe(mut self, cx: &mut ModelContext<Terminal>) -> Terminal {
+        if let Some(mut shell_marks_rx) = self.shell_marks_rx.take() {
+            cx.spawn(|terminal, mut cx| async move {
+                while let Some(mark) = shell_marks_rx.next().await {
+                    terminal.update(&mut cx, |terminal, cx| terminal.shell_mark(mark, cx))?;
+                }
+                anyhow::Ok(())
+            })
+            .detach();
+        }
+
         //Event loop
         cx.spawn(|terminal, mut cx| async move {
             while let Some(event) = self.events_rx.next().await {
+                if matches!(event, AlacTermEvent::Wakeup) {
+                    self.wakeup_pending.store(false, Ordering::Release);
+                }
                 terminal.update(&mut cx, |terminal, cx| {
                     //Process the first event immediately for lowered latency
                     terminal.process_event(&event, cx);
@@ -477,6 +1023,7 @@ impl TerminalBuilder {
                             event = self.events_rx.next() => {
                                 if let Some(event) = event {
                                     if matches!(event, AlacTermEvent::Wakeup) {
+                                        self.wakeup_pending.store(false, Ordering::Release);
                                         wakeup = true;
                                     } else {
                                         events.push(event);
@@ -545,6 +1092,18 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    /// Counts the syncs that produced this content.
+    pub version: u64,
+    /// The `version` in which each line of `cells` last changed, so that whatever
+    /// was laid out for a line before then can be reused.
+    pub line_versions: Vec<u64>,
+    /// The colors a program set with OSC 4, 10, 11 or 12 that the theme is drawn
+    /// with: the 16 ANSI colors, then the foreground, background and cursor.
+    pub dynamic_colors: Vec<Option<Rgb>>,
+    /// The lines on each row of `cells`: one line, or all the lines of a collapsed
+    /// command output. Empty when every row shows one line, `display_offset` lines
+    /// up from the grid line of the same index.
+    pub rows: Vec<RangeInclusive<Line>>,
 }
 
 #[derive(Clone)]
@@ -569,8 +1128,81 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            version: 0,
+            line_versions: Vec::new(),
+            dynamic_colors: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+}
+
+impl TerminalContent {
+    /// The visible cell under `position`, relative to the top left of the grid,
+    /// or `None` when it is outside of the grid.
+    pub fn cell_at(&self, position: Point<Pixels>) -> Option<&IndexedCell> {
+        if position.x < px(0.) || position.y < px(0.) {
+            return None;
+        }
+        let column = (position.x / self.size.cell_width()) as usize;
+        let line = (position.y / self.size.line_height()) as usize;
+        if column >= self.size.columns() || line >= self.size.screen_lines() {
+            return None;
+        }
+        self.cells.get(line * self.size.columns() + column)
+    }
+
+    /// The grid point under `position`, relative to the top left of the grid.
+    pub fn grid_point(&self, position: Point<Pixels>) -> AlacPoint {
+        self.grid_point_and_side(position).0
+    }
+
+    /// The grid point under `position`, and which side of its cell is nearer.
+    pub fn grid_point_and_side(&self, position: Point<Pixels>) -> (AlacPoint, Side) {
+        let (point, side) = grid_point_and_side(position, self.size, 0);
+        (
+            AlacPoint::new(self.row_line(point.line.0), point.column),
+            side,
+        )
+    }
+
+    /// The line shown on `row`, or the first of the lines a collapsed output hides
+    /// there. Rows outside of the view continue from the first and last ones.
+    pub fn row_line(&self, row: i32) -> Line {
+        let (Some(first), Some(last)) = (self.rows.first(), self.rows.last()) else {
+            return Line(row - self.display_offset as i32);
+        };
+        if row < 0 {
+            Line(first.start().0 + row)
+        } else if row as usize >= self.rows.len() {
+            Line(last.end().0 + row - self.rows.len() as i32 + 1)
+        } else {
+            *self.rows[row as usize].start()
+        }
+    }
+
+    /// The row `line` is shown on, which for the lines a collapsed output hides is
+    /// the row the output is collapsed onto. Lines outside of the view continue
+    /// from the first and last rows.
+    pub fn line_row(&self, line: Line) -> i32 {
+        let (Some(first), Some(last)) = (self.rows.first(), self.rows.last()) else {
+            return line.0 + self.display_offset as i32;
+        };
+        if line < *first.start() {
+            line.0 - first.start().0
+        } else if line > *last.end() {
+            self.rows.len() as i32 - 1 + line.0 - last.end().0
+        } else {
+            self.rows.partition_point(|row| *row.end() < line) as i32
         }
     }
+
+    /// Whether `line` is in a collapsed output, rather than shown on its own row.
+    pub fn is_collapsed(&self, line: Line) -> bool {
+        usize::try_from(self.line_row(line))
+            .ok()
+            .and_then(|row| self.rows.get(row))
+            .map_or(false, |row| row.start() != row.end())
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -580,27 +1212,99 @@ pub enum SelectionPhase {
 }
 
 pub struct Terminal {
-    pty_tx: Notifier,
+    pty_tx: Option<Notifier>,
     completion_tx: Sender<()>,
     term: Arc<FairMutex<Term<ZedListener>>>,
     events: VecDeque<InternalEvent>,
+    /// Copy mode toggles queued in `events` since the last sync
+    pending_copy_mode_toggles: usize,
     /// This is only used for mouse mode cell change detection
     last_mouse: Option<(AlacPoint, AlacDirection)>,
     /// This is only used for terminal hovered word checking
     last_mouse_position: Option<Point<Pixels>>,
+    hovered_cell: Option<AlacPoint>,
     pub matches: Vec<RangeInclusive<AlacPoint>>,
     pub last_content: TerminalContent,
     pub selection_head: Option<AlacPoint>,
     pub breadcrumb_text: String,
-    pub pty_info: PtyProcessInfo,
+    pub pty_info: Option<PtyProcessInfo>,
     scroll_px: Pixels,
     next_link_id: usize,
     selection_phase: SelectionPhase,
-    secondary_pressed: bool,
+    link_modifier: TerminalLinkModifier,
+    link_modifier_pressed: bool,
     hovered_word: bool,
     url_regex: RegexSearch,
     word_regex: RegexSearch,
     task: Option<TaskState>,
+    alternate_scroll: AlternateScroll,
+    scroll_to_bottom_on_input: bool,
+    scroll_to_bottom_on_output: bool,
+    scroll_multiplier: f32,
+    fast_scroll_multiplier: f32,
+    paste_sanitization: PasteSanitization,
+    key_bindings: Vec<(Keystroke, Vec<u8>)>,
+    title_template: String,
+    link_rules: Vec<LinkRule>,
+    /// Where the shell's integration marked its prompts and commands. The PTY
+    /// reader places them, so lock this only while holding `term`'s lock.
+    prompt_marks: Arc<Mutex<PromptMarks>>,
+    /// Where typing began on the line of the command being entered, which is
+    /// where the prompt ends.
+    command_start_column: Option<usize>,
+    /// The commands submitted in this terminal, oldest first and without repeats.
+    command_history: Vec<String>,
+    /// The last command submitted, until the shell reports that it started running.
+    submitted_command: Option<String>,
+    running_command: Option<RunningCommand>,
+    long_command_notification: Option<Duration>,
+    started_at: Instant,
+    window_active: bool,
+    child_exit_code: Option<i32>,
+    has_exited: bool,
+    /// Shared with the PTY reader, which records the output it reads while it holds
+    /// `term`'s lock. Recordings start under that lock too, so that they begin with
+    /// the screen the output that follows is drawn over.
+    recording: Arc<Mutex<Option<Recording>>>,
+    last_text_snapshot: Option<TextSnapshot>,
+    replay: Option<Replay>,
+    theme_cache: RefCell<Option<CachedTheme>>,
+    background_report: Option<BackgroundReport>,
+    output_waiters: OutputWaiters,
+    remote_destination: Option<String>,
+    focused: bool,
+    /// Output that arrives before the terminal is first focused is the shell starting
+    /// up, which shouldn't count as unread.
+    was_focused: bool,
+    has_bell: bool,
+    has_unread_output: bool,
+    read_only: bool,
+    term_variable: Option<String>,
+    /// The latest size asked for while a series of resizes is still going on.
+    pending_size: Option<TerminalSize>,
+    resize_task: Option<Task<()>>,
+}
+
+/// A command the shell reported running that it hasn't reported finished yet.
+struct RunningCommand {
+    /// Empty when it wasn't submitted through Zed.
+    command: String,
+    started_at: Instant,
+}
+
+/// How to answer the last OSC 11 background color query, and the color it was
+/// answered with.
+struct BackgroundReport {
+    format: Arc<dyn Fn(Rgb) -> String + Sync + Send>,
+    last_color: Rgb,
+}
+
+/// The result of [`theme_with_dynamic_colors`], along with everything it depends on.
+struct CachedTheme {
+    base: Arc<Theme>,
+    overrides: [TerminalColors; 2],
+    dynamic_colors: Vec<Option<Rgb>>,
+    theme: Arc<Theme>,
 }
 
 pub struct TaskState {
@@ -645,10 +1349,12 @@ impl Terminal {
             AlacTermEvent::Title(title) => {
                 self.breadcrumb_text = title.to_string();
                 cx.emit(Event::BreadcrumbsChanged);
+                cx.emit(Event::TitleChanged(self.title(false)));
             }
             AlacTermEvent::ResetTitle => {
                 self.breadcrumb_text = String::new();
                 cx.emit(Event::BreadcrumbsChanged);
+                cx.emit(Event::TitleChanged(self.title(false)));
             }
             AlacTermEvent::ClipboardStore(_, data) => {
                 cx.write_to_clipboard(ClipboardItem::new_string(data.to_string()))
@@ -671,16 +1377,52 @@ impl Terminal {
             }
             AlacTermEvent::Bell => {
                 cx.emit(Event::Bell);
+                if !self.has_bell && TerminalSettings::get_global(cx).bell != TerminalBell::None {
+                    self.has_bell = true;
+                    cx.emit(Event::ActivityChanged(cx.entity_id()));
+                }
+            }
+            AlacTermEvent::Exit => {
+                // Output that arrived with the exit won't be followed by a wakeup.
+                if !self.output_waiters.is_empty() {
+                    self.output_waiters.resolve(&self.text_snapshot());
+                    self.output_waiters.clear();
+                }
+                self.register_task_finished(None, cx)
             }
-            AlacTermEvent::Exit => self.register_task_finished(None, cx),
             AlacTermEvent::MouseCursorDirty => {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
+                if self.scroll_to_bottom_on_output
+                    && self.last_content.display_offset != 0
+                    && !self.copy_mode_active()
+                {
+                    self.events
+                        .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+                }
+                if let Some(previous) = self.last_text_snapshot.as_ref() {
+                    let snapshot = TextSnapshot::new(&*self.term.lock());
+                    if let Some(change) = snapshot.changes_since(previous) {
+                        cx.emit(Event::TextChanged(change));
+                    }
+                    self.last_text_snapshot = Some(snapshot);
+                }
+                if !self.focused && self.was_focused && !self.has_unread_output {
+                    self.has_unread_output = true;
+                    cx.emit(Event::ActivityChanged(cx.entity_id()));
+                }
+                if !self.output_waiters.is_empty() {
+                    self.output_waiters.resolve(&self.text_snapshot());
+                }
                 cx.emit(Event::Wakeup);
 
-                if self.pty_info.has_changed() {
-                    cx.emit(Event::TitleChanged);
+                if self
+                    .pty_info
+                    .as_mut()
+                    .map_or(false, |pty_info| pty_info.has_changed())
+                {
+                    cx.emit(Event::TitleChanged(self.title(false)));
                 }
             }
             AlacTermEvent::ColorRequest(index, format) => {
@@ -693,8 +1435,14 @@ impl Terminal {
                 // we might respond with out of date value if a "set color" sequence is immediately
                 // followed by a color request sequence.
                 let color = self.term.lock().colors()[*index].unwrap_or_else(|| {
-                    to_alac_rgb(get_color_at_index(*index, cx.theme().as_ref()))
+                    to_alac_rgb(get_color_at_index(*index, &terminal_theme(cx)))
                 });
+                if *index == NamedColor::Background as usize {
+                    self.background_report = Some(BackgroundReport {
+                        format: format.clone(),
+                        last_color: color,
+                    });
+                }
                 self.write_to_pty(format(color));
             }
             AlacTermEvent::ChildExit(error_code) => {
@@ -707,8 +1455,181 @@ impl Terminal {
         self.selection_phase == SelectionPhase::Selecting
     }
 
+    /// The program the shell is currently running in the foreground, if any.
+    pub fn foreground_process_info(&mut self) -> Option<ProcessInfo> {
+        if self.has_exited {
+            return None;
+        }
+        self.pty_info.as_mut()?.foreground_process()
+    }
+
+    /// The name of the program in the foreground, which is the shell while it waits
+    /// for a command.
+    pub fn foreground_process_name(&mut self) -> Option<String> {
+        if self.has_exited {
+            return None;
+        }
+        let process = self.pty_info.as_mut()?.foreground_process_or_shell()?;
+        Some(process.name)
+    }
+
+    /// The working directory of the program in the foreground, which may differ from
+    /// the shell's when the program changed it.
+    pub fn foreground_process_cwd(&mut self) -> Option<PathBuf> {
+        if self.has_exited {
+            return None;
+        }
+        let process = self.pty_info.as_mut()?.foreground_process_or_shell()?;
+        Some(process.cwd).filter(|cwd| !cwd.as_os_str().is_empty())
+    }
+
+    /// Starts writing the shell's output to an asciicast file at `path`, replacing
+    /// any recording in progress.
+    pub fn start_recording(&mut self, path: PathBuf, cx: &ModelContext<Self>) -> Result<()> {
+        if self.pty_tx.is_none() {
+            bail!("only terminals running a shell can be recorded");
+        }
+        let title = self.title(false);
+        let term = self.term.lock();
+        let recording = Recording::start(path, &term, &title)?;
+        let previous = self.recording.lock().replace(recording);
+        drop(term);
+        if let Some(previous) = previous {
+            cx.background_executor()
+                .spawn(async move { previous.finish().log_err() })
+                .detach();
+        }
+        Ok(())
+    }
+
+    /// Finishes the current recording, resolving to the path it was written to once
+    /// the output recorded so far is written.
+    pub fn stop_recording(&mut self, cx: &ModelContext<Self>) -> Option<Task<Result<PathBuf>>> {
+        let recording = self.recording.lock().take()?;
+        Some(
+            cx.background_executor()
+                .spawn(async move { recording.finish() }),
+        )
+    }
+
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        self.recording
+            .lock()
+            .as_ref()
+            .map(|recording| recording.path().to_path_buf())
+    }
+
+    /// Freshly queries what the terminal is running, along with its size and the
+    /// modes the program turned on.
+    pub fn inspect(&mut self) -> TerminalInspection {
+        let (shell, foreground) = match self.pty_info.as_mut() {
+            Some(pty_info) => (pty_info.shell_process(), pty_info.foreground_process()),
+            None => (None, None),
+        };
+        TerminalInspection {
+            shell,
+            foreground,
+            term_variable: self.term_variable.clone(),
+            columns: self.last_content.size.num_columns(),
+            rows: self.last_content.size.num_lines(),
+            modes: self
+                .last_content
+                .mode
+                .iter_names()
+                .map(|(name, _)| name)
+                .collect(),
+        }
+    }
+
+    /// The visible text and the cursor position, for assistive technologies.
+    pub fn text_snapshot(&self) -> TextSnapshot {
+        TextSnapshot::new(&*self.term.lock())
+    }
+
+    /// Resolves once the visible text, with its lines joined by newlines, matches
+    /// `pattern`, which may already be the case. Fails if the program exits first.
+    pub fn wait_for_output(
+        &mut self,
+        pattern: Regex,
+    ) -> impl Future<Output = Result<TextSnapshot>> + 'static {
+        let output = self.output_waiters.wait_for(pattern, self.text_snapshot());
+        async move {
+            output
+                .await
+                .map_err(|_| anyhow!("the terminal exited before its output matched"))
+        }
+    }
+
+    /// Starts or stops emitting [`Event::TextChanged`] whenever the program's output
+    /// changes the visible text, so that it can be read out as it arrives.
+    pub fn set_report_text_changes(&mut self, report: bool) {
+        self.last_text_snapshot = report.then(|| self.text_snapshot());
+    }
+
+    /// How far a terminal created with [`TerminalBuilder::new_replay`] has played.
+    pub fn replay_progress(&self) -> Option<ReplayProgress> {
+        self.replay.as_ref().map(|replay| replay.progress())
+    }
+
+    /// Pauses a playing replay, or resumes it, starting over once it reached the end.
+    pub fn toggle_replay_playback(&mut self, cx: &mut ModelContext<Self>) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        if replay.is_playing() {
+            replay.pause();
+        } else {
+            let progress = replay.progress();
+            if progress.position >= progress.duration {
+                replay.seek(
+                    Duration::ZERO,
+                    &mut *self.term.lock(),
+                    self.last_content.size,
+                );
+            }
+            replay.play();
+            self.advance_replay(cx);
+        }
+        cx.notify();
+    }
+
+    pub fn seek_replay(&mut self, position: Duration, cx: &mut ModelContext<Self>) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        replay.seek(position, &mut *self.term.lock(), self.last_content.size);
+        self.advance_replay(cx);
+    }
+
+    /// Applies the replayed output that is due, and schedules the next update.
+    fn advance_replay(&mut self, cx: &mut ModelContext<Self>) {
+        let Some(replay) = self.replay.as_mut() else {
+            return;
+        };
+        let position = replay.position();
+        replay.apply_until(position, &mut *self.term.lock(), self.last_content.size);
+
+        replay.playback = None;
+        if replay.is_playing() {
+            match replay.next_event_time() {
+                Some(next_event_time) => {
+                    let delay = next_event_time.saturating_sub(position);
+                    replay.playback = Some(cx.spawn(|terminal, mut cx| async move {
+                        cx.background_executor().timer(delay).await;
+                        terminal
+                            .update(&mut cx, |terminal, cx| terminal.advance_replay(cx))
+                            .ok();
+                    }));
+                }
+                None => replay.pause(),
+            }
+        }
+        cx.emit(Event::Wakeup);
+    }
+
     pub fn get_cwd(&self) -> Option<PathBuf> {
-        self.pty_info.current.as_ref().map(|info| info.cwd.clone())
+        let info = self.pty_info.as_ref()?.current.as_ref()?;
+        Some(info.cwd.clone())
     }
 
     ///Takes events from Alacritty and translates them to behavior on this view
@@ -725,16 +1646,34 @@ impl Terminal {
 
                 self.last_content.size = new_size;
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                if let Some(pty_tx) = &self.pty_tx {
+                    pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                }
+
+                let new_size = self
+                    .replay
+                    .as_ref()
+                    .map_or(new_size, |replay| replay.fit(new_size));
+                self.prompt_marks
+                    .lock()
+                    .resize(term, |term| term.resize(new_size));
 
-                term.resize(new_size);
+                if let Some(recording) = self.recording.lock().as_ref() {
+                    recording.record_resize(new_size.num_columns(), new_size.num_lines());
+                }
             }
             InternalEvent::Clear => {
+                let cursor = term.grid().cursor.point;
+                // Only the cursor's line is left, at the top.
+                if !term.mode().contains(TermMode::ALT_SCREEN) {
+                    self.prompt_marks
+                        .lock()
+                        .scroll_off(term.grid().history_size() + cursor.line.0 as usize);
+                }
+
                 // Clear back buffer
                 term.clear_screen(ClearMode::Saved);
 
-                let cursor = term.grid().cursor.point;
-
                 // Clear the lines above
                 term.grid_mut().reset_region(..cursor.line);
 
@@ -761,6 +1700,32 @@ impl Terminal {
 
                 cx.emit(Event::Wakeup);
             }
+            InternalEvent::ClearScreen => {
+                // Full-screen programs own the alternate screen and would draw over
+                // the cleared lines, so ask them to redraw instead, as ctrl-l does.
+                if term.mode().contains(TermMode::ALT_SCREEN) {
+                    self.write_user_input_to_pty(b"\x0c".to_vec());
+                    return;
+                }
+                // Scroll the lines above the cursor into the history rather than
+                // discarding them, leaving the cursor line at the top.
+                let cursor_line = term.grid().cursor.point.line;
+                if cursor_line.0 > 0 {
+                    term.scroll_up(cursor_line.0 as usize);
+                    term.grid_mut().cursor.point.line = Line(0);
+                }
+                if term.screen_lines() > 1 {
+                    term.grid_mut().reset_region(Line(1)..);
+                }
+                cx.emit(Event::Wakeup);
+            }
+            InternalEvent::ClearScrollback => {
+                self.prompt_marks
+                    .lock()
+                    .scroll_off(term.grid().history_size());
+                term.clear_screen(ClearMode::Saved);
+                cx.emit(Event::Wakeup);
+            }
             InternalEvent::Scroll(scroll) => {
                 term.scroll_display(*scroll);
                 self.refresh_hovered_word();
@@ -778,14 +1743,13 @@ impl Terminal {
                 }
                 cx.emit(Event::SelectionsChanged)
             }
-            InternalEvent::UpdateSelection(position) => {
+            InternalEvent::UpdateSelection(position, selection_type) => {
                 if let Some(mut selection) = term.selection.take() {
-                    let (point, side) = grid_point_and_side(
-                        *position,
-                        self.last_content.size,
-                        term.grid().display_offset(),
-                    );
+                    let (point, side) = self.last_content.grid_point_and_side(*position);
 
+                    if let Some(selection_type) = selection_type {
+                        selection.ty = *selection_type;
+                    }
                     selection.update(point, side);
                     term.selection = Some(selection);
 
@@ -800,89 +1764,164 @@ impl Terminal {
             }
 
             InternalEvent::Copy => {
-                if let Some(txt) = term.selection_to_string() {
-                    cx.write_to_clipboard(ClipboardItem::new_string(txt))
+                let settings = TerminalSettings::get_global(cx);
+                let text = if settings.copy_join_wrapped_lines {
+                    term.selection_to_string()
+                } else {
+                    term.selection
+                        .as_ref()
+                        .and_then(|selection| selection.to_range(term))
+                        .map(|range| formatted_copy::plain_text(term, &range, false))
+                };
+                if let Some(mut text) = text {
+                    if settings.copy_trim_trailing_whitespace {
+                        text = trim_trailing_whitespace(&text);
+                    }
+                    cx.write_to_clipboard(ClipboardItem::new_string(text))
                 }
             }
-            InternalEvent::ScrollToAlacPoint(point) => {
-                term.scroll_to_point(*point);
-                self.refresh_hovered_word();
+            InternalEvent::CopyWithFormatting(format) => {
+                if let Some(range) = term.selection.as_ref().and_then(|sel| sel.to_range(term)) {
+                    let text = formatted_copy::format_selection(
+                        term,
+                        &range,
+                        *format,
+                        &theme_with_dynamic_colors(&dynamic_colors(term.colors()), cx),
+                    );
+                    cx.write_to_clipboard(ClipboardItem::new_string(text))
+                }
             }
-            InternalEvent::FindHyperlink(position, open) => {
-                let prev_hovered_word = self.last_content.last_hovered_word.take();
-
-                let point = grid_point(
-                    *position,
-                    self.last_content.size,
-                    term.grid().display_offset(),
-                )
-                .grid_clamp(term, Boundary::Grid);
-
-                let link = term.grid().index(point).hyperlink();
-                let found_word = if link.is_some() {
-                    let mut min_index = point;
-                    loop {
-                        let new_min_index = min_index.sub(term, Boundary::Cursor, 1);
-                        if new_min_index == min_index {
-                            break;
-                        } else if term.grid().index(new_min_index).hyperlink() != link {
-                            break;
-                        } else {
-                            min_index = new_min_index
+            InternalEvent::ScrollToPreviousCommand
+            | InternalEvent::ScrollToNextCommand
+            | InternalEvent::ScrollToPreviousPrompt
+            | InternalEvent::ScrollToNextPrompt => {
+                let display_offset = {
+                    let prompt_marks = self.prompt_marks.lock();
+                    let top = prompt_marks.top_line(term);
+                    let line = match event {
+                        InternalEvent::ScrollToPreviousCommand => {
+                            prompt_marks.previous_boundary(term, top)
                         }
-                    }
-
-                    let mut max_index = point;
-                    loop {
-                        let new_max_index = max_index.add(term, Boundary::Cursor, 1);
-                        if new_max_index == max_index {
-                            break;
-                        } else if term.grid().index(new_max_index).hyperlink() != link {
-                            break;
-                        } else {
-                            max_index = new_max_index
+                        InternalEvent::ScrollToPreviousPrompt => {
+                            prompt_marks.previous_prompt(term, top)
                         }
+                        // Past the last one, there's only the bottom left to go to.
+                        InternalEvent::ScrollToNextCommand => Some(
+                            prompt_marks
+                                .next_boundary(term, top)
+                                .unwrap_or(term.bottommost_line()),
+                        ),
+                        _ => Some(
+                            prompt_marks
+                                .next_prompt(term, top)
+                                .unwrap_or(term.bottommost_line()),
+                        ),
+                    };
+                    line.map(|line| prompt_marks.display_offset_for(term, line))
+                };
+                if let Some(display_offset) = display_offset {
+                    let delta = display_offset as i32 - term.grid().display_offset() as i32;
+                    term.scroll_display(AlacScroll::Delta(delta));
+                    self.refresh_hovered_word();
+                }
+            }
+            InternalEvent::SelectLastCommandOutput => {
+                let output = self.prompt_marks.lock().last_command_output(term);
+                if let Some((start, end)) = output {
+                    let mut selection = Selection::new(SelectionType::Simple, start, Side::Left);
+                    selection.update(end, Side::Left);
+                    term.selection = Some(selection);
+
+                    #[cfg(target_os = "linux")]
+                    if let Some(selection_text) = term.selection_to_string() {
+                        cx.write_to_primary(ClipboardItem::new_string(selection_text));
                     }
 
-                    let url = link.unwrap().uri().to_owned();
-                    let url_match = min_index..=max_index;
-
-                    Some((url, true, url_match))
-                } else if let Some(url_match) = regex_match_at(term, point, &mut self.url_regex) {
-                    let url = term.bounds_to_string(*url_match.start(), *url_match.end());
-                    Some((url, true, url_match))
-                } else if let Some(word_match) = regex_match_at(term, point, &mut self.word_regex) {
-                    let file_path = term.bounds_to_string(*word_match.start(), *word_match.end());
-
-                    let (sanitized_match, sanitized_word) =
-                        if file_path.starts_with('[') && file_path.ends_with(']') {
-                            (
-                                Match::new(
-                                    word_match.start().add(term, Boundary::Cursor, 1),
-                                    word_match.end().sub(term, Boundary::Cursor, 1),
-                                ),
-                                file_path[1..file_path.len() - 1].to_owned(),
-                            )
-                        } else {
-                            (word_match, file_path)
-                        };
+                    self.selection_head = Some(end);
+                    cx.emit(Event::SelectionsChanged);
+                }
+            }
+            InternalEvent::ToggleCommandOutput => {
+                let mut prompt_marks = self.prompt_marks.lock();
+                let bottom = prompt_marks.bottom_line(term);
+                if prompt_marks.toggle_output(term, bottom) {
+                    cx.emit(Event::Wakeup);
+                }
+            }
+            InternalEvent::ExpandCommandOutput(line) => {
+                self.prompt_marks.lock().expand_output(term, *line);
+                cx.emit(Event::Wakeup);
+            }
+            InternalEvent::ExpandAllCommandOutput => {
+                self.prompt_marks.lock().expand_all();
+                cx.emit(Event::Wakeup);
+            }
+            InternalEvent::ScrollToAlacPoint(point) => {
+                // Search matches in collapsed output are shown by expanding it.
+                self.prompt_marks.lock().expand_output(term, point.line);
+                term.scroll_to_point(*point);
+                // Keep the copy mode cursor on the search match, leaving the
+                // match selected so that it can be yanked directly.
+                if term.mode().contains(TermMode::VI) {
+                    term.vi_mode_cursor.point = *point;
+                }
+                self.refresh_hovered_word();
+            }
+            InternalEvent::ToggleViMode => {
+                term.toggle_vi_mode();
+                if !term.mode().contains(TermMode::VI) {
+                    term.selection = None;
+                    term.scroll_display(AlacScroll::Bottom);
+                    cx.emit(Event::SelectionsChanged);
+                }
+            }
+            InternalEvent::ViMotion(motion) => {
+                term.vi_motion(*motion);
+                if term.selection.is_some() {
+                    cx.emit(Event::SelectionsChanged);
+                }
+            }
+            InternalEvent::ToggleViSelection(selection_type) => {
+                if !term.mode().contains(TermMode::VI) {
+                    return;
+                }
 
-                    Some((sanitized_word, false, sanitized_match))
-                } else {
-                    None
-                };
+                match term.selection.as_mut() {
+                    Some(selection) if selection.ty == *selection_type => {
+                        term.selection = None;
+                    }
+                    Some(selection) => {
+                        selection.ty = *selection_type;
+                    }
+                    None => {
+                        let mut selection =
+                            Selection::new(*selection_type, term.vi_mode_cursor.point, Side::Left);
+                        selection.include_all();
+                        term.selection = Some(selection);
+                    }
+                }
+                cx.emit(Event::SelectionsChanged);
+            }
+            InternalEvent::FindHyperlink(position, open) => {
+                let prev_hovered_word = self.last_content.last_hovered_word.take();
+
+                let point = self
+                    .last_content
+                    .grid_point(*position)
+                    .grid_clamp(term, Boundary::Grid);
+
+                let found_word = navigation_word_at(
+                    term,
+                    point,
+                    &mut self.url_regex,
+                    &mut self.link_rules,
+                    &mut self.word_regex,
+                );
 
                 match found_word {
                     Some((maybe_url_or_path, is_url, url_match)) => {
                         if *open {
-                            let target = if is_url {
-                                MaybeNavigationTarget::Url(maybe_url_or_path)
-                            } else {
-                                MaybeNavigationTarget::PathLike(PathLikeTarget {
-                                    maybe_path: maybe_url_or_path,
-                                    terminal_dir: self.get_cwd(),
-                                })
-                            };
+                            let target = self.navigation_target(maybe_url_or_path, is_url);
                             cx.emit(Event::Open(target));
                         } else {
                             self.update_selected_word(
@@ -930,15 +1969,24 @@ impl Terminal {
             word_match,
             id: self.next_link_id(),
         });
-        let navigation_target = if is_url {
+        let navigation_target = self.navigation_target(word, is_url);
+        cx.emit(Event::NewNavigationTarget(Some(navigation_target)));
+    }
+
+    fn navigation_target(&self, word: String, is_url: bool) -> MaybeNavigationTarget {
+        if is_url {
             MaybeNavigationTarget::Url(word)
+        } else if is_commit_sha(&word) {
+            MaybeNavigationTarget::Commit(CommitTarget {
+                sha: word,
+                terminal_dir: self.get_cwd(),
+            })
         } else {
             MaybeNavigationTarget::PathLike(PathLikeTarget {
                 maybe_path: word,
                 terminal_dir: self.get_cwd(),
             })
-        };
-        cx.emit(Event::NewNavigationTarget(Some(navigation_target)));
+        }
     }
 
     fn next_link_id(&mut self) -> usize {
@@ -1008,10 +2056,71 @@ impl Terminal {
         self.events.push_back(InternalEvent::Copy);
     }
 
+    pub fn copy_with_formatting(&mut self, format: CopyFormat) {
+        self.events
+            .push_back(InternalEvent::CopyWithFormatting(format));
+    }
+
     pub fn clear(&mut self) {
         self.events.push_back(InternalEvent::Clear)
     }
 
+    /// Clears the visible screen down to the cursor line, keeping the history.
+    pub fn clear_screen(&mut self) {
+        self.events.push_back(InternalEvent::ClearScreen)
+    }
+
+    /// Discards the history without touching what is on screen.
+    pub fn clear_scrollback(&mut self) {
+        self.events.push_back(InternalEvent::ClearScrollback)
+    }
+
+    /// Whether the keyboard-driven copy mode is active. While it is, keystrokes
+    /// move a cursor over the grid instead of being sent to the shell. Counts
+    /// toggles that haven't been applied to the grid yet.
+    pub fn copy_mode_active(&self) -> bool {
+        self.last_content.mode.contains(TermMode::VI) ^ (self.pending_copy_mode_toggles % 2 == 1)
+    }
+
+    pub fn toggle_copy_mode(&mut self) {
+        self.pending_copy_mode_toggles += 1;
+        self.events.push_back(InternalEvent::ToggleViMode);
+    }
+
+    /// Whether input is blocked. A read-only terminal still shows output, but
+    /// nothing typed, pasted or clicked in it reaches the program.
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        if read_only {
+            self.command_start_column = None;
+        }
+    }
+
+    /// Moves the copy mode cursor, extending the selection if one was started.
+    pub fn copy_mode_move(&mut self, motion: CopyModeMotion) {
+        self.events
+            .push_back(InternalEvent::ViMotion(motion.into()));
+    }
+
+    /// Starts a selection at the copy mode cursor, or clears the current one
+    /// if it already has the given type.
+    pub fn copy_mode_toggle_selection(&mut self, selection_type: SelectionType) {
+        self.events
+            .push_back(InternalEvent::ToggleViSelection(selection_type));
+    }
+
+    /// Copies the selection to the clipboard and leaves copy mode.
+    pub fn copy_mode_yank(&mut self) {
+        if self.copy_mode_active() {
+            self.copy();
+            self.toggle_copy_mode();
+        }
+    }
+
     pub fn scroll_line_up(&mut self) {
         self.events
             .push_back(InternalEvent::Scroll(AlacScroll::Delta(1)));
@@ -1052,41 +2161,300 @@ impl Terminal {
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
     }
 
-    ///Resize the terminal and the PTY.
-    pub fn set_size(&mut self, new_size: TerminalSize) {
-        if self.last_content.size != new_size {
-            self.events.push_back(InternalEvent::Resize(new_size))
+    /// Scrolls the last place above the top of the view where a command's output
+    /// starts or ends, as the shell marked it, to the top.
+    pub fn scroll_to_previous_command(&mut self) {
+        self.events
+            .push_back(InternalEvent::ScrollToPreviousCommand);
+    }
+
+    /// Scrolls the next place below the top of the view where a command's output
+    /// starts or ends to the top, or to the bottom past the last one.
+    pub fn scroll_to_next_command(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextCommand);
+    }
+
+    /// Scrolls the last prompt the shell marked above the top of the view to the
+    /// top.
+    pub fn scroll_to_previous_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToPreviousPrompt);
+    }
+
+    /// Scrolls the next prompt the shell marked below the top of the view to the
+    /// top, or to the bottom past the last one.
+    pub fn scroll_to_next_prompt(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextPrompt);
+    }
+
+    /// Selects what the last command the shell marked as finished wrote.
+    pub fn select_last_command_output(&mut self) {
+        self.events
+            .push_back(InternalEvent::SelectLastCommandOutput);
+    }
+
+    /// Collapses what the last command finished in view wrote onto one row, or
+    /// expands it when it is collapsed.
+    pub fn toggle_command_output(&mut self) {
+        self.events.push_back(InternalEvent::ToggleCommandOutput);
+    }
+
+    pub fn expand_all_command_output(&mut self) {
+        self.events.push_back(InternalEvent::ExpandAllCommandOutput);
+    }
+
+    /// Resizes the terminal and the PTY. Each resize reflows the whole scrollback
+    /// and signals the program, so while a window is being resized, the grid keeps
+    /// its size until the resizing pauses, then takes the last size asked for. The
+    /// first resize of a series applies right away, so that a terminal that was
+    /// just laid out starts at its size.
+    pub fn set_size(&mut self, new_size: TerminalSize, cx: &mut ModelContext<Self>) {
+        if self.pending_size.unwrap_or(self.last_content.size) == new_size {
+            return;
+        }
+        if self.resize_task.is_none() {
+            self.events.push_back(InternalEvent::Resize(new_size));
+        } else {
+            self.pending_size = Some(new_size);
+        }
+        self.resize_task = Some(cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(RESIZE_DEBOUNCE).await;
+            this.update(&mut cx, |this, cx| {
+                this.resize_task = None;
+                if let Some(size) = this.pending_size.take() {
+                    if size != this.last_content.size {
+                        this.events.push_back(InternalEvent::Resize(size));
+                        cx.notify();
+                    }
+                }
+            })
+            .ok();
+        }));
+    }
+
+    ///Apply settings that are fixed when the emulator is created, so that changes
+    ///reach terminals which are already running.
+    pub fn apply_settings(&mut self, settings: &TerminalSettings) {
+        let mut term = self.term.lock();
+        self.prompt_marks.lock().set_history_limit(
+            scrolling_history(self.task.is_some(), settings.max_scroll_history_lines),
+            &mut *term,
+        );
+        term.set_options(terminal_config(
+            self.task.is_some(),
+            settings.max_scroll_history_lines,
+            settings.cursor_shape,
+            &settings.word_characters,
+        ));
+        self.scroll_to_bottom_on_input = settings.scroll_to_bottom_on_input;
+        self.scroll_to_bottom_on_output = settings.scroll_to_bottom_on_output;
+        self.scroll_multiplier = settings.scroll_multiplier;
+        self.fast_scroll_multiplier = settings.fast_scroll_multiplier;
+        self.paste_sanitization = settings.paste_sanitization;
+        self.long_command_notification = settings
+            .long_command_notification_seconds
+            .map(Duration::from_secs);
+        self.key_bindings = parse_key_bindings(&settings.key_bindings);
+        self.title_template.clone_from(&settings.title);
+        self.link_rules = parse_link_rules(&settings.link_rules);
+        if self.link_modifier != settings.link_modifier {
+            self.link_modifier = settings.link_modifier;
+            self.link_modifier_pressed = settings.link_modifier == TerminalLinkModifier::None;
+        }
+
+        // Programs may toggle alternate scrolling themselves, so only touch the mode
+        // when the setting actually changed.
+        if self.alternate_scroll != settings.alternate_scroll {
+            self.alternate_scroll = settings.alternate_scroll;
+            let mode = PrivateMode::Named(NamedPrivateMode::AlternateScroll);
+            match settings.alternate_scroll {
+                AlternateScroll::On => term.set_private_mode(mode),
+                AlternateScroll::Off => term.unset_private_mode(mode),
+            }
         }
     }
 
     ///Write the Input payload to the tty.
     fn write_to_pty(&self, input: String) {
-        self.pty_tx.notify(input.into_bytes());
+        self.write_bytes_to_pty(input.into_bytes());
     }
 
     fn write_bytes_to_pty(&self, input: Vec<u8>) {
-        self.pty_tx.notify(input);
+        if let Some(pty_tx) = &self.pty_tx {
+            pty_tx.notify(input);
+        }
+    }
+
+    /// Writes what the user typed or clicked, unless the terminal is read-only.
+    /// Replies to the program's own queries go through [`Self::write_to_pty`].
+    fn write_user_input_to_pty(&self, input: Vec<u8>) {
+        if !self.read_only {
+            self.write_bytes_to_pty(input);
+        }
     }
 
     pub fn input(&mut self, input: String) {
-        self.events
-            .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+        if self.read_only {
+            return;
+        }
+        if self.scroll_to_bottom_on_input {
+            self.events
+                .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+        }
         self.events.push_back(InternalEvent::SetSelection(None));
 
         self.write_to_pty(input);
     }
 
     pub fn input_bytes(&mut self, input: Vec<u8>) {
-        self.events
-            .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+        if self.read_only {
+            return;
+        }
+        if self.scroll_to_bottom_on_input {
+            self.events
+                .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+        }
         self.events.push_back(InternalEvent::SetSelection(None));
 
         self.write_bytes_to_pty(input);
     }
 
+    /// Notes where the prompt ends when the first key of a command is typed. The
+    /// grid is read before the key reaches the shell, so its echo isn't included.
+    fn start_command(&mut self) {
+        if self.command_start_column.is_none() {
+            let term = self.term.lock();
+            if !term.mode().contains(TermMode::ALT_SCREEN) {
+                self.command_start_column = Some(term.grid().cursor.point.column.0);
+            }
+        }
+    }
+
+    /// Marks the cursor's line as one where a command was submitted, and records
+    /// the command, before the enter that submits it reaches the shell.
+    fn submit_command(&mut self) {
+        let term = self.term.clone();
+        let term = term.lock();
+        let start_column = self.command_start_column.take();
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+        if let Some(start_column) = start_column {
+            let command = command_text::command_text(&term, start_column);
+            drop(term);
+            self.command_submitted(command);
+        }
+    }
+
+    fn command_submitted(&mut self, command: String) {
+        if command.is_empty() {
+            return;
+        }
+        self.submitted_command = Some(command.clone());
+        self.command_history.retain(|entry| *entry != command);
+        self.command_history.push(command);
+        if self.command_history.len() > MAX_COMMAND_HISTORY {
+            self.command_history.remove(0);
+        }
+    }
+
+    /// Times commands by the marks the shell's integration sends around them, to
+    /// notify when a long one finishes. Shells without it send none.
+    fn shell_mark(&mut self, mark: ShellMark, cx: &mut ModelContext<Self>) {
+        match mark {
+            ShellMark::CommandExecuted => {
+                self.running_command = Some(RunningCommand {
+                    command: self.submitted_command.take().unwrap_or_default(),
+                    started_at: Instant::now(),
+                });
+            }
+            ShellMark::CommandFinished(exit_code) => self.command_finished(exit_code, cx),
+            // Integrations that don't send the end of a command still draw the next
+            // prompt after it, though without its exit code.
+            ShellMark::PromptStart => self.command_finished(None, cx),
+            ShellMark::CommandStart => {}
+        }
+    }
+
+    fn command_finished(&mut self, exit_code: Option<i32>, cx: &mut ModelContext<Self>) {
+        let Some(running_command) = self.running_command.take() else {
+            return;
+        };
+        let elapsed = running_command.started_at.elapsed();
+        if !self.should_notify_finished(elapsed) {
+            return;
+        }
+        let command = if running_command.command.is_empty() {
+            "The command".to_string()
+        } else {
+            format!("`{}`", truncate_and_trailoff(&running_command.command, 80))
+        };
+        let (title, body) = match exit_code {
+            Some(0) | None => (
+                "Command finished",
+                format!("{command} ran for {}", format_duration(elapsed)),
+            ),
+            Some(exit_code) => (
+                "Command failed",
+                format!(
+                    "{command} exited with code {exit_code} after {}",
+                    format_duration(elapsed)
+                ),
+            ),
+        };
+        cx.show_notification(title, &body);
+    }
+
+    /// Whether finishing something that took `elapsed` is worth a notification,
+    /// which it isn't when the user is watching the terminal.
+    fn should_notify_finished(&self, elapsed: Duration) -> bool {
+        self.long_command_notification
+            .map_or(false, |threshold| elapsed >= threshold)
+            && !(self.focused && self.window_active)
+    }
+
+    /// Tells the terminal whether the window showing it is the active one.
+    pub fn set_window_active(&mut self, active: bool) {
+        self.window_active = active;
+    }
+
+    /// The commands submitted in this terminal, oldest first and without repeats.
+    /// They are read off the screen, from where typing began to the end of the
+    /// line, so they are only as accurate as what the shell shows.
+    pub fn command_history(&self) -> &[String] {
+        &self.command_history
+    }
+
+    /// Sends `command` to the shell as if it was typed, and submits it with enter
+    /// when `submit` is set.
+    pub fn rerun_command(&mut self, command: &str, submit: bool) {
+        if self.read_only {
+            return;
+        }
+        self.start_command();
+        self.input(command.to_string());
+        if submit {
+            self.command_start_column = None;
+            self.command_submitted(command.to_string());
+            self.input("\r".to_string());
+        }
+    }
+
     pub fn try_keystroke(&mut self, keystroke: &Keystroke, alt_is_meta: bool) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(sequence) = key_binding_sequence(&self.key_bindings, keystroke) {
+            self.input_bytes(sequence.to_vec());
+            return true;
+        }
+
         let esc = to_esc_str(keystroke, &self.last_content.mode, alt_is_meta);
         if let Some(esc) = esc {
+            if esc == "\r" {
+                self.submit_command();
+            } else {
+                self.start_command();
+            }
             self.input(esc);
             true
         } else {
@@ -1095,18 +2463,24 @@ impl Terminal {
     }
 
     pub fn try_modifiers_change(&mut self, modifiers: &Modifiers) -> bool {
-        let changed = self.secondary_pressed != modifiers.secondary();
-        if !self.secondary_pressed && modifiers.secondary() {
+        let pressed = self.link_modifier.is_pressed(modifiers);
+        let changed = self.link_modifier_pressed != pressed;
+        if !self.link_modifier_pressed && pressed {
             self.refresh_hovered_word();
         }
-        self.secondary_pressed = modifiers.secondary();
+        self.link_modifier_pressed = pressed;
         changed
     }
 
     ///Paste text into the terminal
     pub fn paste(&mut self, text: &str) {
+        if self.read_only {
+            return;
+        }
+        self.start_command();
+        let text = sanitize_paste(text, self.paste_sanitization);
         let paste_text = if self.last_content.mode.contains(TermMode::BRACKETED_PASTE) {
-            format!("{}{}{}", "\x1b[200~", text.replace('\x1b', ""), "\x1b[201~")
+            format!("{}{}{}", "\x1b[200~", text, "\x1b[201~")
         } else {
             text.replace("\r\n", "\r").replace('\n', "\r")
         };
@@ -1117,18 +2491,113 @@ impl Terminal {
     pub fn sync(&mut self, cx: &mut ModelContext<Self>) {
         let term = self.term.clone();
         let mut terminal = term.lock_unfair();
+        let display_offset = terminal.grid().display_offset();
         //Note that the ordering of events matters for event processing
         while let Some(e) = self.events.pop_front() {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
+        self.pending_copy_mode_toggles = 0;
+
+        let was_alt_screen = self.last_content.mode.contains(TermMode::ALT_SCREEN);
+        let prompt_marks = self.prompt_marks.lock();
+        prompt_marks.skip_collapsed(&mut terminal, display_offset);
+        self.last_content =
+            Self::make_content(&mut terminal, &prompt_marks, &mut self.last_content);
+        drop(prompt_marks);
+        drop(terminal);
+        self.report_background_change(was_alt_screen, cx);
+    }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+    /// Sends a program that asked for the background color with OSC 11 the new one
+    /// when it changes, such as when the system switches between light and dark, so
+    /// it can switch its own colors. Only full screen programs are sent these, since
+    /// a shell at its prompt would take them for typed input.
+    fn report_background_change(&mut self, was_alt_screen: bool, cx: &AppContext) {
+        if was_alt_screen && !self.last_content.mode.contains(TermMode::ALT_SCREEN) {
+            self.background_report = None;
+        }
+        if self.background_report.is_none()
+            || !self.last_content.mode.contains(TermMode::ALT_SCREEN)
+        {
+            return;
+        }
+        let color = to_alac_rgb(self.theme(cx).colors().terminal_background);
+        let Some(report) = self.background_report.as_mut() else {
+            return;
+        };
+        if color != report.last_color {
+            report.last_color = color;
+            let response = (report.format)(color);
+            self.write_to_pty(response);
+        }
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &mut Term<ZedListener>,
+        prompt_marks: &PromptMarks,
+        last_content: &mut TerminalContent,
+    ) -> TerminalContent {
+        // Only the lines alacritty marked as damaged since the last sync are copied
+        // out of the grid again, the others still hold the same cells.
+        let damaged_lines = match term.damage() {
+            TermDamage::Full => None,
+            TermDamage::Partial(damage) => {
+                Some(damage.map(|bounds| bounds.line).collect::<Vec<_>>())
+            }
+        };
+        term.reset_damage();
+
+        let columns = term.columns();
+        let screen_lines = term.screen_lines();
+        let display_offset = term.grid().display_offset();
+        let version = last_content.version + 1;
+        let rows = prompt_marks.view_rows(term);
+        let mut cells = std::mem::take(&mut last_content.cells);
+        let mut line_versions = std::mem::take(&mut last_content.line_versions);
+        let damaged_lines = damaged_lines.filter(|_| {
+            last_content.display_offset == display_offset
+                && last_content.rows.is_empty()
+                && rows.is_empty()
+                && cells.len() == columns * screen_lines
+                && line_versions.len() == screen_lines
+        });
+
         let content = term.renderable_content();
-        TerminalContent {
-            cells: content
+        let colors = content.colors;
+        if !rows.is_empty() {
+            // Collapsed output moves lines onto other rows, so they are all copied.
+            cells.clear();
+            for row in &rows {
+                if row.start() == row.end() {
+                    for column in 0..columns {
+                        let point = AlacPoint::new(*row.start(), Column(column));
+                        cells.push(indexed_cell(&term.grid()[point], point, colors));
+                    }
+                } else {
+                    cells.extend(collapsed_output_cells(row, columns));
+                }
+            }
+            let last_line = rows.last().map_or(Line(0), |row| *row.end());
+            for row in rows.len()..screen_lines {
+                let line = last_line + (row - rows.len() + 1);
+                cells.extend((0..columns).map(|column| IndexedCell {
+                    point: AlacPoint::new(line, Column(column)),
+                    cell: Cell::default(),
+                }));
+            }
+            line_versions = vec![version; screen_lines];
+        } else if let Some(damaged_lines) = damaged_lines {
+            for line in damaged_lines {
+                line_versions[line] = version;
+                let grid_line = Line(line as i32 - display_offset as i32);
+                for column in 0..columns {
+                    let point = AlacPoint::new(grid_line, Column(column));
+                    cells[line * columns + column] =
+                        indexed_cell(&term.grid()[point], point, colors);
+                }
+            }
+        } else {
+            cells = content
                 .display_iter
                 //TODO: Add this once there's a way to retain empty lines
                 // .filter(|ic| {
@@ -1137,11 +2606,13 @@ impl Terminal {
                 //             && ic.c == ' '
                 //             && !ic.flags.contains(Flags::INVERSE))
                 // })
-                .map(|ic| IndexedCell {
-                    point: ic.point,
-                    cell: ic.cell.clone(),
-                })
-                .collect::<Vec<IndexedCell>>(),
+                .map(|ic| indexed_cell(ic.cell, ic.point, colors))
+                .collect::<Vec<IndexedCell>>();
+            line_versions = vec![version; screen_lines];
+        }
+
+        TerminalContent {
+            cells,
             mode: content.mode,
             display_offset: content.display_offset,
             selection_text: term.selection_to_string(),
@@ -1150,9 +2621,43 @@ impl Terminal {
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            version,
+            line_versions,
+            dynamic_colors: dynamic_colors(colors),
+            rows,
         }
     }
 
+    /// The theme to draw this terminal with, including any colors the running program
+    /// changed with OSC 4, 10, 11 or 12.
+    pub fn theme(&self, cx: &AppContext) -> Arc<Theme> {
+        let dynamic_colors = &self.last_content.dynamic_colors;
+        let overrides = TerminalSettings::get_global(cx)
+            .color_overrides(SystemAppearance::global(cx).0)
+            .map(TerminalColors::clone);
+
+        // Themes with overrides or dynamic colors are cloned to apply them, which is
+        // too slow to repeat every frame.
+        let mut cache = self.theme_cache.borrow_mut();
+        if let Some(cached) = cache.as_ref() {
+            if Arc::ptr_eq(&cached.base, cx.theme())
+                && cached.overrides == overrides
+                && &cached.dynamic_colors == dynamic_colors
+            {
+                return cached.theme.clone();
+            }
+        }
+
+        let theme = theme_with_dynamic_colors(dynamic_colors, cx);
+        *cache = Some(CachedTheme {
+            base: cx.theme().clone(),
+            overrides,
+            dynamic_colors: dynamic_colors.clone(),
+            theme: theme.clone(),
+        });
+        theme
+    }
+
     pub fn last_n_non_empty_lines(&self, n: usize) -> Vec<String> {
         let term = self.term.clone();
         let terminal = term.lock_unfair();
@@ -1178,19 +2683,82 @@ impl Terminal {
         lines
     }
 
-    pub fn focus_in(&self) {
+    /// Returns up to the last `max_lines` lines of output, scrollback included,
+    /// without trailing blank lines.
+    pub fn scrollback_text(&self, max_lines: usize) -> String {
+        let term = self.term.clone();
+        let terminal = term.lock_unfair();
+
+        let bottommost_line = terminal.bottommost_line();
+        let first_line = cmp::max(
+            terminal.topmost_line().0,
+            bottommost_line.0 + 1 - max_lines as i32,
+        );
+        let mut lines = Vec::new();
+        for line in first_line..=bottommost_line.0 {
+            let mut line_buffer = String::new();
+            for cell in &terminal.grid()[Line(line)] {
+                if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    line_buffer.push(cell.c);
+                }
+            }
+            lines.push(line_buffer.trim_end().to_string());
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    pub fn focus_in(&mut self, cx: &mut ModelContext<Self>) {
+        self.focused = true;
+        self.was_focused = true;
+        if self.has_bell || self.has_unread_output {
+            self.has_bell = false;
+            self.has_unread_output = false;
+            cx.emit(Event::ActivityChanged(cx.entity_id()));
+        }
         if self.last_content.mode.contains(TermMode::FOCUS_IN_OUT) {
             self.write_to_pty("\x1b[I".to_string());
         }
     }
 
-    pub fn focus_out(&mut self) {
+    pub fn focus_out(&mut self, cx: &mut ModelContext<Self>) {
+        self.focused = false;
         self.last_mouse_position = None;
+        self.set_hovered_cell(None, cx);
         if self.last_content.mode.contains(TermMode::FOCUS_IN_OUT) {
             self.write_to_pty("\x1b[O".to_string());
         }
     }
 
+    /// Marks the terminal as a shell on a remote host, shown as `destination`.
+    pub fn set_remote_destination(&mut self, destination: String) {
+        self.remote_destination = Some(destination);
+    }
+
+    /// The `user@host` or `host` of the remote shell this terminal is connected to.
+    pub fn remote_destination(&self) -> Option<&str> {
+        self.remote_destination.as_deref()
+    }
+
+    /// Whether the bell rang since the terminal was last focused.
+    pub fn has_bell(&self) -> bool {
+        self.has_bell
+    }
+
+    /// Whether the program printed output since the terminal was last focused.
+    pub fn has_unread_output(&self) -> bool {
+        self.has_unread_output
+    }
+
+    pub fn clear_bell(&mut self, cx: &mut ModelContext<Self>) {
+        if self.has_bell {
+            self.has_bell = false;
+            cx.emit(Event::ActivityChanged(cx.entity_id()));
+        }
+    }
+
     pub fn mouse_changed(&mut self, point: AlacPoint, side: AlacDirection) -> bool {
         match self.last_mouse {
             Some((old_point, old_side)) => {
@@ -1212,22 +2780,49 @@ impl Terminal {
         self.last_content.mode.intersects(TermMode::MOUSE_MODE) && !shift
     }
 
-    pub fn mouse_move(&mut self, e: &MouseMoveEvent, origin: Point<Pixels>) {
+    /// The point and contents of the visible cell under `position`, relative to the
+    /// top left of the grid, as of the last sync.
+    pub fn grid_cell_at(&self, position: Point<Pixels>) -> Option<(AlacPoint, Cell)> {
+        let cell = self.last_content.cell_at(position)?;
+        Some((cell.point, cell.cell.clone()))
+    }
+
+    /// The point of the cell the mouse is over, see [`Event::HoveredCellChanged`].
+    pub fn hovered_cell(&self) -> Option<AlacPoint> {
+        self.hovered_cell
+    }
+
+    fn set_hovered_cell(&mut self, point: Option<AlacPoint>, cx: &mut ModelContext<Self>) {
+        if self.hovered_cell != point {
+            self.hovered_cell = point;
+            cx.emit(Event::HoveredCellChanged(point));
+        }
+    }
+
+    /// Forgets the hovered cell once the mouse left the terminal.
+    pub fn mouse_exit(&mut self, cx: &mut ModelContext<Self>) {
+        self.set_hovered_cell(None, cx);
+    }
+
+    pub fn mouse_move(
+        &mut self,
+        e: &MouseMoveEvent,
+        origin: Point<Pixels>,
+        cx: &mut ModelContext<Self>,
+    ) {
         let position = e.position - origin;
         self.last_mouse_position = Some(position);
+        let hovered_cell = self.last_content.cell_at(position).map(|cell| cell.point);
+        self.set_hovered_cell(hovered_cell, cx);
         if self.mouse_mode(e.modifiers.shift) {
-            let (point, side) = grid_point_and_side(
-                position,
-                self.last_content.size,
-                self.last_content.display_offset,
-            );
+            let (point, side) = self.last_content.grid_point_and_side(position);
 
             if self.mouse_changed(point, side) {
                 if let Some(bytes) = mouse_moved_report(point, e, self.last_content.mode) {
-                    self.pty_tx.notify(bytes);
+                    self.write_user_input_to_pty(bytes);
                 }
             }
-        } else if self.secondary_pressed {
+        } else if self.link_modifier_pressed {
             self.word_from_position(Some(position));
         }
     }
@@ -1255,7 +2850,7 @@ impl Terminal {
             // Alacritty has the same ordering, of first updating the selection
             // then scrolling 15ms later
             self.events
-                .push_back(InternalEvent::UpdateSelection(position));
+                .push_back(InternalEvent::UpdateSelection(position, None));
 
             // Doesn't make sense to scroll the alt screen
             if !self.last_content.mode.contains(TermMode::ALT_SCREEN) {
@@ -1293,27 +2888,26 @@ impl Terminal {
         _cx: &mut ModelContext<Self>,
     ) {
         let position = e.position - origin;
-        let point = grid_point(
-            position,
-            self.last_content.size,
-            self.last_content.display_offset,
-        );
+        let point = self.last_content.grid_point(position);
 
         if self.mouse_mode(e.modifiers.shift) {
             if let Some(bytes) =
                 mouse_button_report(point, e.button, e.modifiers, true, self.last_content.mode)
             {
-                self.pty_tx.notify(bytes);
+                self.write_user_input_to_pty(bytes);
             }
         } else {
             match e.button {
                 MouseButton::Left => {
                     let position = e.position - origin;
-                    let (point, side) = grid_point_and_side(
-                        position,
-                        self.last_content.size,
-                        self.last_content.display_offset,
-                    );
+                    let (point, side) = self.last_content.grid_point_and_side(position);
+
+                    // Clicking a collapsed command output expands it.
+                    if e.click_count == 1 && self.last_content.is_collapsed(point.line) {
+                        self.events
+                            .push_back(InternalEvent::ExpandCommandOutput(point.line));
+                        return;
+                    }
 
                     let selection_type = match e.click_count {
                         0 => return, //This is a release
@@ -1323,6 +2917,18 @@ impl Terminal {
                         _ => None,
                     };
 
+                    // Shift-click extends the selection to the clicked cell, by words or
+                    // lines if it was started with a double or triple click, or when
+                    // shift-double or shift-triple clicking.
+                    if e.modifiers.shift && self.last_content.selection.is_some() {
+                        let selection_type =
+                            (e.click_count > 1).then_some(selection_type).flatten();
+                        self.selection_phase = SelectionPhase::Selecting;
+                        self.events
+                            .push_back(InternalEvent::UpdateSelection(position, selection_type));
+                        return;
+                    }
+
                     let selection = selection_type
                         .map(|selection_type| Selection::new(selection_type, point, side));
 
@@ -1333,9 +2939,8 @@ impl Terminal {
                 }
                 #[cfg(target_os = "linux")]
                 MouseButton::Middle => {
-                    if let Some(item) = _cx.read_from_primary() {
-                        let text = item.text().unwrap_or_default().to_string();
-                        self.input(text);
+                    if let Some(text) = _cx.read_from_primary().and_then(|item| item.text()) {
+                        _cx.emit(Event::PasteRequested(text));
                     }
                 }
                 _ => {}
@@ -1353,16 +2958,12 @@ impl Terminal {
 
         let position = e.position - origin;
         if self.mouse_mode(e.modifiers.shift) {
-            let point = grid_point(
-                position,
-                self.last_content.size,
-                self.last_content.display_offset,
-            );
+            let point = self.last_content.grid_point(position);
 
             if let Some(bytes) =
                 mouse_button_report(point, e.button, e.modifiers, false, self.last_content.mode)
             {
-                self.pty_tx.notify(bytes);
+                self.write_user_input_to_pty(bytes);
             }
         } else {
             if e.button == MouseButton::Left && setting.copy_on_select {
@@ -1374,7 +2975,7 @@ impl Terminal {
                 let mouse_cell_index = content_index_for_mouse(position, &self.last_content.size);
                 if let Some(link) = self.last_content.cells[mouse_cell_index].hyperlink() {
                     cx.open_url(link.uri());
-                } else if self.secondary_pressed {
+                } else if self.link_modifier_pressed {
                     self.events
                         .push_back(InternalEvent::FindHyperlink(position, true));
                 }
@@ -1391,16 +2992,12 @@ impl Terminal {
 
         if let Some(scroll_lines) = self.determine_scroll_lines(e, mouse_mode) {
             if mouse_mode {
-                let point = grid_point(
-                    e.position - origin,
-                    self.last_content.size,
-                    self.last_content.display_offset,
-                );
+                let point = self.last_content.grid_point(e.position - origin);
 
                 if let Some(scrolls) = scroll_report(point, scroll_lines, e, self.last_content.mode)
                 {
                     for scroll in scrolls {
-                        self.pty_tx.notify(scroll);
+                        self.write_user_input_to_pty(scroll);
                     }
                 };
             } else if self
@@ -1409,7 +3006,7 @@ impl Terminal {
                 .contains(TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL)
                 && !e.shift
             {
-                self.pty_tx.notify(alt_scroll(scroll_lines))
+                self.write_user_input_to_pty(alt_scroll(scroll_lines))
             } else {
                 if scroll_lines != 0 {
                     let scroll = AlacScroll::Delta(scroll_lines);
@@ -1425,7 +3022,13 @@ impl Terminal {
     }
 
     fn determine_scroll_lines(&mut self, e: &ScrollWheelEvent, mouse_mode: bool) -> Option<i32> {
-        let scroll_multiplier = if mouse_mode { 1. } else { SCROLL_MULTIPLIER };
+        let scroll_multiplier = if mouse_mode {
+            1.
+        } else if e.modifiers.alt {
+            SCROLL_MULTIPLIER * self.scroll_multiplier * self.fast_scroll_multiplier
+        } else {
+            SCROLL_MULTIPLIER * self.scroll_multiplier
+        };
         let line_height = self.last_content.size.line_height;
         match e.touch_phase {
             /* Reset scroll state on started */
@@ -1466,6 +3069,7 @@ impl Terminal {
 
     pub fn working_directory(&self) -> Option<PathBuf> {
         self.pty_info
+            .as_ref()?
             .current
             .as_ref()
             .map(|process| process.cwd.clone())
@@ -1473,6 +3077,13 @@ impl Terminal {
 
     pub fn title(&self, truncate: bool) -> String {
         const MAX_CHARS: usize = 25;
+        if let Some(replay) = &self.replay {
+            return if truncate {
+                truncate_and_trailoff(&replay.title, MAX_CHARS)
+            } else {
+                replay.title.clone()
+            };
+        }
         match &self.task {
             Some(task_state) => {
                 if truncate {
@@ -1481,12 +3092,27 @@ impl Terminal {
                     task_state.full_label.clone()
                 }
             }
+            // Locally, the foreground process of a remote terminal is `ssh`, which says
+            // less than the title the remote shell may have set.
+            None if self.remote_destination.is_some() => {
+                let destination = self.remote_destination.as_deref().unwrap_or_default();
+                let title = if self.breadcrumb_text.is_empty() {
+                    destination.to_string()
+                } else {
+                    format!("{destination} — {}", self.breadcrumb_text)
+                };
+                if truncate {
+                    truncate_and_trailoff(&title, MAX_CHARS * 2)
+                } else {
+                    title
+                }
+            }
             None => self
                 .pty_info
-                .current
                 .as_ref()
+                .and_then(|pty_info| pty_info.current.as_ref())
                 .map(|fpi| {
-                    let process_file = fpi
+                    let cwd = fpi
                         .cwd
                         .file_name()
                         .map(|name| name.to_string_lossy().to_string())
@@ -1502,22 +3128,61 @@ impl Terminal {
                             "".to_string()
                         }
                     );
-                    let (process_file, process_name) = if truncate {
+                    let (cwd, process_name, osc_title) = if truncate {
                         (
-                            truncate_and_trailoff(&process_file, MAX_CHARS),
+                            truncate_and_trailoff(&cwd, MAX_CHARS),
                             truncate_and_trailoff(&process_name, MAX_CHARS),
+                            truncate_and_trailoff(&self.breadcrumb_text, MAX_CHARS),
                         )
                     } else {
-                        (process_file, process_name)
+                        (cwd, process_name, self.breadcrumb_text.clone())
                     };
-                    format!("{process_file} — {process_name}")
+                    format_title(&self.title_template, &process_name, &cwd, &osc_title)
                 })
                 .unwrap_or_else(|| "Terminal".to_string()),
         }
     }
 
+    /// The link or path-like word under the mouse, whether or not the modifier that
+    /// highlights it is held.
+    pub fn navigation_target_under_mouse(&mut self) -> Option<MaybeNavigationTarget> {
+        let position = self.last_mouse_position?;
+        let term = self.term.clone();
+        let term = term.lock();
+        let point = self
+            .last_content
+            .grid_point(position)
+            .grid_clamp(&*term, Boundary::Grid);
+        let (word, is_url, _) = navigation_word_at(
+            &*term,
+            point,
+            &mut self.url_regex,
+            &mut self.link_rules,
+            &mut self.word_regex,
+        )?;
+        Some(self.navigation_target(word, is_url))
+    }
+
+    pub fn open_navigation_target(
+        &mut self,
+        target: MaybeNavigationTarget,
+        cx: &mut ModelContext<Self>,
+    ) {
+        cx.emit(Event::Open(target));
+    }
+
     pub fn can_navigate_to_selected_word(&self) -> bool {
-        self.secondary_pressed && self.hovered_word
+        self.link_modifier_pressed && self.hovered_word
+    }
+
+    /// Whether this is a plain shell terminal waiting at its prompt, with no
+    /// task or foreground program running in it.
+    pub fn is_idle(&mut self) -> bool {
+        self.task.is_none()
+            && self
+                .pty_info
+                .as_mut()
+                .map_or(false, |pty_info| pty_info.is_shell_in_foreground())
     }
 
     pub fn task(&self) -> Option<&TaskState> {
@@ -1545,8 +3210,11 @@ impl Terminal {
         let task = match &mut self.task {
             Some(task) => task,
             None => {
-                if error_code.is_none() {
-                    cx.emit(Event::CloseTerminal);
+                match error_code {
+                    // The exit code arrives first, the PTY shutting down comes after the remaining output.
+                    Some(error_code) => self.child_exit_code = Some(error_code),
+                    None if TerminalSettings::get_global(cx).hold => self.hold_after_exit(cx),
+                    None => cx.emit(Event::CloseTerminal),
                 }
                 return;
             }
@@ -1563,6 +3231,8 @@ impl Terminal {
             }
         };
 
+        let exit = error_code.map(|error_code| (error_code, task.label.clone()));
+
         let (finished_successfully, task_line, command_line) = task_summary(task, error_code);
         // SAFETY: the invocation happens on non `TaskStatus::Running` tasks, once,
         // after either `AlacTermEvent::Exit` or `AlacTermEvent::ChildExit` events that are spawned
@@ -1580,7 +3250,117 @@ impl Terminal {
                 }
             }
         }
+
+        if let Some((error_code, label)) = exit {
+            let elapsed = self.started_at.elapsed();
+            if self.should_notify_finished(elapsed) {
+                let title = if error_code == 0 {
+                    "Task finished"
+                } else {
+                    "Task failed"
+                };
+                cx.show_notification(
+                    title,
+                    &format!(
+                        "`{label}` exited with code {error_code} after {}",
+                        format_duration(elapsed)
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl Terminal {
+    /// Whether the shell has exited and the terminal is only kept open to show
+    /// its final output, per the `hold` setting.
+    pub fn has_exited(&self) -> bool {
+        self.has_exited
+    }
+
+    fn hold_after_exit(&mut self, cx: &mut ModelContext<Self>) {
+        if self.has_exited {
+            return;
+        }
+        self.has_exited = true;
+
+        let status_line = match self.child_exit_code {
+            Some(code) => format!("[Process exited with code {code}]"),
+            None => "[Process exited]".to_string(),
+        };
+        // SAFETY: the PTY has shut down by the time `AlacTermEvent::Exit` is received.
+        unsafe {
+            append_text_to_term(
+                &mut self.term.lock(),
+                &[&status_line, "Press any key to close the terminal"],
+            )
+        };
+        cx.emit(Event::TitleChanged(self.title(false)));
+        cx.emit(Event::Wakeup);
+    }
+}
+
+/// Removes the control characters `policy` doesn't allow from pasted text, and
+/// always the end of bracketed paste, in its 7-bit and 8-bit forms. Removing one
+/// can join the text around it into another, so this repeats until none are left.
+fn sanitize_paste(text: &str, policy: PasteSanitization) -> String {
+    let mut text: String = match policy {
+        PasteSanitization::Controls => text
+            .chars()
+            .filter(|c| matches!(c, '\t' | '\n' | '\r') || !c.is_control())
+            .collect(),
+        PasteSanitization::Escape => text.replace('\x1b', ""),
+        PasteSanitization::Raw => text.to_string(),
+    };
+    while text.contains("\x1b[201~") || text.contains("\u{9b}201~") {
+        text = text.replace("\x1b[201~", "").replace("\u{9b}201~", "");
+    }
+    text
+}
+
+fn trim_trailing_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A duration in the largest two units that fit it, like `2m 5s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds < 60 {
+        format!("{seconds}s")
+    } else if seconds < 60 * 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {}m", seconds / (60 * 60), seconds / 60 % 60)
+    }
+}
+
+/// Fills in the `{process}`, `{cwd}` and `{title}` placeholders of the `title` setting.
+/// Braces that don't start a placeholder are kept as they are.
+fn format_title(template: &str, process: &str, cwd: &str, title: &str) -> String {
+    let mut formatted = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        formatted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match [("{process}", process), ("{cwd}", cwd), ("{title}", title)]
+            .into_iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                formatted.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                formatted.push('{');
+                rest = &rest[1..];
+            }
+        }
     }
+    formatted.push_str(rest);
+    formatted
 }
 
 const TASK_DELIMITER: &str = "⏵ ";
@@ -1641,12 +3421,93 @@ unsafe fn append_text_to_term(term: &mut Term<ZedListener>, text_lines: &[&str])
 
 impl Drop for Terminal {
     fn drop(&mut self) {
-        self.pty_tx.0.send(Msg::Shutdown).ok();
+        if let Some(pty_tx) = &self.pty_tx {
+            pty_tx.0.send(Msg::Shutdown).ok();
+        }
     }
 }
 
 impl EventEmitter<Event> for Terminal {}
 
+/// Finds the hyperlink, URL, link rule match or path-like word under `point`,
+/// returning its text, whether it is a URL, and where it is in the grid.
+fn navigation_word_at<T>(
+    term: &Term<T>,
+    point: AlacPoint,
+    url_regex: &mut RegexSearch,
+    link_rules: &mut [LinkRule],
+    word_regex: &mut RegexSearch,
+) -> Option<(String, bool, Match)> {
+    let link = term.grid().index(point).hyperlink();
+    if link.is_some() {
+        let mut min_index = point;
+        loop {
+            let new_min_index = min_index.sub(term, Boundary::Cursor, 1);
+            if new_min_index == min_index {
+                break;
+            } else if term.grid().index(new_min_index).hyperlink() != link {
+                break;
+            } else {
+                min_index = new_min_index
+            }
+        }
+
+        let mut max_index = point;
+        loop {
+            let new_max_index = max_index.add(term, Boundary::Cursor, 1);
+            if new_max_index == max_index {
+                break;
+            } else if term.grid().index(new_max_index).hyperlink() != link {
+                break;
+            } else {
+                max_index = new_max_index
+            }
+        }
+
+        let url = link.unwrap().uri().to_owned();
+        let url_match = min_index..=max_index;
+
+        Some((url, true, url_match))
+    } else if let Some(url_match) = regex_match_at(term, point, url_regex) {
+        let url = term.bounds_to_string(*url_match.start(), *url_match.end());
+        Some((url, true, url_match))
+    } else if let Some((url, url_match)) = link_rules.iter_mut().find_map(|rule| {
+        let rule_match = regex_match_at(term, point, &mut rule.search)?;
+        let text = term.bounds_to_string(*rule_match.start(), *rule_match.end());
+        let captures = rule.regex.captures(&text)?;
+        Some((expand_link_url(&rule.url, &captures), rule_match))
+    }) {
+        Some((url, true, url_match))
+    } else if let Some(word_match) = regex_match_at(term, point, word_regex) {
+        let file_path = term.bounds_to_string(*word_match.start(), *word_match.end());
+
+        let (sanitized_match, sanitized_word) =
+            if file_path.starts_with('[') && file_path.ends_with(']') {
+                (
+                    Match::new(
+                        word_match.start().add(term, Boundary::Cursor, 1),
+                        word_match.end().sub(term, Boundary::Cursor, 1),
+                    ),
+                    file_path[1..file_path.len() - 1].to_owned(),
+                )
+            } else {
+                (word_match, file_path)
+            };
+
+        Some((sanitized_word, false, sanitized_match))
+    } else {
+        None
+    }
+}
+
+/// Whether a path-like word could be an abbreviated or full commit SHA. Words without
+/// digits, such as `defaced`, are far more likely to be plain words.
+fn is_commit_sha(word: &str) -> bool {
+    (7..=40).contains(&word.len())
+        && word.chars().all(|c| c.is_ascii_hexdigit())
+        && word.chars().any(|c| c.is_ascii_digit())
+}
+
 /// Based on alacritty/src/display/hint.rs > regex_match_at
 /// Retrieve the match, if the specified point is inside the content matching the regex.
 fn regex_match_at<T>(term: &Term<T>, point: AlacPoint, regex: &mut RegexSearch) -> Option<Match> {
@@ -1694,6 +3555,190 @@ fn content_index_for_mouse(pos: Point<Pixels>, size: &TerminalSize) -> usize {
     clamped_row * size.columns() + clamped_col
 }
 
+thread_local! {
+    static OVERRIDDEN_THEME: RefCell<Option<OverriddenTheme>> = const { RefCell::new(None) };
+}
+
+/// The last theme [`terminal_theme`] applied overrides to, and the result.
+struct OverriddenTheme {
+    base: Arc<Theme>,
+    overrides: [TerminalColors; 2],
+    theme: Arc<Theme>,
+}
+
+/// The theme terminals are drawn with: the active theme, with the `terminal.colors`
+/// setting and the one for the system appearance layered on top.
+pub fn terminal_theme(cx: &AppContext) -> Arc<Theme> {
+    let base = cx.theme();
+    let overrides =
+        TerminalSettings::get_global(cx).color_overrides(SystemAppearance::global(cx).0);
+    if overrides.iter().all(|overrides| overrides.is_empty()) {
+        return base.clone();
+    }
+
+    // Applying the overrides clones the whole theme, which is too slow to repeat
+    // every frame.
+    OVERRIDDEN_THEME.with_borrow_mut(|cached| {
+        if let Some(cached) = cached.as_ref() {
+            if Arc::ptr_eq(&cached.base, base) && cached.overrides.iter().eq(overrides) {
+                return cached.theme.clone();
+            }
+        }
+
+        let mut theme = Theme::clone(base);
+        for overrides in overrides {
+            overrides.apply_to(&mut theme);
+        }
+        let theme = Arc::new(theme);
+        *cached = Some(OverriddenTheme {
+            base: base.clone(),
+            overrides: overrides.map(TerminalColors::clone),
+            theme: theme.clone(),
+        });
+        theme
+    })
+}
+
+/// [`terminal_theme`], with the foreground, background, cursor and first 16 palette
+/// colors a program set through OSC 4, 10, 11 and 12 layered on top. Programs reset
+/// them with OSC 104, 110, 111 and 112, which clears the entries in `colors`.
+/// `dynamic_colors` holds them in the order of [`dynamic_color_indices`].
+fn theme_with_dynamic_colors(dynamic_colors: &[Option<Rgb>], cx: &AppContext) -> Arc<Theme> {
+    let theme = terminal_theme(cx);
+    if dynamic_colors.iter().all(Option::is_none) {
+        return theme;
+    }
+
+    let mut theme = Theme::clone(&theme);
+    let theme_colors = &mut theme.styles.colors;
+    let palette = [
+        &mut theme_colors.terminal_ansi_black,
+        &mut theme_colors.terminal_ansi_red,
+        &mut theme_colors.terminal_ansi_green,
+        &mut theme_colors.terminal_ansi_yellow,
+        &mut theme_colors.terminal_ansi_blue,
+        &mut theme_colors.terminal_ansi_magenta,
+        &mut theme_colors.terminal_ansi_cyan,
+        &mut theme_colors.terminal_ansi_white,
+        &mut theme_colors.terminal_ansi_bright_black,
+        &mut theme_colors.terminal_ansi_bright_red,
+        &mut theme_colors.terminal_ansi_bright_green,
+        &mut theme_colors.terminal_ansi_bright_yellow,
+        &mut theme_colors.terminal_ansi_bright_blue,
+        &mut theme_colors.terminal_ansi_bright_magenta,
+        &mut theme_colors.terminal_ansi_bright_cyan,
+        &mut theme_colors.terminal_ansi_bright_white,
+        &mut theme_colors.terminal_foreground,
+        &mut theme_colors.terminal_background,
+    ];
+    let (palette_colors, cursor_color) = dynamic_colors.split_at(palette.len());
+    for (color, rgb) in palette.into_iter().zip(palette_colors) {
+        if let Some(rgb) = rgb {
+            *color = rgba_color(rgb.r, rgb.g, rgb.b);
+        }
+    }
+    if let Some(Some(rgb)) = cursor_color.first() {
+        if let Some(local_player) = theme.styles.player.0.first_mut() {
+            local_player.cursor = rgba_color(rgb.r, rgb.g, rgb.b);
+        }
+    }
+    Arc::new(theme)
+}
+
+/// The row a collapsed command output is shown as, which says how many lines it
+/// hides.
+fn collapsed_output_cells(lines: &RangeInclusive<Line>, columns: usize) -> Vec<IndexedCell> {
+    let hidden_lines = lines.end().0 - lines.start().0 + 1;
+    let text = format!("⋯ {hidden_lines} lines collapsed");
+    text.chars()
+        .chain(std::iter::repeat(' '))
+        .take(columns)
+        .enumerate()
+        .map(|(column, c)| IndexedCell {
+            point: AlacPoint::new(*lines.start(), Column(column)),
+            cell: Cell {
+                c,
+                flags: Flags::DIM | Flags::ITALIC,
+                ..Cell::default()
+            },
+        })
+        .collect()
+}
+
+fn indexed_cell(cell: &Cell, point: AlacPoint, colors: &Colors) -> IndexedCell {
+    let mut cell = cell.clone();
+    cell.fg = resolve_palette_color(cell.fg, colors);
+    cell.bg = resolve_palette_color(cell.bg, colors);
+    IndexedCell { point, cell }
+}
+
+/// The entries of alacritty's [`Colors`] that [`theme_with_dynamic_colors`] applies.
+fn dynamic_color_indices() -> impl Iterator<Item = usize> {
+    (0..16).chain([
+        NamedColor::Foreground as usize,
+        NamedColor::Background as usize,
+        NamedColor::Cursor as usize,
+    ])
+}
+
+fn dynamic_colors(colors: &Colors) -> Vec<Option<Rgb>> {
+    dynamic_color_indices().map(|index| colors[index]).collect()
+}
+
+/// Replaces 256-color palette entries that a program redefined with OSC 4 by the
+/// color it set. Lower entries are handled by [`theme_with_dynamic_colors`].
+fn resolve_palette_color(color: AnsiColor, colors: &Colors) -> AnsiColor {
+    match color {
+        AnsiColor::Indexed(index) if index >= 16 => {
+            colors[index as usize].map(AnsiColor::Spec).unwrap_or(color)
+        }
+        color => color,
+    }
+}
+
+/// Converts a 2, 8, or 24 bit color ANSI color to the GPUI equivalent.
+pub fn convert_color(fg: &AnsiColor, theme: &Theme) -> Hsla {
+    let colors = theme.colors();
+    match fg {
+        // Named and theme defined colors
+        AnsiColor::Named(n) => match n {
+            NamedColor::Black => colors.terminal_ansi_black,
+            NamedColor::Red => colors.terminal_ansi_red,
+            NamedColor::Green => colors.terminal_ansi_green,
+            NamedColor::Yellow => colors.terminal_ansi_yellow,
+            NamedColor::Blue => colors.terminal_ansi_blue,
+            NamedColor::Magenta => colors.terminal_ansi_magenta,
+            NamedColor::Cyan => colors.terminal_ansi_cyan,
+            NamedColor::White => colors.terminal_ansi_white,
+            NamedColor::BrightBlack => colors.terminal_ansi_bright_black,
+            NamedColor::BrightRed => colors.terminal_ansi_bright_red,
+            NamedColor::BrightGreen => colors.terminal_ansi_bright_green,
+            NamedColor::BrightYellow => colors.terminal_ansi_bright_yellow,
+            NamedColor::BrightBlue => colors.terminal_ansi_bright_blue,
+            NamedColor::BrightMagenta => colors.terminal_ansi_bright_magenta,
+            NamedColor::BrightCyan => colors.terminal_ansi_bright_cyan,
+            NamedColor::BrightWhite => colors.terminal_ansi_bright_white,
+            NamedColor::Foreground => colors.terminal_foreground,
+            NamedColor::Background => colors.terminal_background,
+            NamedColor::Cursor => theme.players().local().cursor,
+            NamedColor::DimBlack => colors.terminal_ansi_dim_black,
+            NamedColor::DimRed => colors.terminal_ansi_dim_red,
+            NamedColor::DimGreen => colors.terminal_ansi_dim_green,
+            NamedColor::DimYellow => colors.terminal_ansi_dim_yellow,
+            NamedColor::DimBlue => colors.terminal_ansi_dim_blue,
+            NamedColor::DimMagenta => colors.terminal_ansi_dim_magenta,
+            NamedColor::DimCyan => colors.terminal_ansi_dim_cyan,
+            NamedColor::DimWhite => colors.terminal_ansi_dim_white,
+            NamedColor::BrightForeground => colors.terminal_bright_foreground,
+            NamedColor::DimForeground => colors.terminal_dim_foreground,
+        },
+        // 'True' colors
+        AnsiColor::Spec(rgb) => rgba_color(rgb.r, rgb.g, rgb.b),
+        // 8 bit, indexed colors
+        AnsiColor::Indexed(i) => get_color_at_index(*i as usize, theme),
+    }
+}
+
 /// Converts an 8 bit ANSI color to its GPUI equivalent.
 /// Accepts `usize` for compatibility with the `alacritty::Colors` interface,
 /// Other than that use case, should only be called with values in the [0,255] range
@@ -1718,18 +3763,8 @@ pub fn get_color_at_index(index: usize, theme: &Theme) -> Hsla {
         13 => colors.terminal_ansi_bright_magenta,
         14 => colors.terminal_ansi_bright_cyan,
         15 => colors.terminal_ansi_bright_white,
-        // 16-231 are mapped to their RGB colors on a 0-5 range per channel
-        16..=231 => {
-            let (r, g, b) = rgb_for_index(index as u8); // Split the index into its ANSI-RGB components
-            let step = (u8::MAX as f32 / 5.).floor() as u8; // Split the RGB range into 5 chunks, with floor so no overflow
-            rgba_color(r * step, g * step, b * step) // Map the ANSI-RGB components to an RGB color
-        }
-        // 232-255 are a 24 step grayscale from black to white
-        232..=255 => {
-            let i = index as u8 - 232; // Align index to 0..24
-            let step = (u8::MAX as f32 / 24.).floor() as u8; // Split the RGB grayscale values into 24 chunks
-            rgba_color(i * step, i * step, i * step) // Map the ANSI-grayscale components to the RGB-grayscale
-        }
+        // 16-255 don't depend on the theme, and are looked up in a table
+        16..=255 => EXTENDED_PALETTE[index - 16],
         // For compatibility with the alacritty::Colors interface
         256 => colors.text,
         257 => colors.background,
@@ -1749,6 +3784,28 @@ pub fn get_color_at_index(index: usize, theme: &Theme) -> Hsla {
     }
 }
 
+/// The colors of the 256 color palette after the first 16, which are the same in
+/// every theme.
+static EXTENDED_PALETTE: LazyLock<[Hsla; 240]> = LazyLock::new(|| {
+    std::array::from_fn(|i| {
+        let index = i as u8 + 16;
+        match index {
+            // 16-231 are mapped to their RGB colors on a 0-5 range per channel
+            16..=231 => {
+                let (r, g, b) = rgb_for_index(index); // Split the index into its ANSI-RGB components
+                let step = (u8::MAX as f32 / 5.).floor() as u8; // Split the RGB range into 5 chunks, with floor so no overflow
+                rgba_color(r * step, g * step, b * step) // Map the ANSI-RGB components to an RGB color
+            }
+            // 232-255 are a 24 step grayscale from black to white
+            _ => {
+                let i = index - 232; // Align index to 0..24
+                let step = (u8::MAX as f32 / 24.).floor() as u8; // Split the RGB grayscale values into 24 chunks
+                rgba_color(i * step, i * step, i * step) // Map the ANSI-grayscale components to the RGB-grayscale
+            }
+        }
+    })
+});
+
 /// Generates the RGB channels in [0, 5] for a given index into the 6x6x6 ANSI color cube.
 /// See: [8 bit ANSI color](https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit).
 ///
@@ -1782,14 +3839,174 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 mod tests {
     use alacritty_terminal::{
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, color::Colors},
+        vte::ansi::{Color as AnsiColor, NamedColor, Rgb},
     };
     use gpui::{point, size, Pixels};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, expand_link_url, format_duration, format_title, is_commit_sha,
+        key_binding_sequence, parse_key_bindings, parse_key_sequence, resolve_palette_color,
+        rgb_for_index, rgba_color, sanitize_paste, semantic_escape_chars,
+        terminal_settings::PasteSanitization, trim_trailing_whitespace, IndexedCell,
+        TerminalContent, TerminalSize, EXTENDED_PALETTE,
     };
+    use std::time::Duration;
+
+    #[test]
+    fn test_semantic_escape_chars() {
+        let escape_chars = semantic_escape_chars("!#$%&*+-./;=?@\\^_~");
+        for c in ",│`|:\"' ()[]{}<>\t".chars() {
+            assert!(escape_chars.contains(c), "{c:?} should separate words");
+        }
+        for c in "/.-_~".chars() {
+            assert!(!escape_chars.contains(c), "{c:?} should belong to words");
+        }
+
+        let escape_chars = semantic_escape_chars("-_");
+        assert!(escape_chars.contains('/'));
+        assert!(escape_chars.contains('.'));
+        assert!(!escape_chars.contains('-'));
+        assert!(!escape_chars.chars().any(|c| c.is_alphanumeric()));
+    }
+
+    #[test]
+    fn test_expand_link_url() {
+        let regex = regex::Regex::new(r"([A-Z]+)-(\d+)").unwrap();
+        let captures = regex.captures("see ZED-123").unwrap();
+        assert_eq!(
+            expand_link_url("https://example.com/{1}/issues/{2}?q={0}", &captures),
+            "https://example.com/ZED/issues/123?q=ZED-123"
+        );
+        assert_eq!(
+            expand_link_url("https://example.com/{3}", &captures),
+            "https://example.com/{3}"
+        );
+    }
+
+    #[test]
+    fn test_format_title() {
+        assert_eq!(
+            format_title("{cwd} — {process}", "cargo test", "zed", ""),
+            "zed — cargo test"
+        );
+        assert_eq!(
+            format_title("{title} ({process})", "vim", "src", "main.rs"),
+            "main.rs (vim)"
+        );
+        assert_eq!(
+            format_title("{{cwd}} {unknown", "{cwd}", "zed", ""),
+            "{zed} {unknown"
+        );
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        assert_eq!(
+            trim_trailing_whitespace("a  \n\tb\t\r\n\nc "),
+            "a\n\tb\n\nc"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(42_900)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_duration(Duration::from_secs(2 * 3600 + 61)), "2h 1m");
+    }
+
+    #[test]
+    fn test_parse_key_sequence() {
+        assert_eq!(parse_key_sequence("\u{1b}[13;2u"), b"\x1b[13;2u");
+        assert_eq!(parse_key_sequence("\\x17"), b"\x17");
+        assert_eq!(parse_key_sequence("a\\x1B\\xffb"), b"a\x1b\xffb");
+        // Escapes without two hex digits are sent as written.
+        assert_eq!(parse_key_sequence("\\xg1\\x+1\\x1"), b"\\xg1\\x+1\\x1");
+    }
+
+    #[test]
+    fn test_key_bindings() {
+        let bindings = parse_key_bindings(&collections::HashMap::from_iter([
+            ("shift-enter".to_string(), "\u{1b}[13;2u".to_string()),
+            ("ctrl-backspace".to_string(), "\\x17".to_string()),
+            ("not-a-key-".to_string(), "ignored".to_string()),
+        ]));
+        assert_eq!(bindings.len(), 2);
+
+        let keystroke = |source| gpui::Keystroke::parse(source).unwrap();
+        assert_eq!(
+            key_binding_sequence(&bindings, &keystroke("shift-enter")),
+            Some(&b"\x1b[13;2u"[..])
+        );
+        assert_eq!(
+            key_binding_sequence(&bindings, &keystroke("ctrl-backspace")),
+            Some(&b"\x17"[..])
+        );
+        assert_eq!(key_binding_sequence(&bindings, &keystroke("enter")), None);
+        assert_eq!(
+            key_binding_sequence(&bindings, &keystroke("ctrl-shift-backspace")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sanitize_paste() {
+        let text = "ls\t-la\r\n\x1b[31mred\x07\u{9b}0m\x1b[201~rm -rf ~";
+        assert_eq!(
+            sanitize_paste(text, PasteSanitization::Controls),
+            "ls\t-la\r\n[31mred0m[201~rm -rf ~"
+        );
+        assert_eq!(
+            sanitize_paste(text, PasteSanitization::Escape),
+            "ls\t-la\r\n[31mred\x07\u{9b}0m[201~rm -rf ~"
+        );
+        assert_eq!(
+            sanitize_paste(text, PasteSanitization::Raw),
+            "ls\t-la\r\n\x1b[31mred\x07\u{9b}0mrm -rf ~"
+        );
+        assert_eq!(
+            sanitize_paste("\x1b[20\x1b[201~1~done", PasteSanitization::Raw),
+            "done"
+        );
+    }
+
+    #[test]
+    fn test_is_commit_sha() {
+        assert!(is_commit_sha("5b25d49"));
+        assert!(is_commit_sha("a19ff1d2c0e4b7f3d5a6e8c9b0a1d2e3f4a5b6c7"));
+        assert!(!is_commit_sha("5b25d4"));
+        assert!(!is_commit_sha("a19ff1d2c0e4b7f3d5a6e8c9b0a1d2e3f4a5b6c7d"));
+        assert!(!is_commit_sha("defaced"));
+        assert!(!is_commit_sha("src/main.rs"));
+        assert!(!is_commit_sha("5b25d49:10"));
+    }
+
+    #[test]
+    fn test_resolve_palette_color() {
+        let rgb = Rgb { r: 1, g: 2, b: 3 };
+        let mut colors = Colors::default();
+        colors[1] = Some(rgb);
+        colors[100] = Some(rgb);
+
+        assert_eq!(
+            resolve_palette_color(AnsiColor::Indexed(100), &colors),
+            AnsiColor::Spec(rgb)
+        );
+        assert_eq!(
+            resolve_palette_color(AnsiColor::Indexed(101), &colors),
+            AnsiColor::Indexed(101)
+        );
+        // The first 16 entries are applied to the theme instead.
+        assert_eq!(
+            resolve_palette_color(AnsiColor::Indexed(1), &colors),
+            AnsiColor::Indexed(1)
+        );
+        assert_eq!(
+            resolve_palette_color(AnsiColor::Named(NamedColor::Red), &colors),
+            AnsiColor::Named(NamedColor::Red)
+        );
+    }
 
     #[test]
     fn test_rgb_for_index() {
@@ -1800,6 +4017,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extended_palette() {
+        assert_eq!(EXTENDED_PALETTE[0], rgba_color(0, 0, 0));
+        assert_eq!(EXTENDED_PALETTE[196 - 16], rgba_color(255, 0, 0));
+        assert_eq!(EXTENDED_PALETTE[231 - 16], rgba_color(255, 255, 255));
+        assert_eq!(EXTENDED_PALETTE[244 - 16], rgba_color(120, 120, 120));
+    }
+
     #[test]
     fn test_mouse_to_cell_test() {
         let mut rng = thread_rng();
@@ -1845,6 +4070,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cell_at() {
+        let mut rng = thread_rng();
+        let size = crate::TerminalSize {
+            cell_width: Pixels::from(10.),
+            line_height: Pixels::from(20.),
+            size: size(Pixels::from(100.), Pixels::from(100.)),
+        };
+        let cells = get_cells(size, &mut rng);
+        let content = convert_cells_to_content(size, &cells);
+
+        let cell = content
+            .cell_at(point(Pixels::from(39.5), Pixels::from(20.)))
+            .unwrap();
+        assert_eq!(cell.point, AlacPoint::new(Line(1), Column(3)));
+        assert_eq!(cell.c, cells[1][3]);
+        let cell = content
+            .cell_at(point(Pixels::from(99.), Pixels::from(99.)))
+            .unwrap();
+        assert_eq!(cell.point, AlacPoint::new(Line(4), Column(9)));
+
+        assert!(content
+            .cell_at(point(Pixels::from(-1.), Pixels::from(0.)))
+            .is_none());
+        assert!(content
+            .cell_at(point(Pixels::from(100.), Pixels::from(0.)))
+            .is_none());
+        assert!(content
+            .cell_at(point(Pixels::from(0.), Pixels::from(100.)))
+            .is_none());
+    }
+
+    #[test]
+    fn test_collapsed_rows() {
+        let size = crate::TerminalSize {
+            cell_width: Pixels::from(10.),
+            line_height: Pixels::from(20.),
+            size: size(Pixels::from(100.), Pixels::from(60.)),
+        };
+        let mut content = TerminalContent {
+            size,
+            display_offset: 2,
+            ..Default::default()
+        };
+        assert_eq!(content.row_line(0), Line(-2));
+        assert_eq!(content.line_row(Line(0)), 2);
+        assert!(!content.is_collapsed(Line(0)));
+
+        // Lines -4 to 0 are collapsed onto the middle row.
+        content.rows = vec![Line(-5)..=Line(-5), Line(-4)..=Line(0), Line(1)..=Line(1)];
+        assert_eq!(content.row_line(-1), Line(-6));
+        assert_eq!(content.row_line(1), Line(-4));
+        assert_eq!(content.row_line(2), Line(1));
+        assert_eq!(content.row_line(3), Line(2));
+        assert_eq!(content.line_row(Line(-6)), -1);
+        assert_eq!(content.line_row(Line(-2)), 1);
+        assert_eq!(content.line_row(Line(1)), 2);
+        assert_eq!(content.line_row(Line(3)), 4);
+        assert!(content.is_collapsed(Line(-4)));
+        assert!(!content.is_collapsed(Line(1)));
+
+        let (point, _) = content.grid_point_and_side(point(Pixels::from(15.), Pixels::from(45.)));
+        assert_eq!(point, AlacPoint::new(Line(1), Column(1)));
+    }
+
     #[test]
     fn test_mouse_to_cell_clamp() {
         let mut rng = thread_rng();
@@ -1,12 +1,16 @@
+use anyhow::Context as _;
 use collections::HashMap;
 use gpui::{
-    px, AbsoluteLength, AppContext, FontFallbacks, FontFeatures, FontWeight, Pixels, SharedString,
+    px, AbsoluteLength, AppContext, FontFallbacks, FontFeatures, FontWeight, Hsla, Modifiers,
+    Pixels, Rgba, SharedString,
 };
 use schemars::{gen::SchemaGenerator, schema::RootSchema, JsonSchema};
 use serde_derive::{Deserialize, Serialize};
 use settings::{add_references_to_properties, SettingsJsonSchemaParams, SettingsSources};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use task::Shell;
+use theme::{Appearance, Theme};
+use util::ResultExt;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -24,18 +28,40 @@ pub struct Toolbar {
 #[derive(Debug, Deserialize)]
 pub struct TerminalSettings {
     pub shell: Shell,
+    pub login_shell: bool,
+    pub shell_args: Vec<String>,
     pub working_directory: WorkingDirectory,
     pub font_size: Option<Pixels>,
     pub font_family: Option<SharedString>,
     pub font_fallbacks: Option<FontFallbacks>,
     pub font_features: Option<FontFeatures>,
     pub font_weight: Option<FontWeight>,
+    pub ligatures: bool,
     pub line_height: TerminalLineHeight,
+    pub max_width_columns: Option<usize>,
     pub env: HashMap<String, String>,
+    pub project_environment: bool,
     pub blinking: TerminalBlink,
+    pub cursor_shape: TerminalCursorShape,
+    pub word_characters: String,
+    pub bold_as_bright: bool,
+    pub minimum_contrast: f32,
+    pub inactive_dimming: f32,
+    pub bell: TerminalBell,
     pub alternate_scroll: AlternateScroll,
     pub option_as_meta: bool,
+    pub key_bindings: HashMap<String, String>,
     pub copy_on_select: bool,
+    pub copy_trim_trailing_whitespace: bool,
+    pub copy_join_wrapped_lines: bool,
+    pub hold: bool,
+    pub long_command_notification_seconds: Option<u64>,
+    pub scroll_to_bottom_on_input: bool,
+    pub scroll_to_bottom_on_output: bool,
+    pub scroll_multiplier: f32,
+    pub fast_scroll_multiplier: f32,
+    pub paste_confirmation_characters: String,
+    pub paste_sanitization: PasteSanitization,
     pub button: bool,
     pub dock: TerminalDockPosition,
     pub default_width: Pixels,
@@ -43,6 +69,15 @@ pub struct TerminalSettings {
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
     pub toolbar: Toolbar,
+    pub title: String,
+    pub colors: TerminalColors,
+    pub light_colors: TerminalColors,
+    pub dark_colors: TerminalColors,
+    pub link_rules: Vec<TerminalLinkRule>,
+    pub link_modifier: TerminalLinkModifier,
+    pub link_tooltip: bool,
+    pub profiles: Vec<TerminalProfile>,
+    pub pass_through_programs: Vec<String>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -95,6 +130,15 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: system
     pub shell: Option<Shell>,
+    /// Whether to start the shell as a login shell, by passing it `-l`, so that it
+    /// reads the profile files that set up the environment. Has no effect on Windows.
+    ///
+    /// Default: false
+    pub login_shell: Option<bool>,
+    /// Arguments to pass to the shell, before any arguments given in `shell`.
+    ///
+    /// Default: []
+    pub shell_args: Option<Vec<String>>,
     /// What working directory to use when launching the terminal
     ///
     /// Default: current_project_directory
@@ -120,18 +164,70 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: comfortable
     pub line_height: Option<TerminalLineHeight>,
+    /// The most columns a terminal is laid out with. Wider terminals center their
+    /// text in the space they have.
+    ///
+    /// Default: null
+    pub max_width_columns: Option<usize>,
     pub font_features: Option<FontFeatures>,
     /// Sets the terminal's font weight in CSS weight units 0-900.
     pub font_weight: Option<f32>,
+    /// Whether neighboring characters are drawn with the font's ligatures, such as
+    /// a single arrow for `->`. Characters keep their own cells, so the cursor and
+    /// selections still move one character at a time.
+    ///
+    /// Default: false
+    pub ligatures: Option<bool>,
     /// Any key-value pairs added to this list will be added to the terminal's
-    /// environment. Use `:` to separate multiple values.
+    /// environment. Use `:` to separate multiple values. `$VAR` and `${VAR}`
+    /// in values are replaced with the inherited environment's variables, or
+    /// for a remote shell, with the host's.
     ///
     /// Default: {}
     pub env: Option<HashMap<String, String>>,
+    /// Whether terminals start with the environment the project's language servers
+    /// use: the variables of a shell started in the worktree, direnv included (see
+    /// the `load_direnv` setting). Variables from `env` take precedence.
+    ///
+    /// Default: false
+    pub project_environment: Option<bool>,
     /// Sets the cursor blinking behavior in the terminal.
     ///
     /// Default: terminal_controlled
     pub blinking: Option<TerminalBlink>,
+    /// Sets the default cursor shape in the terminal. Programs can still
+    /// change it with the DECSCUSR escape sequence.
+    ///
+    /// Default: block
+    pub cursor_shape: Option<TerminalCursorShape>,
+    /// Punctuation characters that are treated as part of a word when
+    /// double-clicking to select. Letters and digits always belong to words,
+    /// and any other punctuation separates them.
+    ///
+    /// Default: "!#$%&*+-./;=?@\\^_~"
+    pub word_characters: Option<String>,
+    /// Whether bold text in one of the eight basic ANSI colors is drawn in the
+    /// bright variant of that color, as many older programs expect.
+    ///
+    /// Default: false
+    pub bold_as_bright: Option<bool>,
+    /// The minimum contrast ratio between text and its background, from 1 to 21.
+    /// Text colors that fall short are lightened or darkened until they meet it,
+    /// keeping output readable whatever colors programs pick. 4.5 matches the
+    /// WCAG guideline for normal text, and 1 leaves colors untouched.
+    ///
+    /// Default: 1
+    pub minimum_contrast: Option<f32>,
+    /// How far the contents of a terminal that doesn't have focus fade toward its
+    /// background, from 0 (not at all) to 1 (entirely), so that it's clear which
+    /// terminal gets what is typed.
+    ///
+    /// Default: 0
+    pub inactive_dimming: Option<f32>,
+    /// What happens when a program rings the bell.
+    ///
+    /// Default: tab
+    pub bell: Option<TerminalBell>,
     /// Sets whether Alternate Scroll mode (code: ?1007) is active by default.
     /// Alternate Scroll mode converts mouse scroll events into up / down key
     /// presses when in the alternate screen (e.g. when running applications
@@ -143,11 +239,71 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: true
     pub option_as_meta: Option<bool>,
+    /// Keystrokes that send the given text to the terminal instead of their usual
+    /// escape sequence, e.g. `{"shift-enter": "\u001b[13;2u"}`. Bytes can also be
+    /// written as `\\xNN` hex escapes.
+    ///
+    /// Default: {}
+    pub key_bindings: Option<HashMap<String, String>>,
     /// Whether or not selecting text in the terminal will automatically
     /// copy to the system clipboard.
     ///
     /// Default: false
     pub copy_on_select: Option<bool>,
+    /// Whether to remove whitespace from the end of each line when copying.
+    ///
+    /// Default: false
+    pub copy_trim_trailing_whitespace: Option<bool>,
+    /// Whether lines that only broke because they were wider than the terminal
+    /// are copied as one line, rather than as they appear on screen.
+    ///
+    /// Default: true
+    pub copy_join_wrapped_lines: Option<bool>,
+    /// Whether to keep the terminal open after its shell exits, showing the
+    /// exit status until a key is pressed.
+    ///
+    /// Default: false
+    pub hold: Option<bool>,
+    /// Shows a system notification when a command that ran for at least this many
+    /// seconds finishes while the terminal isn't focused, or Zed isn't the active
+    /// app. Needs a shell whose integration sends OSC 133 marks. Set to null to
+    /// never show them.
+    ///
+    /// Default: null
+    pub long_command_notification_seconds: Option<u64>,
+    /// Whether typing into the terminal scrolls back down to the prompt when
+    /// viewing the history.
+    ///
+    /// Default: true
+    pub scroll_to_bottom_on_input: Option<bool>,
+    /// Whether new output scrolls back down to the bottom when viewing the
+    /// history.
+    ///
+    /// Default: false
+    pub scroll_to_bottom_on_output: Option<bool>,
+    /// How many times faster than usual the mouse wheel and touchpad scroll
+    /// through the history.
+    ///
+    /// Default: 1.0
+    pub scroll_multiplier: Option<f32>,
+    /// How many times faster scrolling gets while holding `alt`, on top of
+    /// `scroll_multiplier`.
+    ///
+    /// Default: 5.0
+    pub fast_scroll_multiplier: Option<f32>,
+    /// Asks for confirmation, with a preview, before pasting text that contains
+    /// any of these characters, since they may run commands as soon as they
+    /// reach the shell. Set to an empty string to never ask. Pastes into
+    /// programs that enabled bracketed paste mode are never held back.
+    ///
+    /// Default: "\n\r"
+    pub paste_confirmation_characters: Option<String>,
+    /// Which control characters to remove from pasted text before it is sent to
+    /// the program. The sequence that ends a bracketed paste is removed
+    /// regardless, so pasted text can't end the paste early and run as typed input.
+    ///
+    /// Default: escape
+    pub paste_sanitization: Option<PasteSanitization>,
     /// Whether to show the terminal button in the status bar.
     ///
     /// Default: true
@@ -177,6 +333,64 @@ pub struct TerminalSettingsContent {
     pub max_scroll_history_lines: Option<usize>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
+    /// The title of terminal tabs. `{process}` is replaced with the command running in
+    /// the foreground, `{cwd}` with the name of its working directory, and `{title}`
+    /// with the title the program set, if any. Task terminals are titled after the task.
+    ///
+    /// Default: "{cwd} — {process}"
+    pub title: Option<String>,
+    /// Colors that replace the theme's terminal colors, as hex strings such as
+    /// "#1e1e1e". Colors that are not set are taken from the theme.
+    ///
+    /// Default: {}
+    pub colors: Option<TerminalColors>,
+    /// Colors layered over `colors` while the system appearance is light.
+    ///
+    /// Default: {}
+    pub light_colors: Option<TerminalColors>,
+    /// Colors layered over `colors` while the system appearance is dark.
+    ///
+    /// Default: {}
+    pub dark_colors: Option<TerminalColors>,
+    /// Patterns in terminal output that become links, in addition to URLs and
+    /// file paths. Each match opens the rule's URL, with `{0}` replaced by the
+    /// matched text and `{1}`, `{2}`, ... by the regex's capture groups.
+    ///
+    /// Default: []
+    pub link_rules: Option<Vec<TerminalLinkRule>>,
+    /// The modifier to hold for links to be underlined and opened by clicking.
+    ///
+    /// Default: cmd_or_ctrl
+    pub link_modifier: Option<TerminalLinkModifier>,
+    /// Whether hovering a link shows where it goes: the URL, or the absolute path
+    /// of the file it opens.
+    ///
+    /// Default: true
+    pub link_tooltip: Option<bool>,
+    /// Named ways to start a terminal, each with its own program, arguments,
+    /// environment and working directory, that `terminal: new terminal with profile`
+    /// offers next to the default `shell`.
+    ///
+    /// Default: []
+    pub profiles: Option<Vec<TerminalProfile>>,
+    /// Full-screen programs that get keystrokes Zed would otherwise handle itself, such
+    /// as `ctrl-p`, while they run in the foreground. Any program that switches to the
+    /// alternate screen gets them too. `terminal: toggle pass through` suspends this.
+    ///
+    /// Default: ["vim", "nvim", "emacs", "tmux", "screen", "zellij"]
+    pub pass_through_programs: Option<Vec<String>>,
+}
+
+impl TerminalSettings {
+    /// The colors to replace the theme's terminal colors with, in the order to apply
+    /// them: `colors`, then the ones for the system `appearance`.
+    pub fn color_overrides(&self, appearance: Appearance) -> [&TerminalColors; 2] {
+        let appearance_colors = match appearance {
+            Appearance::Light => &self.light_colors,
+            Appearance::Dark => &self.dark_colors,
+        };
+        [&self.colors, appearance_colors]
+    }
 }
 
 impl settings::Settings for TerminalSettings {
@@ -188,7 +402,11 @@ impl settings::Settings for TerminalSettings {
         sources: SettingsSources<Self::FileContent>,
         _: &mut AppContext,
     ) -> anyhow::Result<Self> {
-        sources.json_merge()
+        let mut settings: Self = sources.json_merge()?;
+        settings.colors.remove_invalid();
+        settings.light_colors.remove_invalid();
+        settings.dark_colors.remove_invalid();
+        Ok(settings)
     }
 
     fn json_schema(
@@ -250,6 +468,73 @@ pub enum TerminalBlink {
     On,
 }
 
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalCursorShape {
+    /// A block that surrounds the following character.
+    #[default]
+    Block,
+    /// A vertical bar.
+    Bar,
+    /// An underline that runs along the following character.
+    Underline,
+    /// A box drawn around the following character.
+    Hollow,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalBell {
+    /// Only mark the terminal's tab.
+    #[default]
+    Tab,
+    /// Play the system alert sound.
+    Audible,
+    /// Briefly flash the terminal.
+    Visual,
+    /// Ignore the bell.
+    None,
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalLinkModifier {
+    /// `cmd` on macOS, and `ctrl` elsewhere.
+    #[default]
+    CmdOrCtrl,
+    /// `cmd` on macOS, and the super or Windows key elsewhere.
+    Cmd,
+    Ctrl,
+    Alt,
+    /// Links work without holding a modifier.
+    None,
+}
+
+impl TerminalLinkModifier {
+    /// Whether `modifiers` include this one.
+    pub fn is_pressed(&self, modifiers: &Modifiers) -> bool {
+        match self {
+            Self::CmdOrCtrl => modifiers.secondary(),
+            Self::Cmd => modifiers.platform,
+            Self::Ctrl => modifiers.control,
+            Self::Alt => modifiers.alt,
+            Self::None => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteSanitization {
+    /// Remove control characters other than tab and line breaks.
+    Controls,
+    /// Remove escape characters, which start terminal control sequences.
+    #[default]
+    Escape,
+    /// Paste the text as it is.
+    Raw,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AlternateScroll {
@@ -273,6 +558,184 @@ pub enum WorkingDirectory {
     Always { directory: String },
 }
 
+/// A named terminal configuration. Anything it leaves out comes from the
+/// `shell`, `env` and `working_directory` settings.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct TerminalProfile {
+    /// The name to pick the profile by.
+    pub name: String,
+    /// The program to run instead of the default shell.
+    #[serde(default)]
+    pub program: Option<String>,
+    /// The arguments to pass to `program`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Variables to add to the environment, after the ones from `env`.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Where to start the terminal, instead of the `working_directory` setting.
+    #[serde(default)]
+    pub working_directory: Option<WorkingDirectory>,
+    /// The WSL distribution to run `program` (or its default shell) in, on Windows.
+    #[serde(default)]
+    pub wsl_distribution: Option<String>,
+}
+
+impl TerminalProfile {
+    /// The shell to start, falling back to `default_shell` without a `program`.
+    /// WSL profiles start `wsl.exe` in `working_directory`, translated to where the
+    /// distribution mounts it.
+    pub fn shell(&self, default_shell: &Shell, working_directory: Option<&Path>) -> Shell {
+        if let Some(distribution) = &self.wsl_distribution {
+            let mut args = vec!["-d".to_string(), distribution.clone()];
+            if let Some(directory) = working_directory.and_then(crate::wsl::windows_path_to_wsl) {
+                args.extend(["--cd".to_string(), directory]);
+            }
+            if let Some(program) = &self.program {
+                args.extend(["--".to_string(), program.clone()]);
+                args.extend(self.args.iter().cloned());
+            }
+            return Shell::WithArguments {
+                program: "wsl.exe".to_string(),
+                args,
+            };
+        }
+
+        match &self.program {
+            Some(program) if self.args.is_empty() => Shell::Program(program.clone()),
+            Some(program) => Shell::WithArguments {
+                program: program.clone(),
+                args: self.args.clone(),
+            },
+            None => default_shell.clone(),
+        }
+    }
+}
+
+/// Turns text matching `regex` into a link to `url`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct TerminalLinkRule {
+    pub regex: String,
+    pub url: String,
+}
+
+/// Overrides for the theme's terminal colors.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct TerminalColors {
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub cursor: Option<String>,
+    /// The color of the character under a block cursor.
+    pub cursor_text: Option<String>,
+    pub black: Option<String>,
+    pub red: Option<String>,
+    pub green: Option<String>,
+    pub yellow: Option<String>,
+    pub blue: Option<String>,
+    pub magenta: Option<String>,
+    pub cyan: Option<String>,
+    pub white: Option<String>,
+    pub bright_black: Option<String>,
+    pub bright_red: Option<String>,
+    pub bright_green: Option<String>,
+    pub bright_yellow: Option<String>,
+    pub bright_blue: Option<String>,
+    pub bright_magenta: Option<String>,
+    pub bright_cyan: Option<String>,
+    pub bright_white: Option<String>,
+}
+
+impl TerminalColors {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Unsets the colors that aren't valid, logging each. Settings are loaded far
+    /// less often than the theme is built from them, so they're reported once.
+    fn remove_invalid(&mut self) {
+        for (name, value) in self.values_mut() {
+            let Some(color) = value.as_deref() else {
+                continue;
+            };
+            if Rgba::try_from(color)
+                .with_context(|| format!("invalid terminal color {name}: {color:?}"))
+                .log_err()
+                .is_none()
+            {
+                *value = None;
+            }
+        }
+    }
+
+    fn values_mut(&mut self) -> [(&'static str, &mut Option<String>); 19] {
+        [
+            ("foreground", &mut self.foreground),
+            ("background", &mut self.background),
+            ("cursor", &mut self.cursor),
+            ("black", &mut self.black),
+            ("red", &mut self.red),
+            ("green", &mut self.green),
+            ("yellow", &mut self.yellow),
+            ("blue", &mut self.blue),
+            ("magenta", &mut self.magenta),
+            ("cyan", &mut self.cyan),
+            ("white", &mut self.white),
+            ("bright_black", &mut self.bright_black),
+            ("bright_red", &mut self.bright_red),
+            ("bright_green", &mut self.bright_green),
+            ("bright_yellow", &mut self.bright_yellow),
+            ("bright_blue", &mut self.bright_blue),
+            ("bright_magenta", &mut self.bright_magenta),
+            ("bright_cyan", &mut self.bright_cyan),
+            ("bright_white", &mut self.bright_white),
+        ]
+    }
+
+    /// Replaces the theme's terminal colors with the ones that are set. Invalid
+    /// values were unset when settings loaded.
+    pub fn apply_to(&self, theme: &mut Theme) {
+        fn set(color: &mut Hsla, value: &Option<String>) {
+            if let Some(value) = value {
+                if let Ok(rgba) = Rgba::try_from(value.as_str()) {
+                    *color = rgba.into();
+                }
+            }
+        }
+
+        let colors = &mut theme.styles.colors;
+        set(&mut colors.terminal_foreground, &self.foreground);
+        set(&mut colors.terminal_background, &self.background);
+        set(&mut colors.terminal_ansi_black, &self.black);
+        set(&mut colors.terminal_ansi_red, &self.red);
+        set(&mut colors.terminal_ansi_green, &self.green);
+        set(&mut colors.terminal_ansi_yellow, &self.yellow);
+        set(&mut colors.terminal_ansi_blue, &self.blue);
+        set(&mut colors.terminal_ansi_magenta, &self.magenta);
+        set(&mut colors.terminal_ansi_cyan, &self.cyan);
+        set(&mut colors.terminal_ansi_white, &self.white);
+        set(&mut colors.terminal_ansi_bright_black, &self.bright_black);
+        set(&mut colors.terminal_ansi_bright_red, &self.bright_red);
+        set(&mut colors.terminal_ansi_bright_green, &self.bright_green);
+        set(&mut colors.terminal_ansi_bright_yellow, &self.bright_yellow);
+        set(&mut colors.terminal_ansi_bright_blue, &self.bright_blue);
+        set(
+            &mut colors.terminal_ansi_bright_magenta,
+            &self.bright_magenta,
+        );
+        set(&mut colors.terminal_ansi_bright_cyan, &self.bright_cyan);
+        set(&mut colors.terminal_ansi_bright_white, &self.bright_white);
+        if let Some(local_player) = theme.styles.player.0.first_mut() {
+            set(&mut local_player.cursor, &self.cursor);
+        }
+    }
+
+    /// The color to draw the character under a block cursor in, if it is set.
+    pub fn cursor_text_color(&self) -> Option<Hsla> {
+        let rgba = Rgba::try_from(self.cursor_text.as_deref()?).ok()?;
+        Some(rgba.into())
+    }
+}
+
 // Toolbar related settings
 #[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct ToolbarContent {
@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line},
+    term::cell::Flags,
+    Term,
+};
+
+/// The text a terminal shows, line by line, for assistive technologies such as
+/// screen readers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextSnapshot {
+    /// The visible lines, top to bottom, without trailing whitespace.
+    pub lines: Vec<String>,
+    /// The line the cursor is on, or `None` when it is scrolled out of view.
+    pub cursor_line: Option<usize>,
+    pub cursor_column: usize,
+}
+
+/// Lines that changed between two snapshots of the same terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextChange {
+    /// The range of `lines` in the new snapshot that differ from what was shown before.
+    pub lines: Range<usize>,
+    /// The text of those lines, joined with newlines.
+    pub text: String,
+}
+
+impl TextSnapshot {
+    pub(crate) fn new<T>(term: &Term<T>) -> Self {
+        let display_offset = term.grid().display_offset() as i32;
+        let lines = (0..term.screen_lines() as i32)
+            .map(|line| {
+                let row = &term.grid()[Line(line - display_offset)];
+                let mut text = String::new();
+                for column in 0..term.columns() {
+                    let cell = &row[Column(column)];
+                    if cell
+                        .flags
+                        .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+                    {
+                        continue;
+                    }
+                    text.push(cell.c);
+                    if let Some(zerowidth) = cell.zerowidth() {
+                        text.extend(zerowidth);
+                    }
+                }
+                text.truncate(text.trim_end().len());
+                text
+            })
+            .collect();
+
+        let cursor = term.grid().cursor.point;
+        let cursor_line = cursor.line.0 + display_offset;
+        Self {
+            lines,
+            cursor_line: usize::try_from(cursor_line)
+                .ok()
+                .filter(|line| *line < term.screen_lines()),
+            cursor_column: cursor.column.0,
+        }
+    }
+
+    /// Describes what is new in this snapshot compared to `previous`. Output that
+    /// scrolled the screen up only reports the lines that scrolled into view.
+    pub fn changes_since(&self, previous: &TextSnapshot) -> Option<TextChange> {
+        let lines = changed_lines(&previous.lines, &self.lines)?;
+        Some(TextChange {
+            text: self.lines[lines.clone()].join("\n"),
+            lines,
+        })
+    }
+}
+
+fn changed_lines(old: &[String], new: &[String]) -> Option<Range<usize>> {
+    if old == new {
+        return None;
+    }
+    if old.len() != new.len() {
+        return Some(0..new.len());
+    }
+
+    let len = new.len();
+    let unchanged_prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let unchanged_suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let edited = unchanged_prefix..len - unchanged_suffix;
+
+    // Output that scrolls the screen moves the old lines up, and only the lines
+    // below them are new.
+    let scrolled = (1..len)
+        .find(|shift| old[*shift..] == new[..len - shift])
+        .map(|shift| len - shift..len);
+    match scrolled {
+        Some(scrolled) if scrolled.len() < edited.len() => Some(scrolled),
+        _ => Some(edited),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('|').map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        assert_eq!(changed_lines(&lines("a|b|c"), &lines("a|b|c")), None);
+        assert_eq!(changed_lines(&lines("a|b|c"), &lines("a|x|c")), Some(1..2));
+        assert_eq!(changed_lines(&lines("$|"), &lines("$ ls|")), Some(0..1));
+        // Output scrolled the screen by two lines.
+        assert_eq!(
+            changed_lines(&lines("a|b|c|d"), &lines("c|d|e|f")),
+            Some(2..4)
+        );
+        assert_eq!(changed_lines(&lines("|||"), &lines("|x||")), Some(1..2));
+        assert_eq!(changed_lines(&lines("a|b"), &lines("a|b|c")), Some(0..3));
+    }
+}
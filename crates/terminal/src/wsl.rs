@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use crate::terminal_settings::TerminalProfile;
+
+/// A profile for each installed WSL distribution, named like "WSL: Ubuntu". There are
+/// none outside of Windows.
+///
+/// Distributions are listed by running `wsl.exe`, which blocks, so call this on the
+/// background executor.
+pub fn wsl_profiles() -> Vec<TerminalProfile> {
+    installed_distributions()
+        .into_iter()
+        .map(|distribution| TerminalProfile {
+            name: format!("WSL: {distribution}"),
+            wsl_distribution: Some(distribution),
+            ..TerminalProfile::default()
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn installed_distributions() -> Vec<String> {
+    use std::os::windows::process::CommandExt;
+    use util::ResultExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    std::process::Command::new("wsl.exe")
+        .args(["--list", "--quiet"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .log_err()
+        .filter(|output| output.status.success())
+        .map_or_else(Vec::new, |output| parse_distribution_list(&output.stdout))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn installed_distributions() -> Vec<String> {
+    Vec::new()
+}
+
+/// Parses the output of `wsl.exe --list --quiet`, which is UTF-16 with a distribution
+/// name on each line.
+#[cfg_attr(not(any(target_os = "windows", test)), allow(dead_code))]
+fn parse_distribution_list(output: &[u8]) -> Vec<String> {
+    let units = output
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\0' || c == '\u{feff}'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Where a Windows path is mounted in WSL, such as `/mnt/c/Users` for `C:\Users`.
+/// Paths that aren't on a drive, like network shares, have no such place.
+pub(crate) fn windows_path_to_wsl(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    let rest = chars.as_str().replace('\\', "/");
+    let rest = rest.trim_end_matches('/');
+    Some(format!("/mnt/{}{rest}", drive.to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_distribution_list() {
+        let output = "\u{feff}Ubuntu-22.04\r\nDebian\r\n\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(parse_distribution_list(&output), ["Ubuntu-22.04", "Debian"]);
+    }
+
+    #[test]
+    fn test_windows_path_to_wsl() {
+        let wsl_path = |path: &str| windows_path_to_wsl(Path::new(path));
+        assert_eq!(
+            wsl_path(r"C:\Users\me\src"),
+            Some("/mnt/c/Users/me/src".into())
+        );
+        assert_eq!(wsl_path(r"D:\"), Some("/mnt/d".into()));
+        assert_eq!(wsl_path(r"\\?\E:\work"), Some("/mnt/e/work".into()));
+        assert_eq!(wsl_path(r"\\server\share"), None);
+        assert_eq!(wsl_path("relative"), None);
+    }
+}
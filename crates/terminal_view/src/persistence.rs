@@ -4,6 +4,8 @@ use std::path::PathBuf;
 use db::{define_connection, query, sqlez::statement::Statement, sqlez_macros::sql};
 use workspace::{ItemId, WorkspaceDb, WorkspaceId};
 
+const SCROLLBACK_COMPRESSION_LEVEL: i32 = 3;
+
 define_connection! {
     pub static ref TERMINAL_DB: TerminalDb<WorkspaceDb> =
         &[sql!(
@@ -35,6 +37,13 @@ define_connection! {
             DROP TABLE terminals;
 
             ALTER TABLE terminals2 RENAME TO terminals;
+        ),
+        // Scrollback is stored zstd-compressed.
+        sql!(
+            ALTER TABLE terminals ADD COLUMN scrollback BLOB;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN title TEXT;
         )];
 }
 
@@ -57,8 +66,76 @@ impl TerminalDb {
             workspace_id: WorkspaceId,
             working_directory: PathBuf
         ) -> Result<()> {
-            INSERT OR REPLACE INTO terminals(item_id, workspace_id, working_directory)
-            VALUES (?, ?, ?)
+            INSERT INTO terminals(item_id, workspace_id, working_directory)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                working_directory = ?3
+        }
+    }
+
+    pub async fn save_scrollback(
+        &self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+        scrollback: String,
+    ) -> Result<()> {
+        let scrollback =
+            zstd::stream::encode_all(scrollback.as_bytes(), SCROLLBACK_COMPRESSION_LEVEL)?;
+        self.save_compressed_scrollback(item_id, workspace_id, scrollback)
+            .await
+    }
+
+    pub fn get_scrollback(
+        &self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+    ) -> Result<Option<String>> {
+        let Some(scrollback) = self.get_compressed_scrollback(item_id, workspace_id)? else {
+            return Ok(None);
+        };
+        let scrollback = zstd::stream::decode_all(scrollback.as_slice())?;
+        Ok(Some(String::from_utf8(scrollback)?))
+    }
+
+    query! {
+        async fn save_compressed_scrollback(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            scrollback: Vec<u8>
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, scrollback)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                scrollback = ?3
+        }
+    }
+
+    query! {
+        fn get_compressed_scrollback(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<Vec<u8>>> {
+            SELECT scrollback
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ? AND scrollback IS NOT NULL
+        }
+    }
+
+    query! {
+        pub async fn save_title(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            title: Option<String>
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, title)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(workspace_id, item_id) DO UPDATE SET
+                title = ?3
+        }
+    }
+
+    query! {
+        pub fn get_title(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT title
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ? AND title IS NOT NULL
         }
     }
 
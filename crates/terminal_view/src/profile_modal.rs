@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, Task,
+    View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use settings::Settings;
+use terminal::terminal_settings::{TerminalProfile, TerminalSettings};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::terminal_panel::TerminalPanel;
+
+actions!(terminal_panel, [NewTerminalWithProfile]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(ProfileModal::toggle);
+    })
+    .detach();
+}
+
+/// Lets the user pick one of the `terminal.profiles`, or the default shell, and opens
+/// a terminal with it in the terminal panel.
+pub struct ProfileModal {
+    picker: View<Picker<ProfileDelegate>>,
+    _load_wsl_profiles: Task<()>,
+}
+
+impl ProfileModal {
+    fn toggle(
+        workspace: &mut Workspace,
+        _: &NewTerminalWithProfile,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) else {
+            return;
+        };
+        let terminal_panel = terminal_panel.downgrade();
+        workspace.toggle_modal(cx, |cx| Self::new(terminal_panel, cx));
+    }
+
+    fn new(terminal_panel: WeakView<TerminalPanel>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate = ProfileDelegate {
+            modal: cx.view().downgrade(),
+            terminal_panel,
+            profiles: TerminalSettings::get_global(cx).profiles.clone(),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+
+        // Distributions can be installed or removed at any time, so they're listed
+        // again each time the modal opens.
+        let wsl_profiles = cx
+            .background_executor()
+            .spawn(async { terminal::wsl_profiles() });
+        let load_wsl_profiles = cx.spawn(|this, mut cx| async move {
+            let wsl_profiles = wsl_profiles.await;
+            if wsl_profiles.is_empty() {
+                return;
+            }
+            this.update(&mut cx, |this, cx| {
+                this.picker.update(cx, |picker, cx| {
+                    picker.delegate.add_profiles(wsl_profiles);
+                    picker.refresh(cx);
+                })
+            })
+            .ok();
+        });
+
+        Self {
+            picker,
+            _load_wsl_profiles: load_wsl_profiles,
+        }
+    }
+}
+
+impl Render for ProfileModal {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for ProfileModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for ProfileModal {}
+impl ModalView for ProfileModal {}
+
+const DEFAULT_PROFILE_NAME: &str = "Default";
+
+pub struct ProfileDelegate {
+    modal: WeakView<ProfileModal>,
+    terminal_panel: WeakView<TerminalPanel>,
+    profiles: Vec<TerminalProfile>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ProfileDelegate {
+    /// Adds profiles after the configured ones, unless one of those already took
+    /// their name.
+    fn add_profiles(&mut self, profiles: Vec<TerminalProfile>) {
+        for profile in profiles {
+            if self
+                .profiles
+                .iter()
+                .all(|existing| existing.name != profile.name)
+            {
+                self.profiles.push(profile);
+            }
+        }
+    }
+
+    /// The profile behind a candidate id. The first candidate is the default shell,
+    /// which is `None`.
+    fn profile(&self, candidate_id: usize) -> Option<TerminalProfile> {
+        candidate_id
+            .checked_sub(1)
+            .and_then(|ix| self.profiles.get(ix).cloned())
+    }
+}
+
+impl PickerDelegate for ProfileDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Open a terminal with profile...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = std::iter::once(DEFAULT_PROFILE_NAME.to_string())
+            .chain(self.profiles.iter().map(|profile| profile.name.clone()))
+            .enumerate()
+            .map(|(id, name)| StringMatchCandidate::new(id, name))
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(mat) = self.matches.get(self.selected_index) else {
+            return;
+        };
+        let profile = self.profile(mat.candidate_id);
+        self.terminal_panel
+            .update(cx, |terminal_panel, cx| {
+                terminal_panel
+                    .new_terminal_with_profile(profile, cx)
+                    .detach_and_log_err(cx)
+            })
+            .log_err();
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -0,0 +1,86 @@
+use editor::Editor;
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, View, ViewContext,
+    VisualContext, WeakView,
+};
+use ui::{prelude::*, v_flex, Label};
+use workspace::ModalView;
+
+use crate::TerminalView;
+
+/// Prompts for a new tab title for a terminal. Confirming an empty title goes back to
+/// the title derived from the running process.
+pub struct RenameTerminalModal {
+    terminal_view: WeakView<TerminalView>,
+    title_editor: View<Editor>,
+}
+
+impl ModalView for RenameTerminalModal {}
+
+impl EventEmitter<DismissEvent> for RenameTerminalModal {}
+
+impl FocusableView for RenameTerminalModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.title_editor.focus_handle(cx)
+    }
+}
+
+impl RenameTerminalModal {
+    pub fn new(
+        terminal_view: WeakView<TerminalView>,
+        current_title: String,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let title_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_text(current_title, cx);
+            editor.select_all(&editor::actions::SelectAll, cx);
+            editor
+        });
+        cx.subscribe(&title_editor, |_, _, event, cx| {
+            if let editor::EditorEvent::Blurred = event {
+                cx.emit(DismissEvent);
+            }
+        })
+        .detach();
+
+        Self {
+            terminal_view,
+            title_editor,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let title = self.title_editor.read(cx).text(cx).trim().to_string();
+        self.terminal_view
+            .update(cx, |terminal_view, cx| {
+                terminal_view.set_custom_title((!title.is_empty()).then_some(title), cx);
+                terminal_view.focus_handle.focus(cx);
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for RenameTerminalModal {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .elevation_2(cx)
+            .key_context("RenameTerminal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .w_96()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .child(self.title_editor.clone())
+            .child(
+                Label::new("Rename terminal, or leave empty to use the process title")
+                    .color(Color::Muted),
+            )
+    }
+}
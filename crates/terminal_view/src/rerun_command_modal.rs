@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, Task, View,
+    ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::ModalView;
+
+use crate::TerminalView;
+
+/// Offers the commands run in the project's terminals, and sends the chosen one to
+/// a terminal again.
+pub struct RerunCommandModal {
+    picker: View<Picker<RerunCommandDelegate>>,
+}
+
+impl RerunCommandModal {
+    /// `commands` are the ones to offer, most relevant first.
+    pub fn new(
+        terminal_view: WeakView<TerminalView>,
+        commands: Vec<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = RerunCommandDelegate {
+            modal: cx.view().downgrade(),
+            terminal_view,
+            commands,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for RerunCommandModal {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for RerunCommandModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for RerunCommandModal {}
+impl ModalView for RerunCommandModal {}
+
+pub struct RerunCommandDelegate {
+    modal: WeakView<RerunCommandModal>,
+    terminal_view: WeakView<TerminalView>,
+    commands: Vec<String>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl PickerDelegate for RerunCommandDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Rerun a command...".into()
+    }
+
+    fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
+        "No commands were run in this project's terminals".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(id, command)| StringMatchCandidate::new(id, command.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    /// Runs the command, or with `secondary`, types it without running it so that it
+    /// can be edited first.
+    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(command) = self
+            .matches
+            .get(self.selected_index)
+            .map(|mat| mat.string.clone())
+        else {
+            return;
+        };
+        self.terminal_view
+            .update(cx, |terminal_view, cx| {
+                terminal_view.terminal.update(cx, |terminal, _| {
+                    terminal.rerun_command(&command, !secondary)
+                });
+                terminal_view.focus_handle.focus(cx);
+            })
+            .log_err();
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use db::kvp::KEY_VALUE_STORE;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, Task,
+    View, ViewContext, VisualContext, WeakView,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace, WorkspaceId};
+
+use crate::terminal_panel::TerminalPanel;
+
+const RUN_COMMAND_HISTORY_KEY: &str = "TerminalRunCommandHistory";
+const MAX_HISTORY_LEN: usize = 50;
+
+actions!(terminal_panel, [RunCommand]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(RunCommandModal::toggle);
+    })
+    .detach();
+}
+
+/// Prompts for a shell command, offering previously run ones, and runs it in the
+/// terminal panel.
+pub struct RunCommandModal {
+    picker: View<Picker<RunCommandDelegate>>,
+}
+
+impl RunCommandModal {
+    fn toggle(workspace: &mut Workspace, _: &RunCommand, cx: &mut ViewContext<Workspace>) {
+        let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) else {
+            return;
+        };
+        let history_key = history_key(workspace.database_id());
+        cx.spawn(|workspace, mut cx| async move {
+            let history = cx
+                .background_executor()
+                .spawn({
+                    let history_key = history_key.clone();
+                    async move { KEY_VALUE_STORE.read_kvp(&history_key) }
+                })
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|history| serde_json::from_str::<Vec<String>>(&history).log_err())
+                .unwrap_or_default();
+            workspace.update(&mut cx, |workspace, cx| {
+                let terminal_panel = terminal_panel.downgrade();
+                workspace
+                    .toggle_modal(cx, |cx| Self::new(terminal_panel, history_key, history, cx));
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn new(
+        terminal_panel: WeakView<TerminalPanel>,
+        history_key: String,
+        history: Vec<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate = RunCommandDelegate {
+            modal: cx.view().downgrade(),
+            terminal_panel,
+            history_key,
+            history,
+            matches: Vec::new(),
+            selected_index: 0,
+            selected_explicitly: false,
+            query: String::new(),
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+fn history_key(workspace_id: Option<WorkspaceId>) -> String {
+    match workspace_id {
+        Some(workspace_id) => {
+            let workspace_id: i64 = workspace_id.into();
+            format!("{RUN_COMMAND_HISTORY_KEY}-{workspace_id}")
+        }
+        None => RUN_COMMAND_HISTORY_KEY.to_string(),
+    }
+}
+
+impl Render for RunCommandModal {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for RunCommandModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for RunCommandModal {}
+impl ModalView for RunCommandModal {}
+
+pub struct RunCommandDelegate {
+    modal: WeakView<RunCommandModal>,
+    terminal_panel: WeakView<TerminalPanel>,
+    history_key: String,
+    /// Previously run commands, most recent first.
+    history: Vec<String>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+    /// Whether the selected command was picked by the user since the query last
+    /// changed. Until then, enter runs the query as typed.
+    selected_explicitly: bool,
+    query: String,
+}
+
+impl RunCommandDelegate {
+    /// The history entry enter runs: the one the user picked, or the first one when
+    /// nothing has been typed.
+    fn selected_match(&self) -> Option<&StringMatch> {
+        if self.selected_explicitly || self.query.trim().is_empty() {
+            self.matches.get(self.selected_index)
+        } else {
+            None
+        }
+    }
+
+    fn run(&mut self, command: String, cx: &mut ViewContext<Picker<Self>>) {
+        self.history.retain(|entry| *entry != command);
+        self.history.insert(0, command.clone());
+        self.history.truncate(MAX_HISTORY_LEN);
+        if let Some(history) = serde_json::to_string(&self.history).log_err() {
+            let history_key = self.history_key.clone();
+            cx.background_executor()
+                .spawn(async move { KEY_VALUE_STORE.write_kvp(history_key, history).await })
+                .detach_and_log_err(cx);
+        }
+
+        self.terminal_panel
+            .update(cx, |terminal_panel, cx| {
+                terminal_panel
+                    .run_command(command, cx)
+                    .detach_and_log_err(cx)
+            })
+            .log_err();
+        self.dismissed(cx);
+    }
+}
+
+impl PickerDelegate for RunCommandDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Run a command in the terminal...".into()
+    }
+
+    fn no_matches_text(&self, _cx: &mut WindowContext) -> SharedString {
+        "Press enter to run this command".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+        self.selected_explicitly = true;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(id, command)| StringMatchCandidate::new(id, command.clone()))
+            .collect::<Vec<_>>();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                if delegate.query != query {
+                    delegate.selected_explicitly = false;
+                }
+                delegate.query = query;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, secondary: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let command = match self.selected_match() {
+            Some(mat) if !secondary => mat.string.clone(),
+            _ => self.query.trim().to_string(),
+        };
+        if !command.is_empty() {
+            self.run(command, cx);
+        }
+    }
+
+    fn confirm_input(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let command = self.query.trim().to_string();
+        if !command.is_empty() {
+            self.run(command, cx);
+        }
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.modal
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected && self.selected_match().is_some())
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
@@ -4,8 +4,9 @@ use gpui::{
     DispatchPhase, Element, ElementId, FocusHandle, Font, FontStyle, FontWeight, GlobalElementId,
     HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity, IntoElement,
     LayoutId, Model, ModelContext, ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels,
-    Point, ShapedLine, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle,
-    UTF16Selection, UnderlineStyle, View, WeakView, WhiteSpace, WindowContext, WindowTextSystem,
+    Point, ShapedLine, SharedString, StatefulInteractiveElement, StrikethroughStyle, Styled,
+    TextRun, TextStyle, UTF16Selection, UnderlineStyle, View, WeakView, WhiteSpace, WindowContext,
+    WindowTextSystem,
 };
 use itertools::Itertools;
 use language::CursorShape;
@@ -13,13 +14,15 @@ use settings::Settings;
 use terminal::{
     alacritty_terminal::{
         grid::Dimensions,
-        index::Point as AlacPoint,
+        index::{Line as AlacLine, Point as AlacPoint},
         term::{cell::Flags, TermMode},
         vte::ansi::{
             Color::{self as AnsiColor, Named},
             CursorShape as AlacCursorShape, NamedColor,
         },
     },
+    convert_color,
+    mappings::colors::ensure_minimum_contrast,
     terminal_settings::TerminalSettings,
     HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
 };
@@ -28,9 +31,12 @@ use ui::{ParentElement, Tooltip};
 use workspace::Workspace;
 
 use std::mem;
-use std::{fmt::Debug, ops::RangeInclusive, rc::Rc};
+use std::{cell::RefCell, fmt::Debug, ops::RangeInclusive, rc::Rc, sync::Arc};
 
-use crate::{BlockContext, BlockProperties, TerminalView};
+use crate::{
+    broadcast_targets, BlockContext, BlockProperties, TerminalFontSizeAdjustment, TerminalView,
+    MIN_FONT_SIZE,
+};
 
 /// The information generated during layout that is necessary for painting.
 pub struct LayoutState {
@@ -42,7 +48,6 @@ pub struct LayoutState {
     background_color: Hsla,
     dimensions: TerminalSize,
     mode: TermMode,
-    display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
     last_hovered_word: Option<HoveredWord>,
@@ -56,9 +61,9 @@ struct DisplayCursor {
 }
 
 impl DisplayCursor {
-    fn from(cursor_point: AlacPoint, display_offset: usize) -> Self {
+    fn from(cursor_point: AlacPoint, content: &TerminalContent) -> Self {
         Self {
-            line: cursor_point.line.0 + display_offset as i32,
+            line: content.line_row(cursor_point.line),
             col: cursor_point.column.0,
         }
     }
@@ -72,7 +77,7 @@ impl DisplayCursor {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct LayoutCell {
     pub point: AlacPoint<i32, i32>,
     text: gpui::ShapedLine,
@@ -145,6 +150,229 @@ impl LayoutRect {
     }
 }
 
+/// What laying out the cells of the grid depends on, other than the cells.
+struct GridLayout<'a> {
+    text_style: &'a TextStyle,
+    text_system: &'a WindowTextSystem,
+    hyperlink: Option<(HighlightStyle, &'a RangeInclusive<AlacPoint>)>,
+    theme: &'a Theme,
+    font_size: Pixels,
+    bold_as_bright: bool,
+    minimum_contrast: f32,
+    ligatures: bool,
+}
+
+impl<'a> GridLayout<'a> {
+    fn new(
+        text_style: &'a TextStyle,
+        text_system: &'a WindowTextSystem,
+        hyperlink: Option<(HighlightStyle, &'a RangeInclusive<AlacPoint>)>,
+        theme: &'a Theme,
+        cx: &WindowContext,
+    ) -> Self {
+        let terminal_settings = TerminalSettings::get_global(cx);
+        Self {
+            text_style,
+            text_system,
+            hyperlink,
+            theme,
+            font_size: text_style.font_size.to_pixels(cx.rem_size()),
+            bold_as_bright: terminal_settings.bold_as_bright,
+            minimum_contrast: terminal_settings.minimum_contrast,
+            ligatures: terminal_settings.ligatures,
+        }
+    }
+
+    /// Lays out the cells of the line at `line_index`, which is laid out on its
+    /// own: background rects and ligatures stop at the end of the line.
+    fn layout_line(
+        &self,
+        line_index: usize,
+        line: impl Iterator<Item = IndexedCell>,
+        cells: &mut Vec<LayoutCell>,
+        rects: &mut Vec<LayoutRect>,
+    ) {
+        let GridLayout {
+            text_style,
+            text_system,
+            hyperlink,
+            theme,
+            font_size,
+            bold_as_bright,
+            minimum_contrast,
+            ligatures,
+        } = *self;
+        let shape = |text: String, mut style: TextRun| {
+            style.len = text.len();
+            text_system
+                .shape_line(text.into(), font_size, &[style])
+                .unwrap()
+        };
+
+        let mut cur_rect: Option<LayoutRect> = None;
+        let mut cur_alac_color = None;
+        // Consecutive ASCII cells with the same style, shaped together so the font
+        // can join them into ligatures. Other characters are shaped on their own,
+        // as a fallback font's glyphs could push the rest of a run off its cells.
+        let mut ligature_run: Option<(AlacPoint<i32, i32>, String, TextRun)> = None;
+
+        for cell in line {
+            let mut fg = cell.fg;
+            let mut bg = cell.bg;
+            if cell.flags.contains(Flags::INVERSE) {
+                mem::swap(&mut fg, &mut bg);
+            }
+
+            //Expand background rect range
+            {
+                if matches!(bg, Named(NamedColor::Background)) {
+                    //Continue to next cell, resetting variables if necessary
+                    cur_alac_color = None;
+                    if let Some(rect) = cur_rect {
+                        rects.push(rect);
+                        cur_rect = None
+                    }
+                } else {
+                    match cur_alac_color {
+                        Some(cur_color) => {
+                            if bg == cur_color {
+                                // `cur_rect` can be None if it was moved to the `rects` vec after wrapping around
+                                // from one line to the next. The variables are all set correctly but there is no current
+                                // rect, so we create one if necessary.
+                                cur_rect = cur_rect.map_or_else(
+                                    || {
+                                        Some(LayoutRect::new(
+                                            AlacPoint::new(
+                                                line_index as i32,
+                                                cell.point.column.0 as i32,
+                                            ),
+                                            1,
+                                            convert_color(&bg, theme),
+                                        ))
+                                    },
+                                    |rect| Some(rect.extend()),
+                                );
+                            } else {
+                                cur_alac_color = Some(bg);
+                                if cur_rect.is_some() {
+                                    rects.push(cur_rect.take().unwrap());
+                                }
+                                cur_rect = Some(LayoutRect::new(
+                                    AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
+                                    1,
+                                    convert_color(&bg, theme),
+                                ));
+                            }
+                        }
+                        None => {
+                            cur_alac_color = Some(bg);
+                            cur_rect = Some(LayoutRect::new(
+                                AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
+                                1,
+                                convert_color(&bg, &theme),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            //Layout current cell text
+            {
+                if is_blank(&cell) {
+                    if let Some((start, text, style)) = ligature_run.take() {
+                        cells.push(LayoutCell::new(start, shape(text, style)));
+                    }
+                } else {
+                    let mut cell_style = TerminalElement::cell_style(
+                        &cell,
+                        fg,
+                        theme,
+                        text_style,
+                        hyperlink,
+                        bold_as_bright,
+                    );
+                    if minimum_contrast > 1. {
+                        cell_style.color = ensure_minimum_contrast(
+                            cell_style.color,
+                            convert_color(&bg, theme),
+                            minimum_contrast,
+                        );
+                    }
+
+                    let point = AlacPoint::new(line_index as i32, cell.point.column.0 as i32);
+                    let joins_run = ligatures && cell.c.is_ascii();
+                    let extends_run = joins_run
+                        && ligature_run.as_ref().map_or(false, |(start, text, style)| {
+                            start.column + text.len() as i32 == point.column && *style == cell_style
+                        });
+                    if extends_run {
+                        if let Some((_, text, _)) = ligature_run.as_mut() {
+                            text.push(cell.c);
+                        }
+                    } else {
+                        if let Some((start, text, style)) = ligature_run.take() {
+                            cells.push(LayoutCell::new(start, shape(text, style)));
+                        }
+                        if joins_run {
+                            ligature_run = Some((point, cell.c.to_string(), cell_style));
+                        } else {
+                            cells.push(LayoutCell::new(
+                                point,
+                                shape(cell.c.to_string(), cell_style),
+                            ));
+                        }
+                    }
+                };
+            }
+        }
+
+        if let Some((start, text, style)) = ligature_run.take() {
+            cells.push(LayoutCell::new(start, shape(text, style)));
+        }
+        if cur_rect.is_some() {
+            rects.push(cur_rect.take().unwrap());
+        }
+    }
+}
+
+/// The layout of each line of the terminal from earlier frames, see
+/// [`TerminalElement::layout_content`].
+#[derive(Default)]
+pub struct LineLayoutCache {
+    inputs: Option<LayoutInputs>,
+    lines: Vec<Option<LineLayout>>,
+}
+
+struct LineLayout {
+    /// The [`TerminalContent::line_versions`] entry the line was laid out for.
+    version: u64,
+    cells: Vec<LayoutCell>,
+    rects: Vec<LayoutRect>,
+}
+
+/// Everything other than the cells that [`LineLayoutCache`]'s layouts depend on.
+struct LayoutInputs {
+    text_style: TextStyle,
+    rem_size: Pixels,
+    theme: Arc<Theme>,
+    hyperlink: Option<RangeInclusive<AlacPoint>>,
+    bold_as_bright: bool,
+    minimum_contrast: f32,
+    ligatures: bool,
+}
+
+impl PartialEq for LayoutInputs {
+    fn eq(&self, other: &Self) -> bool {
+        self.text_style == other.text_style
+            && self.rem_size == other.rem_size
+            && Arc::ptr_eq(&self.theme, &other.theme)
+            && self.hyperlink == other.hyperlink
+            && self.bold_as_bright == other.bold_as_bright
+            && self.minimum_contrast == other.minimum_contrast
+            && self.ligatures == other.ligatures
+    }
+}
+
 /// The GPUI element that paints the terminal.
 /// We need to keep a reference to the view for mouse events, do we need it for any other terminal stuff, or can we move that to connection?
 pub struct TerminalElement {
@@ -154,9 +382,10 @@ pub struct TerminalElement {
     focus: FocusHandle,
     focused: bool,
     cursor_visible: bool,
-    can_navigate_to_selected_word: bool,
+    link_target: Option<SharedString>,
     interactivity: Interactivity,
     block_below_cursor: Option<Rc<BlockProperties>>,
+    line_layouts: Rc<RefCell<LineLayoutCache>>,
 }
 
 impl InteractiveElement for TerminalElement {
@@ -176,8 +405,9 @@ impl TerminalElement {
         focus: FocusHandle,
         focused: bool,
         cursor_visible: bool,
-        can_navigate_to_selected_word: bool,
+        link_target: Option<SharedString>,
         block_below_cursor: Option<Rc<BlockProperties>>,
+        line_layouts: Rc<RefCell<LineLayoutCache>>,
     ) -> TerminalElement {
         TerminalElement {
             terminal,
@@ -186,8 +416,9 @@ impl TerminalElement {
             focused,
             focus: focus.clone(),
             cursor_visible,
-            can_navigate_to_selected_word,
+            link_target,
             block_below_cursor,
+            line_layouts,
             interactivity: Default::default(),
         }
         .track_focus(&focus)
@@ -202,105 +433,76 @@ impl TerminalElement {
         // terminal_theme: &TerminalStyle,
         text_system: &WindowTextSystem,
         hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
+        theme: &Theme,
         cx: &WindowContext,
     ) -> (Vec<LayoutCell>, Vec<LayoutRect>) {
-        let theme = cx.theme();
+        let layout = GridLayout::new(text_style, text_system, hyperlink, theme, cx);
         let mut cells = vec![];
         let mut rects = vec![];
-
-        let mut cur_rect: Option<LayoutRect> = None;
-        let mut cur_alac_color = None;
-
         let linegroups = grid.into_iter().chunk_by(|i| i.point.line);
         for (line_index, (_, line)) in linegroups.into_iter().enumerate() {
-            for cell in line {
-                let mut fg = cell.fg;
-                let mut bg = cell.bg;
-                if cell.flags.contains(Flags::INVERSE) {
-                    mem::swap(&mut fg, &mut bg);
-                }
-
-                //Expand background rect range
-                {
-                    if matches!(bg, Named(NamedColor::Background)) {
-                        //Continue to next cell, resetting variables if necessary
-                        cur_alac_color = None;
-                        if let Some(rect) = cur_rect {
-                            rects.push(rect);
-                            cur_rect = None
-                        }
-                    } else {
-                        match cur_alac_color {
-                            Some(cur_color) => {
-                                if bg == cur_color {
-                                    // `cur_rect` can be None if it was moved to the `rects` vec after wrapping around
-                                    // from one line to the next. The variables are all set correctly but there is no current
-                                    // rect, so we create one if necessary.
-                                    cur_rect = cur_rect.map_or_else(
-                                        || {
-                                            Some(LayoutRect::new(
-                                                AlacPoint::new(
-                                                    line_index as i32,
-                                                    cell.point.column.0 as i32,
-                                                ),
-                                                1,
-                                                convert_color(&bg, theme),
-                                            ))
-                                        },
-                                        |rect| Some(rect.extend()),
-                                    );
-                                } else {
-                                    cur_alac_color = Some(bg);
-                                    if cur_rect.is_some() {
-                                        rects.push(cur_rect.take().unwrap());
-                                    }
-                                    cur_rect = Some(LayoutRect::new(
-                                        AlacPoint::new(
-                                            line_index as i32,
-                                            cell.point.column.0 as i32,
-                                        ),
-                                        1,
-                                        convert_color(&bg, theme),
-                                    ));
-                                }
-                            }
-                            None => {
-                                cur_alac_color = Some(bg);
-                                cur_rect = Some(LayoutRect::new(
-                                    AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
-                                    1,
-                                    convert_color(&bg, &theme),
-                                ));
-                            }
-                        }
-                    }
-                }
-
-                //Layout current cell text
-                {
-                    if !is_blank(&cell) {
-                        let cell_text = cell.c.to_string();
-                        let cell_style =
-                            TerminalElement::cell_style(&cell, fg, theme, text_style, hyperlink);
+            layout.layout_line(line_index, line, &mut cells, &mut rects);
+        }
+        (cells, rects)
+    }
 
-                        let layout_cell = text_system
-                            .shape_line(
-                                cell_text.into(),
-                                text_style.font_size.to_pixels(cx.rem_size()),
-                                &[cell_style],
-                            )
-                            .unwrap();
+    /// [`Self::layout_grid`] for the terminal's content, reusing the layout of each
+    /// line that hasn't changed since `cache` was filled, when nothing else that
+    /// the layout depends on has changed either.
+    fn layout_content(
+        content: &TerminalContent,
+        cache: &mut LineLayoutCache,
+        text_style: &TextStyle,
+        text_system: &WindowTextSystem,
+        hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
+        theme: &Arc<Theme>,
+        cx: &WindowContext,
+    ) -> (Vec<LayoutCell>, Vec<LayoutRect>) {
+        let layout = GridLayout::new(text_style, text_system, hyperlink, theme, cx);
+        let inputs = LayoutInputs {
+            text_style: text_style.clone(),
+            rem_size: cx.rem_size(),
+            theme: theme.clone(),
+            hyperlink: hyperlink.map(|(_, range)| range.clone()),
+            bold_as_bright: layout.bold_as_bright,
+            minimum_contrast: layout.minimum_contrast,
+            ligatures: layout.ligatures,
+        };
+        if cache.inputs.as_ref() != Some(&inputs) {
+            cache.inputs = Some(inputs);
+            cache.lines.clear();
+        }
+        cache
+            .lines
+            .resize_with(content.line_versions.len(), || None);
 
-                        cells.push(LayoutCell::new(
-                            AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
-                            layout_cell,
-                        ))
-                    };
-                }
+        let mut cells = vec![];
+        let mut rects = vec![];
+        let columns = content.cells.len() / content.line_versions.len().max(1);
+        let lines = content.cells.chunks(columns.max(1));
+        for (line_index, (line, version)) in lines.zip(&content.line_versions).enumerate() {
+            let cached_line = &mut cache.lines[line_index];
+            if cached_line
+                .as_ref()
+                .map_or(true, |cached_line| cached_line.version != *version)
+            {
+                let mut line_cells = vec![];
+                let mut line_rects = vec![];
+                layout.layout_line(
+                    line_index,
+                    line.iter().cloned(),
+                    &mut line_cells,
+                    &mut line_rects,
+                );
+                *cached_line = Some(LineLayout {
+                    version: *version,
+                    cells: line_cells,
+                    rects: line_rects,
+                });
             }
-
-            if cur_rect.is_some() {
-                rects.push(cur_rect.take().unwrap());
+            if let Some(cached_line) = cached_line {
+                cells.extend(cached_line.cells.iter().cloned());
+                rects.extend(cached_line.rects.iter().cloned());
             }
         }
         (cells, rects)
@@ -342,20 +544,44 @@ impl TerminalElement {
         colors: &Theme,
         text_style: &TextStyle,
         hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
+        bold_as_bright: bool,
     ) -> TextRun {
         let flags = indexed.cell.flags;
-        let mut fg = convert_color(&fg, &colors);
 
+        // Named colors have dim and bright variants in the theme, which look better
+        // than anything computed from the base color.
+        let palette_fg = match fg {
+            Named(named) if flags.intersects(Flags::DIM) => Named(named.to_dim()),
+            Named(named) if bold_as_bright && flags.intersects(Flags::BOLD) => {
+                Named(named.to_bright())
+            }
+            AnsiColor::Indexed(index @ 0..=7)
+                if bold_as_bright && flags.intersects(Flags::BOLD) =>
+            {
+                AnsiColor::Indexed(index + 8)
+            }
+            fg => fg,
+        };
+        let mut fg_color = convert_color(&palette_fg, &colors);
+
+        // Other colors are dimmed by blending them toward the background.
         // Ghostty uses (175/255) as the multiplier (~0.69), Alacritty uses 0.66, Kitty
         // uses 0.75. We're using 0.7 because it's pretty well in the middle of that.
-        if flags.intersects(Flags::DIM) {
-            fg.a *= 0.7;
+        if flags.intersects(Flags::DIM) && palette_fg == fg {
+            fg_color.a *= 0.7;
         }
+        let fg = fg_color;
 
         let underline = (flags.intersects(Flags::ALL_UNDERLINES)
             || indexed.cell.hyperlink().is_some())
         .then(|| UnderlineStyle {
-            color: Some(fg),
+            // Programs like Neovim color diagnostic undercurls with SGR 58.
+            color: Some(
+                indexed
+                    .cell
+                    .underline_color()
+                    .map_or(fg, |color| convert_color(&color, colors)),
+            ),
             thickness: Pixels::from(1.0),
             wavy: flags.contains(Flags::UNDERCURL),
         });
@@ -471,9 +697,11 @@ impl TerminalElement {
 
                 if hitbox.is_hovered(cx) {
                     terminal.update(cx, |terminal, cx| {
-                        terminal.mouse_move(&e, origin);
+                        terminal.mouse_move(&e, origin, cx);
                         cx.notify();
                     })
+                } else if terminal.read(cx).hovered_cell().is_some() {
+                    terminal.update(cx, |terminal, cx| terminal.mouse_exit(cx));
                 }
             }
         });
@@ -611,8 +839,6 @@ impl Element for TerminalElement {
                 let hitbox = hitbox.unwrap();
                 let settings = ThemeSettings::get_global(cx).clone();
 
-                let buffer_font_size = settings.buffer_font_size(cx);
-
                 let terminal_settings = TerminalSettings::get_global(cx);
 
                 let font_family = terminal_settings
@@ -636,12 +862,10 @@ impl Element for TerminalElement {
                 let font_weight = terminal_settings.font_weight.unwrap_or_default();
 
                 let line_height = terminal_settings.line_height.value();
-                let font_size = terminal_settings.font_size;
+                let max_width_columns = terminal_settings.max_width_columns;
+                let font_size = terminal_font_size(cx);
 
-                let font_size =
-                    font_size.map_or(buffer_font_size, |size| theme::adjusted_font_size(size, cx));
-
-                let theme = cx.theme().clone();
+                let theme = self.terminal.read(cx).theme(cx);
 
                 let link_style = HighlightStyle {
                     color: Some(theme.colors().link_text_hover),
@@ -677,7 +901,7 @@ impl Element for TerminalElement {
                 let text_system = cx.text_system();
                 let player_color = theme.players().local();
                 let match_color = theme.colors().search_match_background;
-                let gutter;
+                let mut gutter;
                 let dimensions = {
                     let rem_size = cx.rem_size();
                     let font_pixels = text_style.font_size.to_pixels(rem_size);
@@ -700,6 +924,14 @@ impl Element for TerminalElement {
                         size.width = cell_width * 2.0;
                     }
 
+                    if let Some(max_width_columns) = max_width_columns {
+                        let max_width = cell_width * max_width_columns.max(2) as f32;
+                        if size.width > max_width {
+                            gutter += (size.width - max_width) / 2.;
+                            size.width = max_width;
+                        }
+                    }
+
                     TerminalSize::new(line_height, cell_width, size)
                 };
 
@@ -708,11 +940,9 @@ impl Element for TerminalElement {
                 let background_color = theme.colors().terminal_background;
 
                 let last_hovered_word = self.terminal.update(cx, |terminal, cx| {
-                    terminal.set_size(dimensions);
+                    terminal.set_size(dimensions, cx);
                     terminal.sync(cx);
-                    if self.can_navigate_to_selected_word
-                        && terminal.can_navigate_to_selected_word()
-                    {
+                    if self.link_target.is_some() && terminal.can_navigate_to_selected_word() {
                         terminal.last_content.last_hovered_word.clone()
                     } else {
                         None
@@ -720,48 +950,62 @@ impl Element for TerminalElement {
                 });
 
                 let scroll_top = self.terminal_view.read(cx).scroll_top;
-                let hyperlink_tooltip = last_hovered_word.clone().map(|hovered_word| {
+                let link_tooltip = TerminalSettings::get_global(cx).link_tooltip;
+                let link_target = self
+                    .link_target
+                    .clone()
+                    .filter(|_| last_hovered_word.is_some() && link_tooltip);
+                let hyperlink_tooltip = link_target.map(|link_target| {
                     let offset = bounds.origin + point(gutter, px(0.)) - point(px(0.), scroll_top);
                     let mut element = div()
                         .size_full()
                         .id("terminal-element")
-                        .tooltip(move |cx| Tooltip::text(hovered_word.word.clone(), cx))
+                        .tooltip(move |cx| Tooltip::text(link_target.clone(), cx))
                         .into_any_element();
                     element.prepaint_as_root(offset, bounds.size.into(), cx);
                     element
                 });
 
+                let content = &self.terminal.read(cx).last_content;
                 let TerminalContent {
-                    cells,
                     mode,
-                    display_offset,
                     cursor_char,
                     selection,
                     cursor,
                     ..
-                } = &self.terminal.read(cx).last_content;
+                } = content;
                 let mode = *mode;
-                let display_offset = *display_offset;
 
-                // searches, highlights to a single range representations
+                // searches, highlights to a single range representations, on the
+                // rows of the view
+                let row_point = |point: &AlacPoint| {
+                    AlacPoint::new(AlacLine(content.line_row(point.line)), point.column)
+                };
                 let mut relative_highlighted_ranges = Vec::new();
                 for search_match in search_matches {
-                    relative_highlighted_ranges.push((search_match, match_color))
+                    relative_highlighted_ranges.push((
+                        row_point(search_match.start())..=row_point(search_match.end()),
+                        match_color,
+                    ))
                 }
                 if let Some(selection) = selection {
-                    relative_highlighted_ranges
-                        .push((selection.start..=selection.end, player_color.selection));
+                    relative_highlighted_ranges.push((
+                        row_point(&selection.start)..=row_point(&selection.end),
+                        player_color.selection,
+                    ));
                 }
 
                 // then have that representation be converted to the appropriate highlight data structure
 
-                let (cells, rects) = TerminalElement::layout_grid(
-                    cells.iter().cloned(),
+                let (cells, rects) = TerminalElement::layout_content(
+                    content,
+                    &mut self.line_layouts.borrow_mut(),
                     &text_style,
                     &cx.text_system(),
                     last_hovered_word
                         .as_ref()
                         .map(|last_hovered_word| (link_style, &last_hovered_word.word_match)),
+                    &theme,
                     cx,
                 );
 
@@ -769,8 +1013,14 @@ impl Element for TerminalElement {
                 // if we don't end up showing it.
                 let cursor = if let AlacCursorShape::Hidden = cursor.shape {
                     None
+                } else if content.is_collapsed(cursor.point.line) {
+                    None
                 } else {
-                    let cursor_point = DisplayCursor::from(cursor.point, display_offset);
+                    let cursor_point = DisplayCursor::from(cursor.point, content);
+                    let cursor_text_color = TerminalSettings::get_global(cx)
+                        .colors
+                        .cursor_text_color()
+                        .unwrap_or(theme.colors().terminal_background);
                     let cursor_text = {
                         let str_trxt = cursor_char.to_string();
                         let len = str_trxt.len();
@@ -781,7 +1031,7 @@ impl Element for TerminalElement {
                                 &[TextRun {
                                     len,
                                     font: text_style.font(),
-                                    color: theme.colors().terminal_background,
+                                    color: cursor_text_color,
                                     background_color: None,
                                     underline: Default::default(),
                                     strikethrough: None,
@@ -818,7 +1068,10 @@ impl Element for TerminalElement {
                 let block_below_cursor_element = if let Some(block) = &self.block_below_cursor {
                     let terminal = self.terminal.read(cx);
                     if terminal.last_content.display_offset == 0 {
-                        let target_line = terminal.last_content.cursor.point.line.0 + 1;
+                        let target_line = terminal
+                            .last_content
+                            .line_row(terminal.last_content.cursor.point.line)
+                            + 1;
                         let render = &block.render;
                         let mut block_cx = BlockContext {
                             context: cx,
@@ -855,7 +1108,6 @@ impl Element for TerminalElement {
                     rects,
                     relative_highlighted_ranges,
                     mode,
-                    display_offset,
                     hyperlink_tooltip,
                     gutter,
                     last_hovered_word,
@@ -889,7 +1141,7 @@ impl Element for TerminalElement {
             };
 
             self.register_mouse_listeners(origin, layout.mode, &layout.hitbox, cx);
-            if self.can_navigate_to_selected_word && layout.last_hovered_word.is_some() {
+            if self.link_target.is_some() && layout.last_hovered_word.is_some() {
                 cx.set_cursor_style(gpui::CursorStyle::PointingHand, &layout.hitbox);
             } else {
                 cx.set_cursor_style(gpui::CursorStyle::IBeam, &layout.hitbox);
@@ -949,6 +1201,15 @@ impl Element for TerminalElement {
                         }
                     }
 
+                    let inactive_dimming = TerminalSettings::get_global(cx)
+                        .inactive_dimming
+                        .clamp(0., 1.);
+                    if !self.focused && inactive_dimming > 0. {
+                        let mut veil = layout.background_color;
+                        veil.a *= inactive_dimming;
+                        cx.paint_quad(fill(bounds, veil));
+                    }
+
                     if let Some(mut element) = block_below_cursor_element {
                         element.paint(cx);
                     }
@@ -1015,9 +1276,13 @@ impl InputHandler for TerminalInputHandler {
         text: &str,
         cx: &mut WindowContext,
     ) {
-        self.terminal.update(cx, |terminal, _| {
-            terminal.input(text.into());
-        });
+        if !self.terminal.read(cx).copy_mode_active() {
+            for terminal in broadcast_targets(&self.workspace, &self.terminal, cx) {
+                terminal.update(cx, |terminal, _| terminal.input(text.into()));
+            }
+            self.terminal
+                .update(cx, |terminal, _| terminal.input(text.into()));
+        }
 
         self.workspace
             .update(cx, |this, cx| {
@@ -1049,6 +1314,24 @@ impl InputHandler for TerminalInputHandler {
     }
 }
 
+/// The configured terminal font size, falling back to the buffer font size, before
+/// the adjustment made with the terminal's font size actions.
+pub(crate) fn configured_font_size(cx: &mut WindowContext) -> Pixels {
+    let font_size = TerminalSettings::get_global(cx).font_size;
+    match font_size {
+        Some(size) => theme::adjusted_font_size(size, cx),
+        None => ThemeSettings::get_global(cx).buffer_font_size(cx),
+    }
+}
+
+/// The font size terminals are rendered with.
+pub(crate) fn terminal_font_size(cx: &mut WindowContext) -> Pixels {
+    let adjustment = cx
+        .try_global::<TerminalFontSizeAdjustment>()
+        .map_or(Pixels::ZERO, |adjustment| adjustment.0);
+    (configured_font_size(cx) + adjustment).max(MIN_FONT_SIZE)
+}
+
 pub fn is_blank(cell: &IndexedCell) -> bool {
     if cell.c != ' ' {
         return false;
@@ -1077,30 +1360,11 @@ fn to_highlighted_range_lines(
     layout: &LayoutState,
     origin: Point<Pixels>,
 ) -> Option<(Pixels, Vec<HighlightedRangeLine>)> {
-    // Step 1. Normalize the points to be viewport relative.
-    // When display_offset = 1, here's how the grid is arranged:
-    //-2,0 -2,1...
-    //--- Viewport top
-    //-1,0 -1,1...
-    //--------- Terminal Top
-    // 0,0  0,1...
-    // 1,0  1,1...
-    //--- Viewport Bottom
-    // 2,0  2,1...
-    //--------- Terminal Bottom
-
-    // Normalize to viewport relative, from terminal relative.
-    // lines are i32s, which are negative above the top left corner of the terminal
-    // If the user has scrolled, we use the display_offset to tell us which offset
-    // of the grid data we should be looking at. But for the rendering step, we don't
-    // want negatives. We want things relative to the 'viewport' (the area of the grid
-    // which is currently shown according to the display offset)
-    let unclamped_start = AlacPoint::new(
-        range.start().line + layout.display_offset,
-        range.start().column,
-    );
-    let unclamped_end =
-        AlacPoint::new(range.end().line + layout.display_offset, range.end().column);
+    // Step 1. The points are on the rows of the view already, rather than on lines
+    // of the grid: rows are negative above the viewport, and count collapsed command
+    // output once. See `TerminalContent::line_row`.
+    let unclamped_start = *range.start();
+    let unclamped_end = *range.end();
 
     // Step 2. Clamp range to viewport, and return None if it doesn't overlap
     if unclamped_end.line.0 < 0 || unclamped_start.line.0 > layout.dimensions.num_lines() as i32 {
@@ -1137,50 +1401,3 @@ fn to_highlighted_range_lines(
 
     Some((start_y, highlighted_range_lines))
 }
-
-/// Converts a 2, 8, or 24 bit color ANSI color to the GPUI equivalent.
-pub fn convert_color(fg: &terminal::alacritty_terminal::vte::ansi::Color, theme: &Theme) -> Hsla {
-    let colors = theme.colors();
-    match fg {
-        // Named and theme defined colors
-        terminal::alacritty_terminal::vte::ansi::Color::Named(n) => match n {
-            NamedColor::Black => colors.terminal_ansi_black,
-            NamedColor::Red => colors.terminal_ansi_red,
-            NamedColor::Green => colors.terminal_ansi_green,
-            NamedColor::Yellow => colors.terminal_ansi_yellow,
-            NamedColor::Blue => colors.terminal_ansi_blue,
-            NamedColor::Magenta => colors.terminal_ansi_magenta,
-            NamedColor::Cyan => colors.terminal_ansi_cyan,
-            NamedColor::White => colors.terminal_ansi_white,
-            NamedColor::BrightBlack => colors.terminal_ansi_bright_black,
-            NamedColor::BrightRed => colors.terminal_ansi_bright_red,
-            NamedColor::BrightGreen => colors.terminal_ansi_bright_green,
-            NamedColor::BrightYellow => colors.terminal_ansi_bright_yellow,
-            NamedColor::BrightBlue => colors.terminal_ansi_bright_blue,
-            NamedColor::BrightMagenta => colors.terminal_ansi_bright_magenta,
-            NamedColor::BrightCyan => colors.terminal_ansi_bright_cyan,
-            NamedColor::BrightWhite => colors.terminal_ansi_bright_white,
-            NamedColor::Foreground => colors.terminal_foreground,
-            NamedColor::Background => colors.terminal_background,
-            NamedColor::Cursor => theme.players().local().cursor,
-            NamedColor::DimBlack => colors.terminal_ansi_dim_black,
-            NamedColor::DimRed => colors.terminal_ansi_dim_red,
-            NamedColor::DimGreen => colors.terminal_ansi_dim_green,
-            NamedColor::DimYellow => colors.terminal_ansi_dim_yellow,
-            NamedColor::DimBlue => colors.terminal_ansi_dim_blue,
-            NamedColor::DimMagenta => colors.terminal_ansi_dim_magenta,
-            NamedColor::DimCyan => colors.terminal_ansi_dim_cyan,
-            NamedColor::DimWhite => colors.terminal_ansi_dim_white,
-            NamedColor::BrightForeground => colors.terminal_bright_foreground,
-            NamedColor::DimForeground => colors.terminal_dim_foreground,
-        },
-        // 'True' colors
-        terminal::alacritty_terminal::vte::ansi::Color::Spec(rgb) => {
-            terminal::rgba_color(rgb.r, rgb.g, rgb.b)
-        }
-        // 8 bit, indexed colors
-        terminal::alacritty_terminal::vte::ansi::Color::Indexed(i) => {
-            terminal::get_color_at_index(*i as usize, theme)
-        }
-    }
-}
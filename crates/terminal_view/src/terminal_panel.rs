@@ -1,13 +1,17 @@
 use std::{ops::ControlFlow, path::PathBuf, sync::Arc};
 
-use crate::{default_working_directory, TerminalView};
+use crate::{
+    default_working_directory, profile_modal::NewTerminalWithProfile, working_directory,
+    TerminalPlacement, TerminalView,
+};
 use collections::{HashMap, HashSet};
 use db::kvp::KEY_VALUE_STORE;
 use futures::future::join_all;
 use gpui::{
-    actions, Action, AnchorCorner, AnyView, AppContext, AsyncWindowContext, Entity, EventEmitter,
-    ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement, Pixels, Render,
-    Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
+    actions, Action, AnchorCorner, AnyView, AppContext, AsyncWindowContext, Entity, EntityId,
+    EventEmitter, ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement,
+    Pixels, Render, Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
 };
 use itertools::Itertools;
 use project::{terminals::TerminalKind, Fs, ProjectEntryId};
@@ -16,12 +20,12 @@ use serde::{Deserialize, Serialize};
 use settings::Settings;
 use task::{RevealStrategy, Shell, SpawnInTerminal, TaskId};
 use terminal::{
-    terminal_settings::{TerminalDockPosition, TerminalSettings},
+    terminal_settings::{TerminalDockPosition, TerminalProfile, TerminalSettings},
     Terminal,
 };
 use ui::{
-    h_flex, ButtonCommon, Clickable, ContextMenu, IconButton, IconSize, PopoverMenu, Selectable,
-    Tooltip,
+    h_flex, ButtonCommon, Clickable, Color, ContextMenu, IconButton, IconSize, PopoverMenu,
+    Selectable, Tooltip,
 };
 use util::{ResultExt, TryFutureExt};
 use workspace::{
@@ -37,13 +41,28 @@ use zed_actions::InlineAssist;
 
 const TERMINAL_PANEL_KEY: &str = "TerminalPanel";
 
-actions!(terminal_panel, [ToggleFocus]);
+actions!(
+    terminal_panel,
+    [ToggleFocus, MoveToCenter, MoveToPanel, ToggleBroadcastInput]
+);
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(
         |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
             workspace.register_action(TerminalPanel::new_terminal);
             workspace.register_action(TerminalPanel::open_terminal);
+            workspace.register_action(TerminalPanel::move_to_center);
+            workspace.register_action(TerminalPanel::move_to_panel);
+            workspace.register_action(|workspace, _: &ToggleBroadcastInput, cx| {
+                if let Some(terminal_panel) = workspace.panel::<TerminalPanel>(cx) {
+                    terminal_panel.update(cx, |terminal_panel, cx| {
+                        terminal_panel.broadcast_input = !terminal_panel.broadcast_input;
+                        cx.notify();
+                    });
+                    // Tabs of terminals outside the panel show the state too.
+                    cx.refresh();
+                }
+            });
             workspace.register_action(|workspace, _: &ToggleFocus, cx| {
                 if workspace
                     .panel::<TerminalPanel>(cx)
@@ -67,10 +86,14 @@ pub struct TerminalPanel {
     pending_serialization: Task<Option<()>>,
     pending_terminals_to_add: usize,
     _subscriptions: Vec<Subscription>,
+    /// Subscriptions to the activity of the terminals in the pane, by their view's id.
+    activity_subscriptions: HashMap<EntityId, Subscription>,
     deferred_tasks: HashMap<TaskId, Task<()>>,
     enabled: bool,
     assistant_enabled: bool,
     assistant_tab_bar_button: Option<AnyView>,
+    /// Whether input to any terminal is mirrored to all the workspace's terminals.
+    broadcast_input: bool,
 }
 
 impl TerminalPanel {
@@ -155,9 +178,11 @@ impl TerminalPanel {
             pending_terminals_to_add: 0,
             deferred_tasks: HashMap::default(),
             _subscriptions: subscriptions,
+            activity_subscriptions: HashMap::default(),
             enabled,
             assistant_enabled: false,
             assistant_tab_bar_button: None,
+            broadcast_input: false,
         };
         this.apply_tab_bar_buttons(cx);
         this
@@ -201,6 +226,10 @@ impl TerminalPanel {
                                             "New Terminal",
                                             workspace::NewTerminal.boxed_clone(),
                                         )
+                                        .action(
+                                            "New Terminal With Profile…",
+                                            NewTerminalWithProfile.boxed_clone(),
+                                        )
                                         // We want the focus to go back to terminal panel once task modal is dismissed,
                                         // hence we focus that first. Otherwise, we'd end up without a focused element, as
                                         // context menu will be gone the moment we spawn the modal.
@@ -346,22 +375,93 @@ impl TerminalPanel {
     ) {
         match event {
             pane::Event::ActivateItem { .. } => self.serialize(cx),
-            pane::Event::RemovedItem { .. } => self.serialize(cx),
+            pane::Event::RemovedItem { item_id } => {
+                self.activity_subscriptions.remove(item_id);
+                self.serialize(cx)
+            }
             pane::Event::Remove { .. } => cx.emit(PanelEvent::Close),
             pane::Event::ZoomIn => cx.emit(PanelEvent::ZoomIn),
             pane::Event::ZoomOut => cx.emit(PanelEvent::ZoomOut),
 
             pane::Event::AddItem { item } => {
+                if let Some(terminal_view) = item.downcast::<TerminalView>() {
+                    // Redraw the dock button when a terminal's badge changes.
+                    let subscription = cx.subscribe(&terminal_view, |_, _, event, cx| {
+                        if let terminal::Event::ActivityChanged(_) = event {
+                            cx.notify();
+                        }
+                    });
+                    self.activity_subscriptions
+                        .insert(terminal_view.entity_id(), subscription);
+                }
                 if let Some(workspace) = self.workspace.upgrade() {
                     let pane = self.pane.clone();
                     workspace.update(cx, |workspace, cx| item.added_to_pane(workspace, pane, cx))
                 }
+                if let Some(terminal_view) = item.downcast::<TerminalView>() {
+                    terminal_view.update(cx, |terminal_view, cx| {
+                        terminal_view.set_placement(TerminalPlacement::Docked, cx)
+                    });
+                }
             }
 
             _ => {}
         }
     }
 
+    /// Moves the panel's active terminal into the center pane, keeping its process running.
+    fn move_to_center(
+        workspace: &mut Workspace,
+        _: &MoveToCenter,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(terminal_panel) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        let panel_pane = terminal_panel.read(cx).pane.clone();
+        let Some(terminal_view) = panel_pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.downcast::<TerminalView>())
+        else {
+            return;
+        };
+        let center_pane = workspace.active_pane().clone();
+        let index = center_pane.read(cx).active_item_index() + 1;
+        workspace.move_item(
+            panel_pane,
+            center_pane,
+            terminal_view.entity_id(),
+            index,
+            cx,
+        );
+    }
+
+    /// Moves the active terminal in the center into the panel, keeping its process running.
+    fn move_to_panel(workspace: &mut Workspace, _: &MoveToPanel, cx: &mut ViewContext<Workspace>) {
+        let Some(terminal_panel) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        let center_pane = workspace.active_pane().clone();
+        let Some(terminal_view) = center_pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.downcast::<TerminalView>())
+        else {
+            return;
+        };
+        let panel_pane = terminal_panel.read(cx).pane.clone();
+        let index = panel_pane.read(cx).items_len();
+        workspace.focus_panel::<Self>(cx);
+        workspace.move_item(
+            center_pane,
+            panel_pane,
+            terminal_view.entity_id(),
+            index,
+            cx,
+        );
+    }
+
     pub fn open_terminal(
         workspace: &mut Workspace,
         action: &workspace::OpenTerminal,
@@ -389,6 +489,8 @@ impl TerminalPanel {
             Shell::System => retrieve_system_shell().map(|shell| (shell, Vec::new())),
             Shell::Program(shell) => Some((shell, Vec::new())),
             Shell::WithArguments { program, args } => Some((program, args)),
+            // Tasks run on this machine, where they were defined.
+            Shell::Remote { .. } => retrieve_system_shell().map(|shell| (shell, Vec::new())),
         }) else {
             return;
         };
@@ -473,7 +575,8 @@ impl TerminalPanel {
                 !use_new_terminal,
                 "Should have handled 'allow_concurrent_runs && use_new_terminal' case above"
             );
-            self.replace_terminal(spawn_task, existing_item_index, existing_terminal, cx);
+            self.replace_terminal(spawn_task, existing_item_index, existing_terminal, cx)
+                .detach_and_log_err(cx);
         } else {
             self.deferred_tasks.insert(
                 spawn_in_terminal.id.clone(),
@@ -486,12 +589,14 @@ impl TerminalPanel {
                                     .spawn_in_new_terminal(spawn_task, cx)
                                     .detach_and_log_err(cx);
                             } else {
-                                terminal_panel.replace_terminal(
-                                    spawn_task,
-                                    existing_item_index,
-                                    existing_terminal,
-                                    cx,
-                                );
+                                terminal_panel
+                                    .replace_terminal(
+                                        spawn_task,
+                                        existing_item_index,
+                                        existing_terminal,
+                                        cx,
+                                    )
+                                    .detach_and_log_err(cx);
                             }
                         })
                         .ok();
@@ -528,6 +633,30 @@ impl TerminalPanel {
             .detach_and_log_err(cx);
     }
 
+    /// Opens a terminal with a profile from the `terminal.profiles` setting, or with
+    /// the default shell when there is no profile.
+    pub(crate) fn new_terminal_with_profile(
+        &mut self,
+        profile: Option<TerminalProfile>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Task::ready(Err(anyhow::anyhow!("workspace was dropped")));
+        };
+        let workspace = workspace.read(cx);
+        let kind = match profile {
+            Some(profile) => {
+                let working_directory = match &profile.working_directory {
+                    Some(strategy) => working_directory(strategy, workspace, cx),
+                    None => default_working_directory(workspace, cx),
+                };
+                TerminalKind::Profile(profile, working_directory)
+            }
+            None => TerminalKind::Shell(default_working_directory(workspace, cx)),
+        };
+        self.add_terminal(kind, RevealStrategy::Always, cx)
+    }
+
     fn terminals_for_task(
         &self,
         label: &str,
@@ -572,29 +701,35 @@ impl TerminalPanel {
 
         cx.spawn(|terminal_panel, mut cx| async move {
             let pane = terminal_panel.update(&mut cx, |this, _| this.pane.clone())?;
-            let result = workspace.update(&mut cx, |workspace, cx| {
+            let terminal = workspace.update(&mut cx, |workspace, cx| {
                 let window = cx.window_handle();
-                let terminal = workspace
+                workspace
                     .project()
-                    .update(cx, |project, cx| project.create_terminal(kind, window, cx))?;
-                let terminal_view = Box::new(cx.new_view(|cx| {
-                    TerminalView::new(
-                        terminal.clone(),
-                        workspace.weak_handle(),
-                        workspace.database_id(),
-                        cx,
-                    )
-                }));
-                pane.update(cx, |pane, cx| {
-                    let focus = pane.has_focus(cx);
-                    pane.add_item(terminal_view, true, focus, None, cx);
-                });
-
-                if reveal_strategy == RevealStrategy::Always {
-                    workspace.focus_panel::<Self>(cx);
-                }
-                Ok(terminal)
+                    .update(cx, |project, cx| project.create_terminal(kind, window, cx))
             })?;
+            let result = async {
+                let terminal = terminal.await?;
+                workspace.update(&mut cx, |workspace, cx| {
+                    let terminal_view = Box::new(cx.new_view(|cx| {
+                        TerminalView::new(
+                            terminal.clone(),
+                            workspace.weak_handle(),
+                            workspace.database_id(),
+                            cx,
+                        )
+                    }));
+                    pane.update(cx, |pane, cx| {
+                        let focus = pane.has_focus(cx);
+                        pane.add_item(terminal_view, true, focus, None, cx);
+                    });
+
+                    if reveal_strategy == RevealStrategy::Always {
+                        workspace.focus_panel::<Self>(cx);
+                    }
+                    terminal
+                })
+            }
+            .await;
             terminal_panel.update(&mut cx, |this, cx| {
                 this.pending_terminals_to_add = this.pending_terminals_to_add.saturating_sub(1);
                 this.serialize(cx)
@@ -603,6 +738,53 @@ impl TerminalPanel {
         })
     }
 
+    /// Runs a shell command in an idle terminal of the panel, creating a new terminal
+    /// when every existing one is busy, and focuses the panel.
+    pub(crate) fn run_command(
+        &mut self,
+        command: String,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let idle_terminal = self
+            .pane
+            .read(cx)
+            .items()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let terminal = item.downcast::<TerminalView>()?.read(cx).model().clone();
+                Some((index, terminal))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .find(|(_, terminal)| terminal.update(cx, |terminal, _| terminal.is_idle()));
+        let terminal = if let Some((index, terminal)) = idle_terminal {
+            self.activate_terminal_view(index, cx);
+            Task::ready(Ok(terminal))
+        } else {
+            let working_directory = self
+                .workspace
+                .upgrade()
+                .and_then(|workspace| default_working_directory(workspace.read(cx), cx));
+            self.add_terminal(
+                TerminalKind::Shell(working_directory),
+                RevealStrategy::Always,
+                cx,
+            )
+        };
+
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            let terminal = terminal.await?;
+            terminal.update(&mut cx, |terminal, _| {
+                terminal.input(format!("{command}\r"));
+            })?;
+            workspace.update(&mut cx, |workspace, cx| {
+                workspace.focus_panel::<Self>(cx);
+            })?;
+            Ok(())
+        })
+    }
+
     fn serialize(&mut self, cx: &mut ViewContext<Self>) {
         let mut items_to_serialize = HashSet::default();
         let items = self
@@ -653,38 +835,41 @@ impl TerminalPanel {
         terminal_item_index: usize,
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<'_, Self>,
-    ) -> Option<()> {
-        let project = self
+    ) -> Task<Result<()>> {
+        let project = match self
             .workspace
             .update(cx, |workspace, _| workspace.project().clone())
-            .ok()?;
+        {
+            Ok(project) => project,
+            Err(error) => return Task::ready(Err(error)),
+        };
 
         let reveal = spawn_task.reveal;
         let window = cx.window_handle();
         let new_terminal = project.update(cx, |project, cx| {
-            project
-                .create_terminal(TerminalKind::Task(spawn_task), window, cx)
-                .log_err()
-        })?;
-        terminal_to_replace.update(cx, |terminal_to_replace, cx| {
-            terminal_to_replace.set_terminal(new_terminal, cx);
+            project.create_terminal(TerminalKind::Task(spawn_task), window, cx)
         });
+        let task_workspace = self.workspace.clone();
+        cx.spawn(|terminal_panel, mut cx| async move {
+            let new_terminal = new_terminal.await?;
+            terminal_to_replace.update(&mut cx, |terminal_to_replace, cx| {
+                terminal_to_replace.set_terminal(new_terminal, cx);
+            })?;
 
-        match reveal {
-            RevealStrategy::Always => {
-                self.activate_terminal_view(terminal_item_index, cx);
-                let task_workspace = self.workspace.clone();
-                cx.spawn(|_, mut cx| async move {
+            match reveal {
+                RevealStrategy::Always => {
+                    terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel.activate_terminal_view(terminal_item_index, cx);
+                    })?;
                     task_workspace
                         .update(&mut cx, |workspace, cx| workspace.focus_panel::<Self>(cx))
-                        .ok()
-                })
-                .detach();
+                        .ok();
+                }
+                RevealStrategy::Never => {}
             }
-            RevealStrategy::Never => {}
-        }
 
-        Some(())
+            Ok(())
+        })
     }
 
     fn has_no_terminals(&self, cx: &WindowContext) -> bool {
@@ -694,6 +879,10 @@ impl TerminalPanel {
     pub fn assistant_enabled(&self) -> bool {
         self.assistant_enabled
     }
+
+    pub fn broadcast_input(&self) -> bool {
+        self.broadcast_input
+    }
 }
 
 async fn wait_for_terminals_tasks(
@@ -721,9 +910,7 @@ fn add_paths_to_terminal(pane: &mut Pane, paths: &[PathBuf], cx: &mut ViewContex
         let mut new_text = paths.iter().map(|path| format!(" {path:?}")).join("");
         new_text.push(' ');
         terminal_view.update(cx, |terminal_view, cx| {
-            terminal_view.terminal().update(cx, |terminal, _| {
-                terminal.paste(&new_text);
-            });
+            terminal_view.paste_text(new_text, cx);
         });
     }
 }
@@ -834,6 +1021,26 @@ impl Panel for TerminalPanel {
         }
     }
 
+    fn icon_indicator(&self, cx: &WindowContext) -> Option<Color> {
+        let terminals = self
+            .pane
+            .read(cx)
+            .items()
+            .filter_map(|item| item.downcast::<TerminalView>())
+            .map(|terminal_view| terminal_view.read(cx).terminal().read(cx))
+            .collect::<Vec<_>>();
+        if terminals.iter().any(|terminal| terminal.has_bell()) {
+            Some(Color::Warning)
+        } else if terminals
+            .iter()
+            .any(|terminal| terminal.has_unread_output())
+        {
+            Some(Color::Info)
+        } else {
+            None
+        }
+    }
+
     fn persistent_name() -> &'static str {
         "TerminalPanel"
     }
@@ -1,36 +1,51 @@
 mod persistence;
+mod profile_modal;
+mod rename_modal;
+mod rerun_command_modal;
+mod run_command_modal;
 pub mod terminal_element;
 pub mod terminal_panel;
 
 use collections::HashSet;
-use editor::{actions::SelectAll, scroll::Autoscroll, Editor};
+use editor::{actions::SelectAll, scroll::Autoscroll, Editor, MultiBuffer};
 use futures::{stream::FuturesUnordered, StreamExt};
+use git::{parse_git_remote_url, BuildCommitPermalinkParams, GitHostingProviderRegistry};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task, View,
-    VisualContext, WeakView,
+    actions, anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent,
+    EventEmitter, FocusHandle, FocusableView, Global, Hsla, KeyBinding, KeyContext, KeyDownEvent,
+    Keystroke, Model, MouseButton, MouseDownEvent, NoAction, PathPromptOptions, Pixels,
+    PromptLevel, Render, ScrollWheelEvent, Styled, Subscription, Task, View, VisualContext,
+    WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
-use project::{search::SearchQuery, terminals::TerminalKind, Fs, Metadata, Project};
+use project::{
+    search::SearchQuery, terminals::TerminalKind, DirectoryLister, Fs, Metadata, Project,
+};
+use rename_modal::RenameTerminalModal;
+use rerun_command_modal::RerunCommandModal;
 use terminal::{
     alacritty_terminal::{
         index::Point,
+        selection::SelectionType,
         term::{search::RegexSearch, TermMode},
     },
-    terminal_settings::{TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskStatus, Terminal,
-    TerminalSize,
+    terminal_settings::{TerminalBell, TerminalBlink, TerminalSettings, WorkingDirectory},
+    Cast, Clear, ClearScreen, ClearScrollback, CommitTarget, Copy, CopyModeMove,
+    CopyModeToggleLineSelection, CopyModeToggleSelection, CopyModeYank, CopyWithFormatting, Event,
+    ExpandAllCommandOutput, MaybeNavigationTarget, Paste, ReplayProgress, ScrollLineDown,
+    ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToNextCommand,
+    ScrollToNextPrompt, ScrollToPreviousCommand, ScrollToPreviousPrompt, ScrollToTop,
+    SelectLastCommandOutput, ShowCharacterPalette, TaskStatus, Terminal, TerminalBuilder,
+    TerminalSize, ToggleCommandOutput, ToggleCopyMode,
 };
-use terminal_element::{is_blank, TerminalElement};
+use terminal_element::{is_blank, LineLayoutCache, TerminalElement};
 use terminal_panel::TerminalPanel;
-use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label, Tooltip};
-use util::{paths::PathWithPosition, ResultExt};
+use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Indicator, Label, Tooltip};
+use util::{paths::PathWithPosition, truncate_and_trailoff, ResultExt};
 use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, SerializableItem, TabContentParams},
-    notifications::NotifyResultExt,
+    notifications::NotifyTaskExt,
     register_serializable_item,
     searchable::{SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
     CloseActiveItem, NewCenterTerminal, NewTerminal, OpenVisible, Pane, ToolbarItemLocation,
@@ -44,7 +59,8 @@ use smol::Timer;
 use zed_actions::InlineAssist;
 
 use std::{
-    cmp,
+    cell::RefCell,
+    cmp, iter,
     ops::RangeInclusive,
     path::{Path, PathBuf},
     rc::Rc,
@@ -57,6 +73,14 @@ const REGEX_SPECIAL_CHARS: &[char] = &[
 ];
 
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+const VISUAL_BELL_DURATION: Duration = Duration::from_millis(150);
+const SCROLLBACK_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_PERSISTED_SCROLLBACK_LINES: usize = 1000;
+const MAX_TAB_TITLE_LEN: usize = 25;
+const MIN_FONT_SIZE: Pixels = px(6.0);
+const PASTE_PREVIEW_LINES: usize = 5;
+const PASTE_PREVIEW_LINE_LEN: usize = 80;
+const REPLAY_SEEK_STEP: Duration = Duration::from_secs(5);
 
 ///Event to transmit the scroll from the element to the view
 #[derive(Clone, Debug, PartialEq)]
@@ -68,20 +92,110 @@ pub struct SendText(String);
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct SendKeystroke(String);
 
-impl_actions!(terminal, [SendText, SendKeystroke]);
+/// Sends text to the terminal after replacing the escapes `\e`, `\n`, `\r`, `\t`, `\a`,
+/// `\b`, `\0`, `\\` and `\xHH` in it, which are easier to write in a keymap than the
+/// characters themselves.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SendEscapedText(String);
+
+impl_actions!(terminal, [SendText, SendKeystroke, SendEscapedText]);
+
+actions!(
+    terminal,
+    [
+        RenameTerminal,
+        RerunCommand,
+        CopyScrollbackToBuffer,
+        InspectTerminal,
+        StartRecording,
+        StopRecording,
+        OpenRecording,
+        ToggleReplayPlayback,
+        ReplaySeekBackward,
+        ReplaySeekForward,
+        IncreaseFontSize,
+        DecreaseFontSize,
+        ResetFontSize,
+        TogglePassThrough,
+        ToggleReadOnly,
+    ]
+);
 
 pub fn init(cx: &mut AppContext) {
     terminal_panel::init(cx);
+    run_command_modal::init(cx);
+    profile_modal::init(cx);
     terminal::init(cx);
 
     register_serializable_item::<TerminalView>(cx);
 
     cx.observe_new_views(|workspace: &mut Workspace, _| {
         workspace.register_action(TerminalView::deploy);
+        workspace.register_action(TerminalView::open_recording);
     })
     .detach();
 }
 
+/// Lets terminals that pass keystrokes through to a full-screen program have the
+/// `ctrl` and `alt` keystrokes that Zed binds outside of terminals. Each gets a
+/// `NoAction` binding in the `Terminal && pass_through` context, which takes
+/// precedence over the shallower workspace and pane contexts, so the keystroke
+/// reaches the terminal. Keystrokes bound to terminal actions, like
+/// `terminal::TogglePassThrough`, are left alone. Call it after loading keymaps.
+pub fn bind_pass_through_keys(cx: &mut AppContext) {
+    let keymap = cx.key_bindings();
+    let keymap = keymap.borrow();
+    let mut terminal_keystrokes = HashSet::default();
+    let mut other_keystrokes = Vec::new();
+    for binding in keymap.bindings() {
+        let [keystroke] = binding.keystrokes() else {
+            continue;
+        };
+        let modifiers = keystroke.modifiers;
+        if modifiers.platform || !(modifiers.control || modifiers.alt) {
+            continue;
+        }
+        if binding.action().name().starts_with("terminal::") {
+            terminal_keystrokes.insert(keystroke.clone());
+        } else if !other_keystrokes.contains(keystroke) {
+            other_keystrokes.push(keystroke.clone());
+        }
+    }
+    drop(keymap);
+
+    let bindings = other_keystrokes
+        .into_iter()
+        .filter(|keystroke| !terminal_keystrokes.contains(keystroke))
+        .map(|keystroke| {
+            KeyBinding::new(
+                &keystroke_source(&keystroke),
+                NoAction,
+                Some("Terminal && pass_through"),
+            )
+        })
+        .collect::<Vec<_>>();
+    cx.bind_keys(bindings);
+}
+
+/// The keymap syntax for a keystroke, such as `ctrl-shift-p`.
+fn keystroke_source(keystroke: &Keystroke) -> String {
+    let mut source = String::new();
+    if keystroke.modifiers.control {
+        source.push_str("ctrl-");
+    }
+    if keystroke.modifiers.alt {
+        source.push_str("alt-");
+    }
+    if keystroke.modifiers.shift {
+        source.push_str("shift-");
+    }
+    if keystroke.modifiers.function {
+        source.push_str("fn-");
+    }
+    source.push_str(&keystroke.key);
+    source
+}
+
 pub struct BlockProperties {
     pub height: u8,
     pub render: Box<dyn Send + Fn(&mut BlockContext) -> AnyElement>,
@@ -92,23 +206,53 @@ pub struct BlockContext<'a, 'b> {
     pub dimensions: TerminalSize,
 }
 
+/// How far the terminal font size actions have moved every terminal's font size away
+/// from the configured one.
+struct TerminalFontSizeAdjustment(Pixels);
+
+impl Global for TerminalFontSizeAdjustment {}
+
+/// Where a terminal view is shown, which picks the key context it adds next to `Terminal`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerminalPlacement {
+    /// In a center pane.
+    #[default]
+    Center,
+    /// In the terminal panel: `DockedTerminal`.
+    Docked,
+}
+
 ///A terminal view, maintains the PTY's file handles and communicates with the terminal
 pub struct TerminalView {
     terminal: Model<Terminal>,
     workspace: WeakView<Workspace>,
     focus_handle: FocusHandle,
-    //Currently using iTerm bell, show bell emoji in tab until input is received
-    has_bell: bool,
+    bell_flash: Option<Task<()>>,
     context_menu: Option<(View<ContextMenu>, gpui::Point<Pixels>, Subscription)>,
     blink_state: bool,
     blinking_on: bool,
     blinking_paused: bool,
     blink_epoch: usize,
-    can_navigate_to_selected_word: bool,
+    /// Where the hovered link goes, when it goes anywhere: its URL, or the absolute
+    /// path of the file it opens.
+    link_target: Option<SharedString>,
+    /// Looks up the hovered link's target when that takes a while, like a commit's.
+    resolve_link_target: Task<()>,
     workspace_id: Option<WorkspaceId>,
+    placement: TerminalPlacement,
     show_title: bool,
+    custom_title: Option<String>,
+    /// The title the terminal last reported for the running process.
+    terminal_title: String,
+    /// Whether the foreground program is one of the `pass_through_programs`.
+    pass_through_program_running: bool,
+    pass_through_suspended: bool,
     block_below_cursor: Option<Rc<BlockProperties>>,
+    /// The layout of each line as of the last frame, for the lines that haven't changed since.
+    line_layouts: Rc<RefCell<LineLayoutCache>>,
     scroll_top: Pixels,
+    scrollback_changed: bool,
+    _scrollback_checkpoint: Task<()>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -133,24 +277,25 @@ impl TerminalView {
         let working_directory = default_working_directory(workspace, cx);
 
         let window = cx.window_handle();
-        let terminal = workspace
-            .project()
-            .update(cx, |project, cx| {
-                project.create_terminal(TerminalKind::Shell(working_directory), window, cx)
+        let terminal = workspace.project().update(cx, |project, cx| {
+            project.create_terminal(TerminalKind::Shell(working_directory), window, cx)
+        });
+
+        cx.spawn(|workspace, mut cx| async move {
+            let terminal = terminal.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let view = cx.new_view(|cx| {
+                    TerminalView::new(
+                        terminal,
+                        workspace.weak_handle(),
+                        workspace.database_id(),
+                        cx,
+                    )
+                });
+                workspace.add_item_to_active_pane(Box::new(view), None, true, cx);
             })
-            .notify_err(workspace, cx);
-
-        if let Some(terminal) = terminal {
-            let view = cx.new_view(|cx| {
-                TerminalView::new(
-                    terminal,
-                    workspace.weak_handle(),
-                    workspace.database_id(),
-                    cx,
-                )
-            });
-            workspace.add_item_to_active_pane(Box::new(view), None, true, cx);
-        }
+        })
+        .detach_and_notify_err(cx);
     }
 
     pub fn new(
@@ -171,24 +316,40 @@ impl TerminalView {
         });
 
         Self {
+            terminal_title: terminal.read(cx).title(false),
             terminal,
             workspace: workspace_handle,
-            has_bell: false,
+            bell_flash: None,
             focus_handle,
             context_menu: None,
             blink_state: true,
             blinking_on: false,
             blinking_paused: false,
             blink_epoch: 0,
-            can_navigate_to_selected_word: false,
+            link_target: None,
+            resolve_link_target: Task::ready(()),
             workspace_id,
+            placement: TerminalPlacement::default(),
             show_title: TerminalSettings::get_global(cx).toolbar.title,
+            custom_title: None,
+            pass_through_program_running: false,
+            pass_through_suspended: false,
             block_below_cursor: None,
+            line_layouts: Default::default(),
             scroll_top: Pixels::ZERO,
+            scrollback_changed: false,
+            _scrollback_checkpoint: Self::checkpoint_scrollback(cx),
             _subscriptions: vec![
                 focus_in,
                 focus_out,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
+                // `light_colors` and `dark_colors` follow the system appearance.
+                cx.observe_window_appearance(|_, cx| cx.notify()),
+                cx.observe_window_activation(|this, cx| {
+                    let active = cx.is_window_active();
+                    this.terminal
+                        .update(cx, |terminal, _| terminal.set_window_active(active));
+                }),
             ],
             _terminal_subscriptions: terminal_subscriptions,
         }
@@ -198,13 +359,326 @@ impl TerminalView {
         &self.terminal
     }
 
-    pub fn has_bell(&self) -> bool {
-        self.has_bell
+    /// The title shown in this terminal's tab: the one set by the user, if any,
+    /// or the one derived from the running process.
+    pub fn title(&self, truncate: bool) -> String {
+        match &self.custom_title {
+            Some(title) if truncate => truncate_and_trailoff(title, MAX_TAB_TITLE_LEN),
+            Some(title) => title.clone(),
+            // The process title combines several parts, such as the directory and
+            // the command, so it gets more room than one the user picked.
+            None if truncate => truncate_and_trailoff(&self.terminal_title, MAX_TAB_TITLE_LEN * 2),
+            None => self.terminal_title.clone(),
+        }
+    }
+
+    pub fn set_custom_title(&mut self, title: Option<String>, cx: &mut ViewContext<Self>) {
+        self.custom_title = title;
+        cx.emit(ItemEvent::UpdateTab);
+        cx.notify();
+    }
+
+    fn rename(&mut self, _: &RenameTerminal, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let terminal_view = cx.view().downgrade();
+        let current_title = self.title(false);
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(cx, |cx| {
+                RenameTerminalModal::new(terminal_view, current_title, cx)
+            });
+        });
+    }
+
+    /// Offers the commands run in this terminal, most recent first, followed by the
+    /// ones run in the project's other terminals, to run one here again.
+    fn rerun_command(&mut self, _: &RerunCommand, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let other_terminals = workspace
+            .read(cx)
+            .project()
+            .read(cx)
+            .local_terminal_handles()
+            .clone();
+        let mut seen = HashSet::default();
+        let mut commands = Vec::new();
+        for terminal in iter::once(self.terminal.downgrade()).chain(other_terminals) {
+            let Some(terminal) = terminal.upgrade() else {
+                continue;
+            };
+            for command in terminal.read(cx).command_history().iter().rev() {
+                if seen.insert(command.clone()) {
+                    commands.push(command.clone());
+                }
+            }
+        }
+
+        let terminal_view = cx.view().downgrade();
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(cx, |cx| RerunCommandModal::new(terminal_view, commands, cx));
+        });
+    }
+
+    /// Opens everything the terminal has printed, scrollback included, in a read-only
+    /// editor so that it can be searched and navigated like any other buffer.
+    fn copy_scrollback_to_buffer(
+        &mut self,
+        _: &CopyScrollbackToBuffer,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let terminal = self.terminal.read(cx);
+        let text = terminal.scrollback_text(terminal.total_lines());
+        let title = format!("{} Output", self.title(false));
+        self.open_in_buffer(text, title, cx);
+    }
+
+    /// Opens a read-only buffer describing the program the terminal runs and the
+    /// state it put the terminal in.
+    fn inspect_terminal(&mut self, _: &InspectTerminal, cx: &mut ViewContext<Self>) {
+        let text = self
+            .terminal
+            .update(cx, |terminal, _| terminal.inspect())
+            .report();
+        let title = format!("{} Info", self.title(false));
+        self.open_in_buffer(text, title, cx);
+    }
+
+    fn open_in_buffer(&self, text: String, title: String, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        workspace.update(cx, |workspace, cx| {
+            let project = workspace.project().clone();
+            if project.read(cx).is_via_collab() {
+                return;
+            }
+            let buffer = project.update(cx, |project, cx| {
+                project.create_local_buffer(&text, None, cx)
+            });
+            let buffer =
+                cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(title.clone()));
+            let editor = cx.new_view(|cx| {
+                let mut editor = Editor::for_multibuffer(buffer, Some(project), true, cx);
+                editor.set_read_only(true);
+                editor.set_breadcrumb_header(title);
+                editor
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+        });
+    }
+
+    fn start_recording(&mut self, _: &StartRecording, cx: &mut ViewContext<Self>) {
+        let directory = self
+            .terminal
+            .read(cx)
+            .get_cwd()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory);
+        cx.spawn(|this, mut cx| async move {
+            let Some(path) = path.await?? else {
+                return Ok(());
+            };
+            this.update(&mut cx, |this, cx| {
+                this.terminal
+                    .update(cx, |terminal, cx| terminal.start_recording(path, cx))
+            })?
+        })
+        .detach_and_notify_err(cx);
+    }
+
+    fn stop_recording(&mut self, _: &StopRecording, cx: &mut ViewContext<Self>) {
+        let Some(finished) = self
+            .terminal
+            .update(cx, |terminal, cx| terminal.stop_recording(cx))
+        else {
+            return;
+        };
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            if let Err(error) = finished.await {
+                workspace.update(&mut cx, |workspace, cx| workspace.show_error(&error, cx))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Opens an asciicast recording in a terminal that plays it back.
+    fn open_recording(
+        workspace: &mut Workspace,
+        _: &OpenRecording,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let fs = workspace.app_state().fs.clone();
+        let paths = workspace.prompt_for_open_path(
+            PathPromptOptions {
+                files: true,
+                directories: false,
+                multiple: false,
+            },
+            DirectoryLister::Local(fs.clone()),
+            cx,
+        );
+        cx.spawn(|workspace, mut cx| async move {
+            let Some(path) = paths.await?.and_then(|mut paths| paths.pop()) else {
+                return Ok(());
+            };
+            let text = fs
+                .load(&path)
+                .await
+                .with_context(|| format!("reading {path:?}"))?;
+            let cast = Cast::parse(&text)?;
+            let title = cast.title.clone().unwrap_or_else(|| {
+                path.file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+            });
+
+            workspace.update(&mut cx, |workspace, cx| {
+                let terminal =
+                    cx.new_model(|cx| TerminalBuilder::new_replay(cast, title, cx).subscribe(cx));
+                terminal.update(cx, |terminal, cx| terminal.toggle_replay_playback(cx));
+                let view = cx
+                    .new_view(|cx| TerminalView::new(terminal, workspace.weak_handle(), None, cx));
+                workspace.add_item_to_active_pane(Box::new(view), None, true, cx);
+            })
+        })
+        .detach_and_notify_err(cx);
+    }
+
+    fn toggle_replay_playback(&mut self, _: &ToggleReplayPlayback, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |terminal, cx| terminal.toggle_replay_playback(cx));
+    }
+
+    fn replay_seek_backward(&mut self, _: &ReplaySeekBackward, cx: &mut ViewContext<Self>) {
+        self.seek_replay_by(|position| position.saturating_sub(REPLAY_SEEK_STEP), cx);
+    }
+
+    fn replay_seek_forward(&mut self, _: &ReplaySeekForward, cx: &mut ViewContext<Self>) {
+        self.seek_replay_by(|position| position + REPLAY_SEEK_STEP, cx);
+    }
+
+    fn seek_replay_by(
+        &mut self,
+        seek: impl FnOnce(Duration) -> Duration,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal.update(cx, |terminal, cx| {
+            if let Some(progress) = terminal.replay_progress() {
+                terminal.seek_replay(seek(progress.position), cx);
+            }
+        });
+    }
+
+    fn render_replay_controls(
+        &self,
+        progress: ReplayProgress,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        h_flex()
+            .absolute()
+            .bottom_2()
+            .right_4()
+            .gap_1()
+            .px_1()
+            .elevation_2(cx)
+            .child(
+                IconButton::new("replay-seek-backward", IconName::ChevronLeft)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::for_action("Back 5 Seconds", &ReplaySeekBackward, cx))
+                    .on_click(cx.listener(|this, _, cx| {
+                        this.replay_seek_backward(&ReplaySeekBackward, cx)
+                    })),
+            )
+            .child(
+                IconButton::new(
+                    "replay-toggle-playback",
+                    if progress.playing {
+                        IconName::Stop
+                    } else {
+                        IconName::Play
+                    },
+                )
+                .icon_size(IconSize::Small)
+                .tooltip(move |cx| {
+                    let label = if progress.playing { "Pause" } else { "Play" };
+                    Tooltip::for_action(label, &ToggleReplayPlayback, cx)
+                })
+                .on_click(
+                    cx.listener(|this, _, cx| {
+                        this.toggle_replay_playback(&ToggleReplayPlayback, cx)
+                    }),
+                ),
+            )
+            .child(
+                IconButton::new("replay-seek-forward", IconName::ChevronRight)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::for_action("Forward 5 Seconds", &ReplaySeekForward, cx))
+                    .on_click(
+                        cx.listener(|this, _, cx| this.replay_seek_forward(&ReplaySeekForward, cx)),
+                    ),
+            )
+            .child(
+                Label::new(format!(
+                    "{} / {}",
+                    format_replay_time(progress.position),
+                    format_replay_time(progress.duration)
+                ))
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+            )
+    }
+
+    fn increase_font_size(&mut self, _: &IncreaseFontSize, cx: &mut ViewContext<Self>) {
+        Self::adjust_font_size(px(1.), cx);
+    }
+
+    fn decrease_font_size(&mut self, _: &DecreaseFontSize, cx: &mut ViewContext<Self>) {
+        Self::adjust_font_size(px(-1.), cx);
+    }
+
+    fn reset_font_size(&mut self, _: &ResetFontSize, cx: &mut ViewContext<Self>) {
+        if cx.has_global::<TerminalFontSizeAdjustment>() {
+            cx.remove_global::<TerminalFontSizeAdjustment>();
+            cx.refresh();
+        }
+    }
+
+    /// Changing the font size changes the cell size, so the next layout also
+    /// resizes the PTY to the new number of rows and columns.
+    fn adjust_font_size(delta: Pixels, cx: &mut ViewContext<Self>) {
+        let configured_size = terminal_element::configured_font_size(cx);
+        let adjustment = cx
+            .try_global::<TerminalFontSizeAdjustment>()
+            .map_or(Pixels::ZERO, |adjustment| adjustment.0);
+        let adjustment = (adjustment + delta).max(MIN_FONT_SIZE - configured_size);
+        cx.set_global(TerminalFontSizeAdjustment(adjustment));
+        cx.refresh();
+    }
+
+    pub fn has_bell(&self, cx: &AppContext) -> bool {
+        self.terminal.read(cx).has_bell()
+    }
+
+    fn flash_bell(&mut self, cx: &mut ViewContext<Self>) {
+        self.bell_flash = Some(cx.spawn(|this, mut cx| async move {
+            Timer::after(VISUAL_BELL_DURATION).await;
+            this.update(&mut cx, |this, cx| {
+                this.bell_flash = None;
+                cx.notify();
+            })
+            .ok();
+        }));
+        cx.notify();
     }
 
     pub fn clear_bell(&mut self, cx: &mut ViewContext<TerminalView>) {
-        self.has_bell = false;
-        cx.emit(Event::Wakeup);
+        self.terminal
+            .update(cx, |terminal, cx| terminal.clear_bell(cx));
     }
 
     pub fn deploy_context_menu(
@@ -219,14 +693,64 @@ impl TerminalView {
             .map_or(false, |terminal_panel| {
                 terminal_panel.read(cx).assistant_enabled()
             });
+        let navigation_target = self
+            .terminal
+            .update(cx, |terminal, _| terminal.navigation_target_under_mouse());
+        let recording = self.terminal.read(cx).recording_path().is_some();
+        let read_only = self.terminal.read(cx).read_only();
+        let is_docked = self.placement == TerminalPlacement::Docked;
+        let terminal = self.terminal.downgrade();
         let context_menu = ContextMenu::build(cx, |menu, _| {
             menu.context(self.focus_handle.clone())
+                .when_some(navigation_target, |menu, target| {
+                    let label = match &target {
+                        MaybeNavigationTarget::Url(_) => "Open Link",
+                        MaybeNavigationTarget::PathLike(_) => "Open File",
+                        MaybeNavigationTarget::Commit(_) => "Open Commit",
+                    };
+                    menu.entry(label, None, move |cx| {
+                        terminal
+                            .update(cx, |terminal, cx| {
+                                terminal.open_navigation_target(target.clone(), cx)
+                            })
+                            .ok();
+                    })
+                    .separator()
+                })
                 .action("New Terminal", Box::new(NewTerminal))
+                .action("Rename Terminal", Box::new(RenameTerminal))
+                .map(|menu| {
+                    if is_docked {
+                        menu.action("Move to Center", Box::new(terminal_panel::MoveToCenter))
+                    } else {
+                        menu.action("Move to Panel", Box::new(terminal_panel::MoveToPanel))
+                    }
+                })
                 .separator()
                 .action("Copy", Box::new(Copy))
+                .action("Copy HTML Source", Box::new(CopyWithFormatting::default()))
                 .action("Paste", Box::new(Paste))
                 .action("Select All", Box::new(SelectAll))
+                .action("Find", Box::new(search::buffer_search::Deploy::find()))
                 .action("Clear", Box::new(Clear))
+                .action("Rerun Command…", Box::new(RerunCommand))
+                .action(
+                    if read_only {
+                        "Unlock Input"
+                    } else {
+                        "Lock Input"
+                    },
+                    Box::new(ToggleReadOnly),
+                )
+                .action("Open Output in Buffer", Box::new(CopyScrollbackToBuffer))
+                .action("Inspect Terminal", Box::new(InspectTerminal))
+                .map(|menu| {
+                    if recording {
+                        menu.action("Stop Recording", Box::new(StopRecording))
+                    } else {
+                        menu.action("Start Recording…", Box::new(StartRecording))
+                    }
+                })
                 .when(assistant_enabled, |menu| {
                     menu.separator()
                         .action("Inline Assist", Box::new(InlineAssist::default()))
@@ -250,9 +774,88 @@ impl TerminalView {
         self.context_menu = Some((context_menu, position, subscription));
     }
 
+    /// Periodically saves recent output to the database, so that it can be shown
+    /// again if the app exits without serializing the workspace.
+    fn checkpoint_scrollback(cx: &mut ViewContext<Self>) -> Task<()> {
+        cx.spawn(|this, mut cx| async move {
+            loop {
+                Timer::after(SCROLLBACK_CHECKPOINT_INTERVAL).await;
+                match this.update(&mut cx, |this, cx| this.save_scrollback(cx)) {
+                    Ok(Some(save)) => {
+                        save.await.log_err();
+                    }
+                    Ok(None) => {}
+                    Err(_) => break,
+                }
+            }
+        })
+    }
+
+    fn save_scrollback(&mut self, cx: &mut ViewContext<Self>) -> Option<Task<gpui::Result<()>>> {
+        if !self.scrollback_changed {
+            return None;
+        }
+        let workspace_id = self.workspace_id?;
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() || terminal.replay_progress().is_some() {
+            return None;
+        }
+
+        self.scrollback_changed = false;
+        let scrollback = terminal.scrollback_text(MAX_PERSISTED_SCROLLBACK_LINES);
+        let item_id = cx.entity_id().as_u64();
+        Some(cx.background_executor().spawn(async move {
+            TERMINAL_DB
+                .save_scrollback(item_id, workspace_id, scrollback)
+                .await
+        }))
+    }
+
     fn settings_changed(&mut self, cx: &mut ViewContext<Self>) {
         let settings = TerminalSettings::get_global(cx);
         self.show_title = settings.toolbar.title;
+        self.terminal_title = self.terminal.update(cx, |terminal, cx| {
+            terminal.apply_settings(TerminalSettings::get_global(cx));
+            terminal.title(false)
+        });
+        self.update_pass_through_program(cx);
+        cx.notify();
+    }
+
+    fn update_pass_through_program(&mut self, cx: &mut ViewContext<Self>) {
+        let process_name = self
+            .terminal
+            .update(cx, |terminal, _| terminal.foreground_process_name());
+        self.pass_through_program_running = process_name.map_or(false, |name| {
+            TerminalSettings::get_global(cx)
+                .pass_through_programs
+                .contains(&name)
+        });
+    }
+
+    /// Whether keystrokes that Zed binds should go to the full-screen program running
+    /// in the terminal instead.
+    pub fn is_passing_through(&self, cx: &AppContext) -> bool {
+        !self.pass_through_suspended
+            && (self.pass_through_program_running
+                || self
+                    .terminal
+                    .read(cx)
+                    .last_content
+                    .mode
+                    .contains(TermMode::ALT_SCREEN))
+    }
+
+    fn toggle_pass_through(&mut self, _: &TogglePassThrough, cx: &mut ViewContext<Self>) {
+        self.pass_through_suspended = !self.pass_through_suspended;
+        cx.notify();
+    }
+
+    fn toggle_read_only(&mut self, _: &ToggleReadOnly, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |terminal, _| {
+            terminal.set_read_only(!terminal.read_only());
+        });
+        cx.emit(ItemEvent::UpdateTab);
         cx.notify();
     }
 
@@ -286,6 +889,17 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn clear_screen(&mut self, _: &ClearScreen, cx: &mut ViewContext<Self>) {
+        self.scroll_top = px(0.);
+        self.terminal.update(cx, |term, _| term.clear_screen());
+        cx.notify();
+    }
+
+    fn clear_scrollback(&mut self, _: &ClearScrollback, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.clear_scrollback());
+        cx.notify();
+    }
+
     fn max_scroll_top(&self, cx: &AppContext) -> Pixels {
         let terminal = self.terminal.read(cx);
 
@@ -342,7 +956,26 @@ impl TerminalView {
             .update(cx, |term, _| term.scroll_wheel(event, origin));
     }
 
+    /// The alternate screen has no scrollback, so programs using it (pagers, editors)
+    /// get the scrolling keys instead: propagating the action lets `key_down` send
+    /// the keystroke to the PTY.
+    fn forward_scroll_to_application(&self, cx: &mut ViewContext<Self>) -> bool {
+        let alt_screen = self
+            .terminal
+            .read(cx)
+            .last_content
+            .mode
+            .contains(TermMode::ALT_SCREEN);
+        if alt_screen {
+            cx.propagate();
+        }
+        alt_screen
+    }
+
     fn scroll_line_up(&mut self, _: &ScrollLineUp, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
         let terminal_content = self.terminal.read(cx).last_content();
         if self.block_below_cursor.is_some()
             && terminal_content.display_offset == 0
@@ -358,6 +991,9 @@ impl TerminalView {
     }
 
     fn scroll_line_down(&mut self, _: &ScrollLineDown, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
         let terminal_content = self.terminal.read(cx).last_content();
         if self.block_below_cursor.is_some() && terminal_content.display_offset == 0 {
             let max_scroll_top = self.max_scroll_top(cx);
@@ -373,6 +1009,9 @@ impl TerminalView {
     }
 
     fn scroll_page_up(&mut self, _: &ScrollPageUp, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
         if self.scroll_top == Pixels::ZERO {
             self.terminal.update(cx, |term, _| term.scroll_page_up());
         } else {
@@ -393,6 +1032,9 @@ impl TerminalView {
     }
 
     fn scroll_page_down(&mut self, _: &ScrollPageDown, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
         self.terminal.update(cx, |term, _| term.scroll_page_down());
         let terminal = self.terminal.read(cx);
         if terminal.last_content().display_offset < terminal.viewport_lines() {
@@ -402,11 +1044,91 @@ impl TerminalView {
     }
 
     fn scroll_to_top(&mut self, _: &ScrollToTop, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
         self.terminal.update(cx, |term, _| term.scroll_to_top());
         cx.notify();
     }
 
+    fn scroll_to_previous_command(
+        &mut self,
+        _: &ScrollToPreviousCommand,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_command());
+        cx.notify();
+    }
+
+    fn scroll_to_next_command(&mut self, _: &ScrollToNextCommand, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_command());
+        cx.notify();
+    }
+
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn select_last_command_output(
+        &mut self,
+        _: &SelectLastCommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.select_last_command_output());
+        cx.notify();
+    }
+
+    fn toggle_command_output(&mut self, _: &ToggleCommandOutput, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.toggle_command_output());
+        cx.notify();
+    }
+
+    fn expand_all_command_output(
+        &mut self,
+        _: &ExpandAllCommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.expand_all_command_output());
+        cx.notify();
+    }
+
     fn scroll_to_bottom(&mut self, _: &ScrollToBottom, cx: &mut ViewContext<Self>) {
+        if self.forward_scroll_to_application(cx) {
+            return;
+        }
+        self.scroll_to_end(cx);
+    }
+
+    fn scroll_to_end(&mut self, cx: &mut ViewContext<Self>) {
         self.terminal.update(cx, |term, _| term.scroll_to_bottom());
         if self.block_below_cursor.is_some() {
             self.scroll_top = self.max_scroll_top(cx);
@@ -471,7 +1193,7 @@ impl TerminalView {
 
     pub fn set_block_below_cursor(&mut self, block: BlockProperties, cx: &mut ViewContext<Self>) {
         self.block_below_cursor = Some(Rc::new(block));
-        self.scroll_to_bottom(&ScrollToBottom, cx);
+        self.scroll_to_end(cx);
         cx.notify();
     }
 
@@ -499,33 +1221,180 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn copy_with_formatting(&mut self, action: &CopyWithFormatting, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_with_formatting(action.format));
+        cx.notify();
+    }
+
     ///Attempt to paste the clipboard into the terminal
     fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
         if let Some(clipboard_string) = cx.read_from_clipboard().and_then(|item| item.text()) {
-            self.terminal
-                .update(cx, |terminal, _cx| terminal.paste(&clipboard_string));
+            self.paste_text(clipboard_string, cx);
         }
     }
 
-    fn send_text(&mut self, text: &SendText, cx: &mut ViewContext<Self>) {
-        self.clear_bell(cx);
+    /// Pastes text into the terminal, first asking for confirmation when the text
+    /// contains any of the `paste_confirmation_characters`. Every way of pasting,
+    /// whether from the clipboard, the primary selection or a drop, goes through here.
+    pub(crate) fn paste_text(&mut self, clipboard_string: String, cx: &mut ViewContext<Self>) {
+        // With bracketed paste the shell receives the text as a whole instead of
+        // running it line by line, so there is nothing to warn about.
+        let confirmation_characters =
+            &TerminalSettings::get_global(cx).paste_confirmation_characters;
+        let bracketed = self
+            .terminal
+            .read(cx)
+            .last_content()
+            .mode
+            .contains(TermMode::BRACKETED_PASTE);
+        if bracketed || !clipboard_string.contains(|c| confirmation_characters.contains(c)) {
+            self.paste_confirmed(&clipboard_string, cx);
+            return;
+        }
+
+        let line_count = clipboard_string.lines().count();
+        let mut preview = clipboard_string
+            .lines()
+            .take(PASTE_PREVIEW_LINES)
+            .map(|line| truncate_and_trailoff(line, PASTE_PREVIEW_LINE_LEN))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if line_count > PASTE_PREVIEW_LINES {
+            preview.push_str("\n…");
+        }
+        let answer = cx.prompt(
+            PromptLevel::Warning,
+            "The pasted text may run as commands. Paste anyway?",
+            Some(&preview),
+            &["Paste", "Cancel"],
+        );
+        cx.spawn(|this, mut cx| async move {
+            if let Ok(0) = answer.await {
+                this.update(&mut cx, |this, cx| {
+                    this.paste_confirmed(&clipboard_string, cx)
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Pastes into this terminal, and into the workspace's other terminals while the
+    /// terminal panel broadcasts input.
+    fn paste_confirmed(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        for terminal in broadcast_targets(&self.workspace, &self.terminal, cx) {
+            terminal.update(cx, |terminal, _| terminal.paste(text));
+        }
+        self.terminal.update(cx, |terminal, _| terminal.paste(text));
+    }
+
+    fn toggle_copy_mode(&mut self, _: &ToggleCopyMode, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.toggle_copy_mode());
+        cx.notify();
+    }
+
+    fn copy_mode_move(&mut self, action: &CopyModeMove, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_mode_move(action.0));
+        cx.notify();
+    }
+
+    fn copy_mode_toggle_selection(
+        &mut self,
+        _: &CopyModeToggleSelection,
+        cx: &mut ViewContext<Self>,
+    ) {
         self.terminal.update(cx, |term, _| {
-            term.input(text.0.to_string());
+            term.copy_mode_toggle_selection(SelectionType::Simple)
         });
+        cx.notify();
+    }
+
+    fn copy_mode_toggle_line_selection(
+        &mut self,
+        _: &CopyModeToggleLineSelection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal.update(cx, |term, _| {
+            term.copy_mode_toggle_selection(SelectionType::Lines)
+        });
+        cx.notify();
+    }
+
+    fn copy_mode_yank(&mut self, _: &CopyModeYank, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.copy_mode_yank());
+        cx.notify();
+    }
+
+    /// Closes the terminal if its shell has exited and it was only held open to show
+    /// the exit status.
+    fn close_if_exited(&mut self, cx: &mut ViewContext<Self>) -> bool {
+        let has_exited = self.terminal.read(cx).has_exited();
+        if has_exited {
+            cx.emit(ItemEvent::CloseItem);
+        }
+        has_exited
+    }
+
+    fn send_text(&mut self, text: &SendText, cx: &mut ViewContext<Self>) {
+        if self.close_if_exited(cx) {
+            return;
+        }
+        self.clear_bell(cx);
+        self.input(text.0.to_string(), cx);
+    }
+
+    fn send_escaped_text(&mut self, text: &SendEscapedText, cx: &mut ViewContext<Self>) {
+        if self.close_if_exited(cx) {
+            return;
+        }
+        self.clear_bell(cx);
+        self.input(interpret_escapes(&text.0), cx);
+    }
+
+    /// Writes input to this terminal, and to the workspace's other terminals while the
+    /// terminal panel broadcasts input.
+    fn input(&mut self, input: String, cx: &mut ViewContext<Self>) {
+        for terminal in broadcast_targets(&self.workspace, &self.terminal, cx) {
+            terminal.update(cx, |terminal, _| terminal.input(input.clone()));
+        }
+        self.terminal
+            .update(cx, |terminal, _| terminal.input(input));
     }
 
     fn send_keystroke(&mut self, text: &SendKeystroke, cx: &mut ViewContext<Self>) {
+        if self.close_if_exited(cx) {
+            return;
+        }
         if let Some(keystroke) = Keystroke::parse(&text.0).log_err() {
             self.clear_bell(cx);
-            self.terminal.update(cx, |term, cx| {
-                term.try_keystroke(&keystroke, TerminalSettings::get_global(cx).option_as_meta);
-            });
+            self.try_keystroke(&keystroke, cx);
+        }
+    }
+
+    /// Sends a keystroke to this terminal, and to the workspace's other terminals while
+    /// the terminal panel broadcasts input. Returns whether the terminal handled it.
+    fn try_keystroke(&mut self, keystroke: &Keystroke, cx: &mut ViewContext<Self>) -> bool {
+        let option_as_meta = TerminalSettings::get_global(cx).option_as_meta;
+        let handled = self
+            .terminal
+            .update(cx, |term, _| term.try_keystroke(keystroke, option_as_meta));
+        if handled {
+            for terminal in broadcast_targets(&self.workspace, &self.terminal, cx) {
+                terminal.update(cx, |term, _| term.try_keystroke(keystroke, option_as_meta));
+            }
         }
+        handled
     }
 
-    fn dispatch_context(&self, cx: &AppContext) -> KeyContext {
+    fn dispatch_context(&self, cx: &ViewContext<Self>) -> KeyContext {
         let mut dispatch_context = KeyContext::new_with_defaults();
         dispatch_context.add("Terminal");
+        match self.placement {
+            TerminalPlacement::Center => {}
+            TerminalPlacement::Docked => dispatch_context.add("DockedTerminal"),
+        }
 
         let mode = self.terminal.read(cx).last_content.mode;
         dispatch_context.set(
@@ -537,6 +1406,16 @@ impl TerminalView {
             },
         );
 
+        if self.is_passing_through(cx) {
+            dispatch_context.add("pass_through");
+        }
+        if self.terminal.read(cx).replay_progress().is_some() {
+            dispatch_context.add("replay");
+        }
+        if self.terminal.read(cx).read_only() {
+            dispatch_context.add("read_only");
+        }
+
         if mode.contains(TermMode::APP_CURSOR) {
             dispatch_context.add("DECCKM");
         }
@@ -598,9 +1477,23 @@ impl TerminalView {
         dispatch_context
     }
 
+    pub fn placement(&self) -> TerminalPlacement {
+        self.placement
+    }
+
+    /// Records where the view is shown. Panes update it as terminals are added or
+    /// moved between them.
+    pub fn set_placement(&mut self, placement: TerminalPlacement, cx: &mut ViewContext<Self>) {
+        if self.placement != placement {
+            self.placement = placement;
+            cx.notify();
+        }
+    }
+
     fn set_terminal(&mut self, terminal: Model<Terminal>, cx: &mut ViewContext<'_, TerminalView>) {
         self._terminal_subscriptions =
             subscribe_for_terminal_events(&terminal, self.workspace.clone(), cx);
+        self.terminal_title = terminal.read(cx).title(false);
         self.terminal = terminal;
     }
 }
@@ -614,26 +1507,49 @@ fn subscribe_for_terminal_events(
     let terminal_events_subscription =
         cx.subscribe(terminal, move |this, _, event, cx| match event {
             Event::Wakeup => {
+                this.scrollback_changed = true;
                 cx.notify();
                 cx.emit(Event::Wakeup);
                 cx.emit(ItemEvent::UpdateTab);
                 cx.emit(SearchEvent::MatchesInvalidated);
             }
 
-            Event::Bell => {
-                this.has_bell = true;
-                cx.emit(Event::Wakeup);
+            Event::Bell => match TerminalSettings::get_global(cx).bell {
+                TerminalBell::Audible => cx.beep(),
+                TerminalBell::Visual => this.flash_bell(cx),
+                TerminalBell::Tab | TerminalBell::None => {}
+            },
+
+            Event::ActivityChanged(terminal_id) => {
+                cx.emit(ItemEvent::UpdateTab);
+                cx.emit(Event::ActivityChanged(*terminal_id));
+                cx.notify();
             }
 
             Event::BlinkChanged => this.blinking_on = !this.blinking_on,
 
-            Event::TitleChanged => {
+            Event::TitleChanged(title) => {
+                this.terminal_title.clone_from(title);
+                this.update_pass_through_program(cx);
                 cx.emit(ItemEvent::UpdateTab);
             }
 
             Event::NewNavigationTarget(maybe_navigation_target) => {
-                this.can_navigate_to_selected_word = match maybe_navigation_target {
-                    Some(MaybeNavigationTarget::Url(_)) => true,
+                this.resolve_link_target = Task::ready(());
+                this.link_target = match maybe_navigation_target {
+                    Some(MaybeNavigationTarget::Url(url)) => Some(url.clone().into()),
+                    Some(MaybeNavigationTarget::Commit(commit)) => {
+                        let permalink = commit_permalink(&workspace, commit, cx);
+                        this.resolve_link_target = cx.spawn(|this, mut cx| async move {
+                            let permalink = permalink.await;
+                            this.update(&mut cx, |this, cx| {
+                                this.link_target = permalink.map(Into::into);
+                                cx.notify();
+                            })
+                            .ok();
+                        });
+                        None
+                    }
                     Some(MaybeNavigationTarget::PathLike(path_like_target)) => {
                         if let Ok(fs) = workspace.update(cx, |workspace, cx| {
                             workspace.project().read(cx).fs().clone()
@@ -645,20 +1561,34 @@ fn subscribe_for_terminal_events(
                                 &path_like_target.maybe_path,
                                 cx,
                             );
-                            smol::block_on(valid_files_to_open_task).len() > 0
+                            smol::block_on(valid_files_to_open_task)
+                                .first()
+                                .map(|(path, _)| {
+                                    path.to_string(|path| path.display().to_string()).into()
+                                })
                         } else {
-                            false
+                            None
                         }
                     }
-                    None => false,
+                    None => None,
                 }
             }
 
             Event::Open(maybe_navigation_target) => match maybe_navigation_target {
                 MaybeNavigationTarget::Url(url) => cx.open_url(url),
 
+                MaybeNavigationTarget::Commit(commit) => {
+                    let permalink = commit_permalink(&workspace, commit, cx);
+                    cx.spawn(|_, mut cx| async move {
+                        if let Some(permalink) = permalink.await {
+                            cx.update(|cx| cx.open_url(&permalink)).ok();
+                        }
+                    })
+                    .detach();
+                }
+
                 MaybeNavigationTarget::PathLike(path_like_target) => {
-                    if !this.can_navigate_to_selected_word {
+                    if this.link_target.is_none() {
                         return;
                     }
                     let task_workspace = workspace.clone();
@@ -753,6 +1683,9 @@ fn subscribe_for_terminal_events(
                 cx.invalidate_character_coordinates();
                 cx.emit(SearchEvent::ActiveMatchChanged)
             }
+            Event::PasteRequested(text) => this.paste_text(text.clone(), cx),
+            // Only emitted for subscribers that asked for text changes.
+            Event::TextChanged(_) | Event::HoveredCellChanged(_) => {}
         });
     vec![terminal_subscription, terminal_events_subscription]
 }
@@ -849,6 +1782,52 @@ fn possible_open_targets(
     possible_open_paths_metadata(fs, row, column, canonicalized_paths, cx)
 }
 
+/// The hosting provider's page for a commit, when its SHA resolves in the repository
+/// the terminal is in, or else in the repository of the first worktree.
+/// Builds the URL of a commit on its repository's hosting provider. Looking the
+/// commit up reads the repository, so it happens on the background executor.
+fn commit_permalink(
+    workspace: &WeakView<Workspace>,
+    commit: &CommitTarget,
+    cx: &mut ViewContext<TerminalView>,
+) -> Task<Option<String>> {
+    const REMOTE_NAME: &str = "origin";
+    let provider_registry = GitHostingProviderRegistry::default_global(cx);
+    let Some(workspace) = workspace.upgrade() else {
+        return Task::ready(None);
+    };
+    let project = workspace.read(cx).project().read(cx);
+    let repo = commit
+        .terminal_dir
+        .as_ref()
+        .and_then(|terminal_dir| {
+            project.worktrees(cx).find_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let path = terminal_dir.strip_prefix(worktree.abs_path()).ok()?;
+                worktree.as_local()?.local_git_repo(path)
+            })
+        })
+        .or_else(|| project.get_first_worktree_root_repo(cx));
+    let Some(repo) = repo else {
+        return Task::ready(None);
+    };
+
+    let sha = commit.sha.clone();
+    cx.background_executor().spawn(async move {
+        let sha = repo.resolve_commit(&sha)?;
+        let remote_url = repo.remote_url(REMOTE_NAME)?;
+        let (provider, remote) = parse_git_remote_url(provider_registry, &remote_url)?;
+        let permalink =
+            provider.build_commit_permalink(&remote, BuildCommitPermalinkParams { sha: &sha });
+        Some(permalink.to_string())
+    })
+}
+
+fn format_replay_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
 fn regex_to_literal(regex: &str) -> String {
     regex
         .chars()
@@ -863,11 +1842,18 @@ fn regex_to_literal(regex: &str) -> String {
 }
 
 pub fn regex_search_for_query(query: &project::search::SearchQuery) -> Option<RegexSearch> {
-    let query = query.as_str();
-    if query == "." {
+    let pattern = query.as_str();
+    if pattern == "." {
         return None;
     }
-    let searcher = RegexSearch::new(&query);
+    // Alacritty picks case sensitivity from the pattern itself, so pin it to the
+    // search option with an inline flag.
+    let case_flag = if query.case_sensitive() {
+        "(?-i)"
+    } else {
+        "(?i)"
+    };
+    let searcher = RegexSearch::new(&format!("{case_flag}{pattern}"));
     searcher.ok()
 }
 
@@ -876,27 +1862,36 @@ impl TerminalView {
         self.clear_bell(cx);
         self.pause_cursor_blinking(cx);
 
-        self.terminal.update(cx, |term, cx| {
-            let handled = term.try_keystroke(
-                &event.keystroke,
-                TerminalSettings::get_global(cx).option_as_meta,
-            );
-            if handled {
-                cx.stop_propagation();
-            }
-        });
+        if self.close_if_exited(cx) {
+            cx.stop_propagation();
+            return;
+        }
+
+        // Keystrokes without a copy mode binding are dropped rather than sent to the shell,
+        // as is everything typed into a read-only terminal.
+        let terminal = self.terminal.read(cx);
+        let drop_keystrokes = terminal.copy_mode_active() || terminal.read_only();
+        if drop_keystrokes {
+            cx.stop_propagation();
+            return;
+        }
+
+        if self.try_keystroke(&event.keystroke, cx) {
+            cx.stop_propagation();
+        }
     }
 
     fn focus_in(&mut self, cx: &mut ViewContext<Self>) {
-        self.terminal.read(cx).focus_in();
+        self.terminal
+            .update(cx, |terminal, cx| terminal.focus_in(cx));
         self.blink_cursors(self.blink_epoch, cx);
         cx.invalidate_character_coordinates();
         cx.notify();
     }
 
     fn focus_out(&mut self, cx: &mut ViewContext<Self>) {
-        self.terminal.update(cx, |terminal, _| {
-            terminal.focus_out();
+        self.terminal.update(cx, |terminal, cx| {
+            terminal.focus_out(cx);
         });
         cx.notify();
     }
@@ -908,25 +1903,55 @@ impl Render for TerminalView {
         let terminal_view_handle = cx.view().clone();
 
         let focused = self.focus_handle.is_focused(cx);
+        let copy_mode = self.terminal.read(cx).copy_mode_active();
 
         div()
             .size_full()
             .relative()
-            .track_focus(&self.focus_handle)
             .key_context(self.dispatch_context(cx))
             .on_action(cx.listener(TerminalView::send_text))
             .on_action(cx.listener(TerminalView::send_keystroke))
+            .on_action(cx.listener(TerminalView::send_escaped_text))
             .on_action(cx.listener(TerminalView::copy))
+            .on_action(cx.listener(TerminalView::copy_with_formatting))
+            .on_action(cx.listener(TerminalView::copy_scrollback_to_buffer))
+            .on_action(cx.listener(TerminalView::inspect_terminal))
+            .on_action(cx.listener(TerminalView::start_recording))
+            .on_action(cx.listener(TerminalView::stop_recording))
+            .on_action(cx.listener(TerminalView::toggle_replay_playback))
+            .on_action(cx.listener(TerminalView::replay_seek_backward))
+            .on_action(cx.listener(TerminalView::replay_seek_forward))
+            .on_action(cx.listener(TerminalView::increase_font_size))
+            .on_action(cx.listener(TerminalView::decrease_font_size))
+            .on_action(cx.listener(TerminalView::reset_font_size))
             .on_action(cx.listener(TerminalView::paste))
             .on_action(cx.listener(TerminalView::clear))
+            .on_action(cx.listener(TerminalView::clear_screen))
+            .on_action(cx.listener(TerminalView::clear_scrollback))
             .on_action(cx.listener(TerminalView::scroll_line_up))
             .on_action(cx.listener(TerminalView::scroll_line_down))
             .on_action(cx.listener(TerminalView::scroll_page_up))
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_command))
+            .on_action(cx.listener(TerminalView::scroll_to_next_command))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::select_last_command_output))
+            .on_action(cx.listener(TerminalView::toggle_command_output))
+            .on_action(cx.listener(TerminalView::expand_all_command_output))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
+            .on_action(cx.listener(TerminalView::rename))
+            .on_action(cx.listener(TerminalView::rerun_command))
+            .on_action(cx.listener(TerminalView::toggle_pass_through))
+            .on_action(cx.listener(TerminalView::toggle_read_only))
+            .on_action(cx.listener(TerminalView::toggle_copy_mode))
+            .on_action(cx.listener(TerminalView::copy_mode_move))
+            .on_action(cx.listener(TerminalView::copy_mode_toggle_selection))
+            .on_action(cx.listener(TerminalView::copy_mode_toggle_line_selection))
+            .on_action(cx.listener(TerminalView::copy_mode_yank))
             .on_key_down(cx.listener(Self::key_down))
             .on_mouse_down(
                 MouseButton::Right,
@@ -939,16 +1964,39 @@ impl Render for TerminalView {
             )
             .child(
                 // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
-                div().size_full().child(TerminalElement::new(
-                    terminal_handle,
-                    terminal_view_handle,
-                    self.workspace.clone(),
-                    self.focus_handle.clone(),
-                    focused,
-                    self.should_show_cursor(focused, cx),
-                    self.can_navigate_to_selected_word,
-                    self.block_below_cursor.clone(),
-                )),
+                div()
+                    .size_full()
+                    .track_focus(&self.focus_handle)
+                    // Copy mode's context sits below `Terminal`, so its bindings take
+                    // precedence over the terminal's, and input actions stop here.
+                    .when(copy_mode, |div| {
+                        div.key_context("TerminalCopyMode")
+                            .on_action(|_: &SendText, _| {})
+                            .on_action(|_: &SendEscapedText, _| {})
+                            .on_action(|_: &SendKeystroke, _| {})
+                    })
+                    .child(TerminalElement::new(
+                        terminal_handle,
+                        terminal_view_handle,
+                        self.workspace.clone(),
+                        self.focus_handle.clone(),
+                        focused,
+                        self.should_show_cursor(focused, cx),
+                        self.link_target.clone(),
+                        self.block_below_cursor.clone(),
+                        self.line_layouts.clone(),
+                    )),
+            )
+            .when(self.bell_flash.is_some(), |this| {
+                let flash = Hsla {
+                    a: 0.1,
+                    ..cx.theme().colors().text
+                };
+                this.child(div().absolute().size_full().top_0().left_0().bg(flash))
+            })
+            .when_some(
+                self.terminal.read(cx).replay_progress(),
+                |this, progress| this.child(self.render_replay_controls(progress, cx)),
             )
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 deferred(
@@ -965,13 +2013,13 @@ impl Render for TerminalView {
 impl Item for TerminalView {
     type Event = ItemEvent;
 
-    fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
-        Some(self.terminal().read(cx).title(false).into())
+    fn tab_tooltip_text(&self, _: &AppContext) -> Option<SharedString> {
+        Some(self.title(false).into())
     }
 
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
+        let title = self.title(true);
         let terminal = self.terminal().read(cx);
-        let title = terminal.title(true);
         let rerun_button = |task_id: task::TaskId| {
             IconButton::new("rerun-icon", IconName::Rerun)
                 .icon_size(IconSize::Small)
@@ -1004,7 +2052,19 @@ impl Item for TerminalView {
                     }
                 }
             },
-            None => (IconName::Terminal, Color::Muted, None),
+            None => {
+                let icon = if terminal.remote_destination().is_some() {
+                    IconName::Server
+                } else {
+                    IconName::Terminal
+                };
+                let color = if is_broadcasting(&self.workspace, cx) {
+                    Color::Warning
+                } else {
+                    Color::Muted
+                };
+                (icon, color, None)
+            }
         };
 
         h_flex()
@@ -1030,6 +2090,24 @@ impl Item for TerminalView {
                     }),
             )
             .child(Label::new(title).color(params.text_color()))
+            .when(terminal.read_only(), |this| {
+                this.child(
+                    Icon::new(IconName::FileLock)
+                        .size(IconSize::XSmall)
+                        .color(Color::Muted),
+                )
+            })
+            .when(
+                terminal.has_bell() || terminal.has_unread_output(),
+                |this| {
+                    let color = if terminal.has_bell() {
+                        Color::Warning
+                    } else {
+                        Color::Info
+                    };
+                    this.child(Indicator::dot().color(color))
+                },
+            )
             .into_any()
     }
 
@@ -1059,7 +2137,7 @@ impl Item for TerminalView {
     fn is_dirty(&self, cx: &gpui::AppContext) -> bool {
         match self.terminal.read(cx).task() {
             Some(task) => task.status == TaskStatus::Running,
-            None => self.has_bell(),
+            None => self.has_bell(cx),
         }
     }
 
@@ -1067,6 +2145,16 @@ impl Item for TerminalView {
         false
     }
 
+    fn close_confirmation(&mut self, cx: &mut ViewContext<Self>) -> Option<String> {
+        let process = self
+            .terminal
+            .update(cx, |terminal, _| terminal.foreground_process_info())?;
+        Some(format!(
+            "`{}` is still running. Close the terminal anyway?",
+            process.name
+        ))
+    }
+
     fn as_searchable(&self, handle: &View<Self>) -> Option<Box<dyn SearchableItemHandle>> {
         Some(Box::new(handle.clone()))
     }
@@ -1088,6 +2176,8 @@ impl Item for TerminalView {
     }
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
+        // The terminal panel marks its own terminals as docked once they are added.
+        self.set_placement(TerminalPlacement::Center, cx);
         if self.terminal().read(cx).task().is_none() {
             if let Some((new_id, old_id)) = workspace.database_id().zip(self.workspace_id) {
                 cx.background_executor()
@@ -1120,23 +2210,36 @@ impl SerializableItem for TerminalView {
         &mut self,
         _workspace: &mut Workspace,
         item_id: workspace::ItemId,
-        _closing: bool,
+        closing: bool,
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<gpui::Result<()>>> {
+        let workspace_id = self.workspace_id?;
         let terminal = self.terminal().read(cx);
-        if terminal.task().is_some() {
+        // Replays have no shell to restore, and their output isn't the user's.
+        if terminal.task().is_some() || terminal.replay_progress().is_some() {
             return None;
         }
 
-        if let Some((cwd, workspace_id)) = terminal.get_cwd().zip(self.workspace_id) {
-            Some(cx.background_executor().spawn(async move {
-                TERMINAL_DB
-                    .save_working_directory(item_id, workspace_id, cwd)
-                    .await
-            }))
+        let cwd = terminal.get_cwd();
+        let title = self.custom_title.clone();
+        // Don't lose the output produced since the last checkpoint when the workspace goes away.
+        let save_scrollback = if closing {
+            self.save_scrollback(cx)
         } else {
             None
-        }
+        };
+        Some(cx.background_executor().spawn(async move {
+            if let Some(cwd) = cwd {
+                TERMINAL_DB
+                    .save_working_directory(item_id, workspace_id, cwd)
+                    .await?;
+            }
+            TERMINAL_DB.save_title(item_id, workspace_id, title).await?;
+            if let Some(save_scrollback) = save_scrollback {
+                save_scrollback.await?;
+            }
+            Ok(())
+        }))
     }
 
     fn should_serialize(&self, event: &Self::Event) -> bool {
@@ -1171,12 +2274,32 @@ impl SerializableItem for TerminalView {
                 })
                 .ok()
                 .flatten();
+            let scrollback = TERMINAL_DB
+                .get_scrollback(item_id, workspace_id)
+                .log_err()
+                .flatten()
+                .filter(|scrollback| !scrollback.is_empty());
+            let title = TERMINAL_DB
+                .get_title(item_id, workspace_id)
+                .log_err()
+                .flatten();
 
-            let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(TerminalKind::Shell(cwd), window, cx)
-            })??;
+            let terminal = project
+                .update(&mut cx, |project, cx| {
+                    project.create_terminal_with_scrollback(
+                        TerminalKind::Shell(cwd),
+                        scrollback,
+                        window,
+                        cx,
+                    )
+                })?
+                .await?;
             pane.update(&mut cx, |_, cx| {
-                cx.new_view(|cx| TerminalView::new(terminal, workspace, Some(workspace_id), cx))
+                cx.new_view(|cx| {
+                    let mut view = TerminalView::new(terminal, workspace, Some(workspace_id), cx);
+                    view.custom_title = title;
+                    view
+                })
             })
         })
     }
@@ -1187,7 +2310,7 @@ impl SearchableItem for TerminalView {
 
     fn supported_options() -> SearchOptions {
         SearchOptions {
-            case: false,
+            case: true,
             word: false,
             regex: true,
             replacement: false,
@@ -1304,7 +2427,21 @@ impl SearchableItem for TerminalView {
 ///Gets the working directory for the given workspace, respecting the user's settings.
 /// None implies "~" on whichever machine we end up on.
 pub fn default_working_directory(workspace: &Workspace, cx: &AppContext) -> Option<PathBuf> {
-    match &TerminalSettings::get_global(cx).working_directory {
+    working_directory(
+        &TerminalSettings::get_global(cx).working_directory,
+        workspace,
+        cx,
+    )
+}
+
+/// Resolves a working directory strategy to a directory, where `None` means the
+/// user's home directory.
+pub fn working_directory(
+    strategy: &WorkingDirectory,
+    workspace: &Workspace,
+    cx: &AppContext,
+) -> Option<PathBuf> {
+    match strategy {
         WorkingDirectory::CurrentProjectDirectory => {
             workspace.project().read(cx).active_project_directory(cx)
         }
@@ -1327,6 +2464,81 @@ fn first_project_directory(workspace: &Workspace, cx: &AppContext) -> Option<Pat
     Some(worktree.abs_path().to_path_buf())
 }
 
+/// Whether the terminal panel broadcasts input. This is the state the panel keeps,
+/// so it's cheap enough to check when rendering, unlike [`broadcast_targets`].
+fn is_broadcasting(workspace: &WeakView<Workspace>, cx: &AppContext) -> bool {
+    workspace.upgrade().map_or(false, |workspace| {
+        workspace
+            .read(cx)
+            .panel::<TerminalPanel>(cx)
+            .map_or(false, |panel| panel.read(cx).broadcast_input())
+    })
+}
+
+/// The workspace's other terminals that input to `terminal` is mirrored to, which are
+/// none unless the terminal panel broadcasts input.
+pub(crate) fn broadcast_targets(
+    workspace: &WeakView<Workspace>,
+    terminal: &Model<Terminal>,
+    cx: &AppContext,
+) -> Vec<Model<Terminal>> {
+    if !is_broadcasting(workspace, cx) {
+        return Vec::new();
+    }
+    let Some(workspace) = workspace.upgrade() else {
+        return Vec::new();
+    };
+    workspace
+        .read(cx)
+        .project()
+        .read(cx)
+        .local_terminal_handles()
+        .iter()
+        .filter_map(|handle| handle.upgrade())
+        .filter(|other| other != terminal && other.read(cx).task().is_none())
+        .collect()
+}
+
+/// Replaces the escapes that [`SendEscapedText`] supports. Unknown escapes are kept as
+/// they are.
+fn interpret_escapes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('e') => result.push('\x1b'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('a') => result.push('\x07'),
+            Some('b') => result.push('\x08'),
+            Some('0') => result.push('\0'),
+            Some('\\') => result.push('\\'),
+            Some('x') => {
+                let hex = chars.clone().skip(1).take(2).collect::<String>();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 && byte.is_ascii() => {
+                        result.push(byte as char);
+                        chars.nth(2);
+                    }
+                    _ => result.push('\\'),
+                }
+                continue;
+            }
+            _ => {
+                result.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1335,6 +2547,15 @@ mod tests {
     use std::path::Path;
     use workspace::AppState;
 
+    #[test]
+    fn test_interpret_escapes() {
+        assert_eq!(interpret_escapes(r"ls -la\n"), "ls -la\n");
+        assert_eq!(interpret_escapes(r"\e[A\r"), "\x1b[A\r");
+        assert_eq!(interpret_escapes(r"\x03\x1B"), "\x03\x1b");
+        assert_eq!(interpret_escapes(r"a\\nb"), "a\\nb");
+        assert_eq!(interpret_escapes(r"\q \x4 \xff\"), r"\q \x4 \xff\");
+    }
+
     // Working directory calculation tests
 
     // No Worktrees in project -> home_dir()
@@ -12,7 +12,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::SettingsStore;
 use std::sync::Arc;
-use ui::{h_flex, ContextMenu, IconButton, Tooltip};
+use ui::{h_flex, ContextMenu, IconButton, Indicator, Tooltip};
 use ui::{prelude::*, right_click_menu};
 
 const RESIZE_HANDLE_SIZE: Pixels = Pixels(6.);
@@ -39,6 +39,10 @@ pub trait Panel: FocusableView + EventEmitter<PanelEvent> {
     fn icon_label(&self, _: &WindowContext) -> Option<String> {
         None
     }
+    /// The color of a dot drawn over the panel's button, to point out activity in it.
+    fn icon_indicator(&self, _: &WindowContext) -> Option<Color> {
+        None
+    }
     fn is_zoomed(&self, _cx: &WindowContext) -> bool {
         false
     }
@@ -72,6 +76,7 @@ pub trait PanelHandle: Send + Sync {
     fn icon_tooltip(&self, cx: &WindowContext) -> Option<&'static str>;
     fn toggle_action(&self, cx: &WindowContext) -> Box<dyn Action>;
     fn icon_label(&self, cx: &WindowContext) -> Option<String>;
+    fn icon_indicator(&self, cx: &WindowContext) -> Option<Color>;
     fn focus_handle(&self, cx: &AppContext) -> FocusHandle;
     fn to_any(&self) -> AnyView;
 }
@@ -144,6 +149,10 @@ where
         self.read(cx).icon_label(cx)
     }
 
+    fn icon_indicator(&self, cx: &WindowContext) -> Option<Color> {
+        self.read(cx).icon_indicator(cx)
+    }
+
     fn to_any(&self) -> AnyView {
         self.clone().into()
     }
@@ -509,6 +518,10 @@ impl Dock {
         self.panel_entries.len()
     }
 
+    pub fn panels(&self) -> impl Iterator<Item = &Arc<dyn PanelHandle>> {
+        self.panel_entries.iter().map(|entry| &entry.panel)
+    }
+
     pub fn activate_panel(&mut self, panel_ix: usize, cx: &mut ViewContext<Self>) {
         if panel_ix != self.active_panel_index {
             if let Some(active_panel) = self.panel_entries.get(self.active_panel_index) {
@@ -718,6 +731,7 @@ impl Render for PanelButtons {
             .filter_map(|(i, entry)| {
                 let icon = entry.panel.icon(cx)?;
                 let icon_tooltip = entry.panel.icon_tooltip(cx)?;
+                let indicator = entry.panel.icon_indicator(cx);
                 let name = entry.panel.persistent_name();
                 let panel = entry.panel.clone();
 
@@ -765,15 +779,28 @@ impl Render for PanelButtons {
                         .anchor(menu_anchor)
                         .attach(menu_attach)
                         .trigger(
-                            IconButton::new(name, icon)
-                                .icon_size(IconSize::Small)
-                                .selected(is_active_button)
-                                .on_click({
-                                    let action = action.boxed_clone();
-                                    move |_, cx| cx.dispatch_action(action.boxed_clone())
-                                })
-                                .tooltip(move |cx| {
-                                    Tooltip::for_action(tooltip.clone(), &*action, cx)
+                            div()
+                                .relative()
+                                .child(
+                                    IconButton::new(name, icon)
+                                        .icon_size(IconSize::Small)
+                                        .selected(is_active_button)
+                                        .on_click({
+                                            let action = action.boxed_clone();
+                                            move |_, cx| cx.dispatch_action(action.boxed_clone())
+                                        })
+                                        .tooltip(move |cx| {
+                                            Tooltip::for_action(tooltip.clone(), &*action, cx)
+                                        }),
+                                )
+                                .when_some(indicator, |this, color| {
+                                    this.child(
+                                        div()
+                                            .absolute()
+                                            .top_0p5()
+                                            .right_0p5()
+                                            .child(Indicator::dot().color(color)),
+                                    )
                                 }),
                         ),
                 )
@@ -230,6 +230,11 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
     fn can_save(&self, _cx: &AppContext) -> bool {
         false
     }
+    /// The question to ask before closing the item, when closing it would lose
+    /// something that saving can't keep, like a program running in it.
+    fn close_confirmation(&mut self, _cx: &mut ViewContext<Self>) -> Option<String> {
+        None
+    }
     fn save(
         &mut self,
         _format: bool,
@@ -403,6 +408,7 @@ pub trait ItemHandle: 'static + Send {
     fn is_dirty(&self, cx: &AppContext) -> bool;
     fn has_conflict(&self, cx: &AppContext) -> bool;
     fn can_save(&self, cx: &AppContext) -> bool;
+    fn close_confirmation(&self, cx: &mut WindowContext) -> Option<String>;
     fn save(
         &self,
         format: bool,
@@ -773,6 +779,10 @@ impl<T: Item> ItemHandle for View<T> {
         self.read(cx).can_save(cx)
     }
 
+    fn close_confirmation(&self, cx: &mut WindowContext) -> Option<String> {
+        self.update(cx, |item, cx| item.close_confirmation(cx))
+    }
+
     fn save(
         &self,
         format: bool,
@@ -1045,6 +1055,7 @@ pub mod test {
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
+        pub close_confirmation: Option<String>,
         pub project_items: Vec<Model<TestProjectItem>>,
         pub nav_history: Option<ItemNavHistory>,
         pub tab_descriptions: Option<Vec<&'static str>>,
@@ -1106,6 +1117,7 @@ pub mod test {
                 reload_count: 0,
                 is_dirty: false,
                 has_conflict: false,
+                close_confirmation: None,
                 project_items: Vec::new(),
                 is_singleton: true,
                 nav_history: None,
@@ -1143,6 +1155,11 @@ pub mod test {
             self
         }
 
+        pub fn with_close_confirmation(mut self, confirmation: &str) -> Self {
+            self.close_confirmation = Some(confirmation.to_string());
+            self
+        }
+
         pub fn with_project_items(mut self, items: &[Model<TestProjectItem>]) -> Self {
             self.project_items.clear();
             self.project_items.extend(items.iter().cloned());
@@ -1259,6 +1276,7 @@ pub mod test {
                 is_dirty: self.is_dirty,
                 is_singleton: self.is_singleton,
                 has_conflict: self.has_conflict,
+                close_confirmation: self.close_confirmation.clone(),
                 project_items: self.project_items.clone(),
                 nav_history: None,
                 tab_descriptions: None,
@@ -1277,6 +1295,10 @@ pub mod test {
             self.has_conflict
         }
 
+        fn close_confirmation(&mut self, _: &mut ViewContext<Self>) -> Option<String> {
+            self.close_confirmation.clone()
+        }
+
         fn can_save(&self, cx: &AppContext) -> bool {
             !self.project_items.is_empty()
                 && self
@@ -1271,6 +1271,10 @@ impl Pane {
                     continue;
                 };
 
+                if !Self::confirm_close_item(&pane, item_ix, &*item, &mut cx).await? {
+                    break;
+                }
+
                 // Check if this view has any project items that are not open anywhere else
                 // in the workspace, AND that the user has not already been prompted to save.
                 // If there are any such project entries, prompt the user to save this item.
@@ -1439,6 +1443,25 @@ impl Pane {
         cx.notify();
     }
 
+    /// Asks whether to close an item that wants confirmation first, returning whether
+    /// it should be closed.
+    async fn confirm_close_item(
+        pane: &WeakView<Pane>,
+        item_ix: usize,
+        item: &dyn ItemHandle,
+        cx: &mut AsyncWindowContext,
+    ) -> Result<bool> {
+        let answer = pane.update(cx, |pane, cx| {
+            let message = item.close_confirmation(cx)?;
+            pane.activate_item(item_ix, true, true, cx);
+            Some(cx.prompt(PromptLevel::Warning, &message, None, &["Close", "Cancel"]))
+        })?;
+        match answer {
+            Some(answer) => Ok(answer.await.ok() == Some(0)),
+            None => Ok(true),
+        }
+    }
+
     pub async fn save_item(
         project: Model<Project>,
         pane: &WeakView<Pane>,
@@ -3210,6 +3233,49 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_close_item_with_confirmation(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        add_labeled_item(&pane, "A", false, cx);
+        pane.update(cx, |pane, cx| {
+            let item = Box::new(cx.new_view(|cx| {
+                TestItem::new(cx)
+                    .with_label("B")
+                    .with_close_confirmation("B is busy")
+            }));
+            pane.add_item(item, false, false, None, cx);
+        });
+        assert_item_labels(&pane, ["A", "B*"], cx);
+
+        // Cancelling keeps the item open.
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_all_items(&CloseAllItems { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(1);
+        close.await.unwrap();
+        assert_item_labels(&pane, ["B*"], cx);
+
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_all_items(&CloseAllItems { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(0);
+        close.await.unwrap();
+        assert_item_labels(&pane, [], cx);
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
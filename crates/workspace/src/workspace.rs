@@ -1693,6 +1693,13 @@ impl Workspace {
                 }
             }
 
+            let answer = this.update(&mut cx, |this, cx| this.confirm_close_all_items(cx))?;
+            if let Some(answer) = answer {
+                if answer.await.log_err() != Some(0) {
+                    return anyhow::Ok(false);
+                }
+            }
+
             let save_result = this
                 .update(&mut cx, |this, cx| {
                     this.save_all_internal(SaveIntent::Close, cx)
@@ -1713,6 +1720,46 @@ impl Workspace {
         })
     }
 
+    /// Asks whether to close the window when some of its items, in the center or in
+    /// panels, want confirmation before they are closed.
+    fn confirm_close_all_items(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<oneshot::Receiver<usize>> {
+        let mut panes = self.panes.clone();
+        for dock in [&self.left_dock, &self.bottom_dock, &self.right_dock] {
+            let dock = dock.read(cx);
+            panes.extend(dock.panels().filter_map(|panel| panel.pane(cx)));
+        }
+        let items = panes
+            .iter()
+            .flat_map(|pane| pane.read(cx).items().cloned().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let confirmations = items
+            .iter()
+            .filter_map(|item| item.close_confirmation(cx))
+            .collect::<Vec<_>>();
+
+        match confirmations.as_slice() {
+            [] => None,
+            [confirmation] => Some(cx.prompt(
+                PromptLevel::Warning,
+                confirmation,
+                None,
+                &["Close", "Cancel"],
+            )),
+            _ => Some(cx.prompt(
+                PromptLevel::Warning,
+                &format!(
+                    "{} items are still busy. Close anyway?",
+                    confirmations.len()
+                ),
+                Some(&confirmations.join("\n")),
+                &["Close", "Cancel"],
+            )),
+        }
+    }
+
     fn save_all(&mut self, action: &SaveAll, cx: &mut ViewContext<Self>) {
         self.save_all_internal(action.save_intent.unwrap_or(SaveIntent::SaveAll), cx)
             .detach_and_log_err(cx);
@@ -813,6 +813,7 @@ fn reload_keymaps(cx: &mut AppContext, keymap_content: &KeymapFile) {
     cx.clear_key_bindings();
     load_default_keymap(cx);
     keymap_content.clone().add_to_cx(cx).log_err();
+    terminal_view::bind_pass_through_keys(cx);
     cx.set_menus(app_menus());
     cx.set_dock_menu(vec![MenuItem::action("New Window", workspace::NewWindow)])
 }